@@ -0,0 +1,49 @@
+//! Command line tools for RMD module description files.
+//!
+//! Usage: `rmd lint <file.rmd>...`
+//! Prints the problems found in every file and exits with status 1 if any file has errors.
+
+use std::process::ExitCode;
+
+use tinymesh_cc_tool::mk_module_description::MkRmdSeverity;
+use tinymesh_cc_tool::rmd_lint::lint_rmd;
+
+const USAGE: &str = "Usage: rmd lint <file.rmd>...";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 || args[0] != "lint" {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+    let mut has_errors = false;
+    for file_path in &args[1..] {
+        let input = match std::fs::read_to_string(file_path) {
+            Ok(input) => input,
+            Err(err) => {
+                eprintln!("{}: {}", file_path, err);
+                has_errors = true;
+                continue;
+            }
+        };
+        let diagnostics = lint_rmd(&input);
+        for diagnostic in &diagnostics {
+            println!("{}: {}", file_path, diagnostic);
+        }
+        let errors = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == MkRmdSeverity::Error)
+            .count();
+        println!(
+            "{}: {} errors, {} warnings",
+            file_path,
+            errors,
+            diagnostics.len() - errors
+        );
+        has_errors |= errors > 0;
+    }
+    if has_errors {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
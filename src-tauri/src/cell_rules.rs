@@ -0,0 +1,64 @@
+//! This module evaluates the rules of the RMD file that relate cells to each other:
+//! cells that are only used by the module when other cells have certain values (`ACTIVE_IF`),
+//! and combinations of values that the module does not accept (`REQUIRE`).
+
+use crate::cell_validation::CellValidationError;
+use crate::data_types::{MkCellCondition, MkDeviceCell};
+
+/// This function checks a condition against the current values of the cells.
+/// A condition on a cell that is not part of `cells` does not hold.
+pub fn condition_holds(condition: &MkCellCondition, cells: &[MkDeviceCell]) -> bool {
+    cells
+        .iter()
+        .find(|cell| cell.address == condition.address)
+        .is_some_and(|cell| condition.holds_for(cell.current_value))
+}
+
+/// This function finds the cells that are not used by the module with the current values of the cells.
+///
+/// # Arguments
+/// * `cells` - The cells with the current values
+///
+/// # Returns
+/// The addresses of the cells whose `ACTIVE_IF` conditions don't all hold.
+pub fn find_inactive_cells(cells: &[MkDeviceCell]) -> Vec<usize> {
+    cells
+        .iter()
+        .filter(|cell| {
+            !cell
+                .active_if
+                .iter()
+                .all(|condition| condition_holds(condition, cells))
+        })
+        .map(|cell| cell.address)
+        .collect()
+}
+
+/// This function checks the current values of the cells against the `REQUIRE` rules of the cells.
+///
+/// # Arguments
+/// * `cells` - The cells with the current values
+///
+/// # Returns
+/// A `CellValidationError::RuleViolated` for every rule that applies but doesn't hold, ordered by address.
+pub fn check_cell_rules(cells: &[MkDeviceCell]) -> Vec<CellValidationError> {
+    let mut result = vec![];
+    for cell in cells {
+        for rule in &cell.rules {
+            let applies = rule.when.iter().all(|condition| condition_holds(condition, cells));
+            if applies
+                && !rule
+                    .conditions
+                    .iter()
+                    .all(|condition| condition_holds(condition, cells))
+            {
+                result.push(CellValidationError::RuleViolated {
+                    address: cell.address,
+                    name: cell.name.clone(),
+                    rule: rule.to_string(),
+                });
+            }
+        }
+    }
+    return result;
+}
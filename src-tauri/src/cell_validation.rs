@@ -0,0 +1,192 @@
+//! This module contains the validation of cell values against the constraints of the RMD file.
+//! Every write to the configuration or calibration memory is validated before it is sent,
+//! so a buggy frontend or script can never write a locked cell or a value the module does not accept.
+
+use std::fmt;
+
+use crate::cell_rules::check_cell_rules;
+use crate::data_types::MkDeviceCell;
+
+/// A reason for rejecting the requested value of a cell.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "kind")]
+pub enum CellValidationError {
+    /// The cell is locked in the RMD file and must not be written.
+    Locked { address: usize, name: String },
+    /// The value is outside the `MIN_MAX` range of the cell.
+    OutOfRange {
+        address: usize,
+        name: String,
+        value: u8,
+        min_value: u8,
+        max_value: u8,
+    },
+    /// The value is not one of the `ALLOW` values of the cell.
+    NotAllowed {
+        address: usize,
+        name: String,
+        value: u8,
+        allowed_values: Vec<u8>,
+    },
+    /// The RMD file gives the cell neither a `MIN_MAX` range nor `ALLOW` values,
+    /// so no value is known to be accepted by the module.
+    NotWritable { address: usize, name: String },
+    /// The cell does not match any cell of the module description.
+    UnknownCell { address: usize },
+    /// The values of the cells break a `REQUIRE` rule of the cell.
+    RuleViolated {
+        address: usize,
+        name: String,
+        rule: String,
+    },
+}
+
+impl fmt::Display for CellValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellValidationError::Locked { address, name } => {
+                write!(f, "Cell 0x{:02X} ({}) is locked", address, name)
+            }
+            CellValidationError::OutOfRange {
+                address,
+                name,
+                value,
+                min_value,
+                max_value,
+            } => write!(
+                f,
+                "Value {} of cell 0x{:02X} ({}) is outside {}..={}",
+                value, address, name, min_value, max_value
+            ),
+            CellValidationError::NotAllowed {
+                address,
+                name,
+                value,
+                allowed_values,
+            } => write!(
+                f,
+                "Value {} of cell 0x{:02X} ({}) is not one of {:?}",
+                value, address, name, allowed_values
+            ),
+            CellValidationError::NotWritable { address, name } => write!(
+                f,
+                "Cell 0x{:02X} ({}) has no MIN_MAX range or ALLOW values and cannot be written",
+                address, name
+            ),
+            CellValidationError::UnknownCell { address } => {
+                write!(f, "Cell 0x{:02X} is not part of the module description", address)
+            }
+            CellValidationError::RuleViolated {
+                address,
+                name,
+                rule,
+            } => write!(f, "Cell 0x{:02X} ({}) requires {}", address, name, rule),
+        }
+    }
+}
+
+impl std::error::Error for CellValidationError {}
+
+/// This function checks a value against the constraints of a cell.
+/// Cells with `ALLOW` values only accept those values. Otherwise the `MIN_MAX` range applies.
+/// A cell without either (both bounds are 0) is not writable.
+///
+/// # Arguments
+/// * `cell` - The cell as described by the RMD file
+/// * `value` - The value to write
+///
+/// # Returns
+/// An `Ok(())` if the value is accepted, or the `CellValidationError`.
+pub fn validate_cell_value(cell: &MkDeviceCell, value: u8) -> Result<(), CellValidationError> {
+    if !cell.allowed_values.is_empty() {
+        if cell.allowed_values.contains(&value) {
+            return Ok(());
+        }
+        return Err(CellValidationError::NotAllowed {
+            address: cell.address,
+            name: cell.name.clone(),
+            value,
+            allowed_values: cell.allowed_values.clone(),
+        });
+    }
+    if cell.min_value == 0 && cell.max_value == 0 {
+        return Err(CellValidationError::NotWritable {
+            address: cell.address,
+            name: cell.name.clone(),
+        });
+    }
+    if value < cell.min_value || value > cell.max_value {
+        return Err(CellValidationError::OutOfRange {
+            address: cell.address,
+            name: cell.name.clone(),
+            value,
+            min_value: cell.min_value,
+            max_value: cell.max_value,
+        });
+    }
+    Ok(())
+}
+
+/// This function validates the cells that are about to be written.
+/// The constraints are taken from `previous_cells`, which were decoded by the backend,
+/// and never from the requested cells, which come from the frontend.
+/// Besides the constraints of every changed cell, the `REQUIRE` rules broken by the change are reported.
+///
+/// # Arguments
+/// * `previous_cells` - The cells last read from the device
+/// * `requested_cells` - The cells with the requested values, matched to `previous_cells` by address
+/// * `locked_cells` - The addresses of the locked cells
+///
+/// # Returns
+/// An `Ok(())` if every changed cell may be written, or all the `CellValidationError`s found.
+pub fn validate_cell_changes(
+    previous_cells: &[MkDeviceCell],
+    requested_cells: &[MkDeviceCell],
+    locked_cells: &[usize],
+) -> Result<(), Vec<CellValidationError>> {
+    let mut errors = vec![];
+    for requested in requested_cells {
+        let previous = previous_cells
+            .iter()
+            .find(|previous| previous.address == requested.address);
+        let previous = match previous {
+            Some(previous) => previous,
+            None => {
+                errors.push(CellValidationError::UnknownCell {
+                    address: requested.address,
+                });
+                continue;
+            }
+        };
+        if previous.current_value == requested.current_value {
+            continue;
+        }
+        if locked_cells.contains(&previous.address) {
+            errors.push(CellValidationError::Locked {
+                address: previous.address,
+                name: previous.name.clone(),
+            });
+            continue;
+        }
+        if let Err(err) = validate_cell_value(previous, requested.current_value) {
+            errors.push(err);
+        }
+    }
+
+    // the rules are taken from the previous cells too, and only the violations caused by the change are reported,
+    // so a device that already breaks a rule can still be written
+    let mut requested_values = previous_cells.to_vec();
+    for (cell, requested) in requested_values.iter_mut().zip(requested_cells) {
+        cell.current_value = requested.current_value;
+    }
+    let previous_violations = check_cell_rules(previous_cells);
+    for violation in check_cell_rules(&requested_values) {
+        if !previous_violations.contains(&violation) {
+            errors.push(violation);
+        }
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(errors)
+}
@@ -0,0 +1,150 @@
+//! This module contains the multi-byte and bit-packed values of the configuration memory.
+//! The RMD file declares them on the cells holding their bytes (`SIZE`, `ENDIAN` and `BITS`),
+//! and they are read and written through the `current_value` of those cells, so a changed value is sent
+//! to the device as the `M` address/value pairs of the bytes that changed.
+
+use crate::data_types::{MkBitsValue, MkDeviceCell, MkTypedValue};
+
+/// This function decodes the multi-byte and bit-packed values of the cells.
+///
+/// # Arguments
+/// * `cells` - The cells with the current values of the device
+///
+/// # Returns
+/// One `MkTypedValue` per cell with a `SIZE` or `BITS` section, ordered by address.
+/// Values whose bytes are not all part of `cells` are left out.
+pub fn decode_typed_values(cells: &[MkDeviceCell]) -> Vec<MkTypedValue> {
+    let mut result = vec![];
+    for cell in cells {
+        if let Some(multi_byte) = &cell.multi_byte {
+            let bytes: Option<Vec<u8>> = (0..multi_byte.size)
+                .map(|offset| {
+                    find_cell(cells, cell.address + offset).map(|cell| cell.current_value)
+                })
+                .collect();
+            if let Some(bytes) = bytes {
+                result.push(MkTypedValue {
+                    address: cell.address,
+                    name: cell.name.clone(),
+                    size: multi_byte.size,
+                    value: multi_byte.decode(&bytes),
+                    bits: vec![],
+                });
+            }
+        } else if !cell.bits.is_empty() {
+            result.push(MkTypedValue {
+                address: cell.address,
+                name: cell.name.clone(),
+                size: 1,
+                value: u32::from(cell.current_value),
+                bits: cell
+                    .bits
+                    .iter()
+                    .map(|bits| MkBitsValue {
+                        name: bits.name.clone(),
+                        first_bit: bits.first_bit,
+                        last_bit: bits.last_bit,
+                        value: bits.extract(cell.current_value),
+                    })
+                    .collect(),
+            });
+        }
+    }
+    result.sort_by_key(|value| value.address);
+    return result;
+}
+
+/// This function sets a multi-byte value, or the named bits of an option byte, in the cells holding it.
+///
+/// # Arguments
+/// * `cells` - The cells to change
+/// * `address` - The first address of the value
+/// * `bits_name` - The name of the bits to set, or `None` to set the whole value
+/// * `value` - The new value
+///
+/// # Returns
+/// The address/value pairs of the bytes that changed, or a `String` if the cell has no such value
+/// or the value doesn't fit in it.
+pub fn set_typed_value(
+    cells: &mut [MkDeviceCell],
+    address: usize,
+    bits_name: Option<&str>,
+    value: u32,
+) -> Result<Vec<(usize, u8)>, String> {
+    let cell = find_cell(cells, address).ok_or(format!("Cell 0x{:02X} does not exist", address))?;
+    let bytes = match (bits_name, &cell.multi_byte) {
+        (Some(bits_name), _) => {
+            let bits = cell
+                .bits
+                .iter()
+                .find(|bits| bits.name == bits_name)
+                .ok_or(format!(
+                    "Cell 0x{:02X} has no bits named '{}'",
+                    address, bits_name
+                ))?;
+            let byte = u8::try_from(value)
+                .ok()
+                .and_then(|value| bits.insert(cell.current_value, value))
+                .ok_or(format!(
+                    "{} does not fit in bits {}-{} of cell 0x{:02X}",
+                    value, bits.first_bit, bits.last_bit, address
+                ))?;
+            vec![byte]
+        }
+        (None, Some(multi_byte)) => multi_byte.encode(value).ok_or(format!(
+            "{} does not fit in the {} bytes of cell 0x{:02X}",
+            value, multi_byte.size, address
+        ))?,
+        (None, None) => vec![u8::try_from(value)
+            .map_err(|_| format!("{} does not fit in cell 0x{:02X}", value, address))?],
+    };
+    if (0..bytes.len()).any(|offset| find_cell(cells, address + offset).is_none()) {
+        return Err(format!(
+            "Cell 0x{:02X} spans addresses beyond the configuration memory",
+            address
+        ));
+    }
+
+    let mut changes = vec![];
+    for (offset, byte) in bytes.into_iter().enumerate() {
+        if let Some(cell) = cells
+            .iter_mut()
+            .find(|cell| cell.address == address + offset)
+        {
+            if cell.current_value != byte {
+                cell.current_value = byte;
+                changes.push((cell.address, byte));
+            }
+        }
+    }
+    return Ok(changes);
+}
+
+/// This function sets a cell, or the multi-byte value starting at it, to a value entered by the user
+/// in engineering units (e.g. `5 s` or `-10 dBm`, see `MkDeviceCell::parse_engineering`).
+///
+/// # Arguments
+/// * `cells` - The cells to change
+/// * `address` - The address of the cell
+/// * `input` - The engineering value, with or without its unit
+///
+/// # Returns
+/// The address/value pairs of the bytes that changed, or a `String` if the value is not accepted.
+pub fn set_engineering_value(
+    cells: &mut [MkDeviceCell],
+    address: usize,
+    input: &str,
+) -> Result<Vec<(usize, u8)>, String> {
+    let raw = find_cell(cells, address)
+        .ok_or(format!("Cell 0x{:02X} does not exist", address))?
+        .parse_engineering(input)?;
+    set_typed_value(cells, address, None, raw)
+}
+
+fn find_cell(cells: &[MkDeviceCell], address: usize) -> Option<&MkDeviceCell> {
+    // the cells are usually indexed by their address, so the search only falls back to a scan
+    cells
+        .get(address)
+        .filter(|cell| cell.address == address)
+        .or_else(|| cells.iter().find(|cell| cell.address == address))
+}
@@ -0,0 +1,235 @@
+//! This module compares two configurations cell by cell, e.g. two modules, a module and a saved profile,
+//! or a module and the defaults of its RMD file, and exports the differences for change reviews.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::cell_rules::find_inactive_cells;
+use crate::cell_values::decode_typed_values;
+use crate::config_profile::MkConfigProfile;
+use crate::data_types::{MkDeviceCell, MkDeviceConfig};
+
+/// The configuration on one side of a diff
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind")]
+pub enum MkConfigSource {
+    /// The configuration last read from the connected device
+    Device,
+    /// A configuration profile saved with `export_config_profile`
+    Profile { file_path: String },
+    /// The `DEF` values of the RMD file of the connected device
+    Defaults,
+}
+
+/// A cell, or a value spanning several cells, that differs between two configurations
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct MkCellDiff {
+    /// The first address of the value
+    pub address: usize,
+    pub name: String,
+    /// The number of addresses holding the value
+    pub size: usize,
+    /// The raw value, `None` if the configuration has no cell at the address
+    pub old_value: Option<u32>,
+    pub new_value: Option<u32>,
+    /// The label or engineering value with its unit (see `MkDeviceCell::format_value`)
+    pub old_engineering_value: Option<String>,
+    pub new_engineering_value: Option<String>,
+    /// True if the cell is locked in the RMD file
+    pub locked: bool,
+}
+
+/// The differences between two configurations, ordered by address
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct MkConfigDiff {
+    /// Describes the old configuration, e.g. `RF TM4070 (HW 1.00, FW 1.53)`
+    pub old_name: String,
+    pub new_name: String,
+    pub cells: Vec<MkCellDiff>,
+}
+
+impl MkConfigDiff {
+    /// Returns the pretty-printed JSON representation of the diff.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|err| err.to_string())
+    }
+
+    /// Writes the diff to a file, as JSON if the file name ends with `.json` and as text otherwise.
+    pub fn save(&self, file_path: &Path) -> Result<(), String> {
+        let is_json = file_path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let content = if is_json {
+            self.to_json()?
+        } else {
+            self.to_string()
+        };
+        std::fs::write(file_path, content).map_err(|err| err.to_string())
+    }
+}
+
+impl fmt::Display for MkConfigDiff {
+    /// Formats the diff as text, one line per cell, e.g.
+    /// `0x01 RF Power: 5 (11 dBm) -> 2 (-10 dBm)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "--- {}", self.old_name)?;
+        writeln!(f, "+++ {}", self.new_name)?;
+        if self.cells.is_empty() {
+            return writeln!(f, "No differences");
+        }
+        for cell in &self.cells {
+            let address = if cell.size > 1 {
+                format!(
+                    "0x{:02X}-0x{:02X}",
+                    cell.address,
+                    cell.address + cell.size - 1
+                )
+            } else {
+                format!("0x{:02X}", cell.address)
+            };
+            writeln!(
+                f,
+                "{} {}: {} -> {}{}",
+                address,
+                cell.name,
+                format_side(cell.old_value, &cell.old_engineering_value),
+                format_side(cell.new_value, &cell.new_engineering_value),
+                if cell.locked { " [locked]" } else { "" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats one side of a cell diff, e.g. `5 (11 dBm)`, or `-` if the configuration has no value.
+fn format_side(value: Option<u32>, engineering_value: &Option<String>) -> String {
+    match (value, engineering_value) {
+        (Some(value), Some(engineering_value)) if *engineering_value != value.to_string() => {
+            format!("{} ({})", value, engineering_value)
+        }
+        (Some(value), _) => value.to_string(),
+        (None, _) => "-".to_string(),
+    }
+}
+
+/// This function compares two configurations by address.
+/// The bytes of a multi-byte value are compared as one value, reported at its first address.
+/// The cell names, units and lock status are taken from `new`, or from `old` for cells `new` doesn't have.
+///
+/// # Arguments
+/// * `old` - The configuration before the change
+/// * `new` - The configuration after the change
+/// * `names` - The names of the configurations, shown in the diff
+///
+/// # Returns
+/// The differences, ordered by address.
+pub fn diff_device_configs(
+    old: &MkDeviceConfig,
+    new: &MkDeviceConfig,
+    (old_name, new_name): (String, String),
+) -> MkConfigDiff {
+    let mut addresses: Vec<usize> = old
+        .cells
+        .iter()
+        .chain(&new.cells)
+        .map(|cell| cell.address)
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+
+    let mut cells = vec![];
+    // the addresses up to which the bytes belong to a multi-byte value already compared
+    let mut covered_until = 0;
+    for address in addresses {
+        if address < covered_until {
+            continue;
+        }
+        let (old_cell, new_cell) = (find_cell(old, address), find_cell(new, address));
+        let definition = match new_cell.or(old_cell) {
+            Some(definition) => definition,
+            None => continue,
+        };
+        let size = definition
+            .multi_byte
+            .map(|multi_byte| multi_byte.size)
+            .unwrap_or(1);
+        covered_until = address + size;
+        let old_value = read_value(old, definition, size);
+        let new_value = read_value(new, definition, size);
+        if old_value == new_value {
+            continue;
+        }
+        cells.push(MkCellDiff {
+            address,
+            name: definition.name.clone(),
+            size,
+            old_value,
+            new_value,
+            old_engineering_value: old_value.map(|value| definition.format_value(value)),
+            new_engineering_value: new_value.map(|value| definition.format_value(value)),
+            locked: is_locked(new, address)
+                .or(is_locked(old, address))
+                .unwrap_or_default(),
+        });
+    }
+    MkConfigDiff {
+        old_name,
+        new_name,
+        cells,
+    }
+}
+
+/// This function builds the configuration of a side of a diff.
+///
+/// # Arguments
+/// * `source` - The configuration to build
+/// * `device_config` - The configuration read from the connected device, whose cell definitions are used
+///
+/// # Returns
+/// The configuration and its name, or a `String` if the profile can't be read or was made for another model.
+pub fn resolve_config_source(
+    source: &MkConfigSource,
+    device_config: &MkDeviceConfig,
+) -> Result<(MkDeviceConfig, String), String> {
+    let device_name = format!(
+        "{} (HW {}, FW {})",
+        device_config.model, device_config.hw_version, device_config.firmware_version
+    );
+    match source {
+        MkConfigSource::Device => Ok((device_config.clone(), device_name)),
+        MkConfigSource::Profile { file_path } => {
+            let profile = MkConfigProfile::load(Path::new(file_path))?;
+            let config = profile.to_device_config(device_config)?;
+            Ok((config, file_path.clone()))
+        }
+        MkConfigSource::Defaults => {
+            let mut config = device_config.clone();
+            for cell in config.cells.iter_mut() {
+                cell.current_value = cell.default_value;
+            }
+            config.typed_values = decode_typed_values(&config.cells);
+            config.inactive_cells = find_inactive_cells(&config.cells);
+            Ok((config, format!("RMD defaults of {}", device_config.model)))
+        }
+    }
+}
+
+fn find_cell(config: &MkDeviceConfig, address: usize) -> Option<&MkDeviceCell> {
+    config.cells.iter().find(|cell| cell.address == address)
+}
+
+/// Reads the value of `size` bytes starting at the address of `definition`, `None` if a byte is missing.
+fn read_value(config: &MkDeviceConfig, definition: &MkDeviceCell, size: usize) -> Option<u32> {
+    let bytes: Vec<u8> = (0..size)
+        .map(|offset| find_cell(config, definition.address + offset).map(|cell| cell.current_value))
+        .collect::<Option<Vec<u8>>>()?;
+    match &definition.multi_byte {
+        Some(multi_byte) => Some(multi_byte.decode(&bytes)),
+        None => Some(u32::from(bytes[0])),
+    }
+}
+
+/// Returns the lock status of the address in the configuration, if it has a cell at the address.
+fn is_locked(config: &MkDeviceConfig, address: usize) -> Option<bool> {
+    find_cell(config, address).map(|_| config.locked_cells.contains(&address))
+}
@@ -0,0 +1,178 @@
+//! This module contains configuration profiles, i.e. backups of a module's configuration stored as JSON files.
+//! A profile can be imported back into a module of the same model; only the editable cells that
+//! differ from the connected module are written.
+
+use std::path::Path;
+
+use crate::cell_rules::find_inactive_cells;
+use crate::cell_values::decode_typed_values;
+use crate::data_types::{MkDeviceCell, MkDeviceConfig};
+
+/// The version written into new profiles. Profiles with a newer version are rejected.
+pub const CONFIG_PROFILE_VERSION: u32 = 1;
+
+/// A single cell stored in a configuration profile.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MkProfileCell {
+    pub address: usize,
+    pub name: String,
+    pub value: u8,
+}
+
+/// A configuration profile, as stored on disk.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MkConfigProfile {
+    pub version: u32,
+    pub model: String,
+    pub hw_version: String,
+    pub firmware_version: String,
+    pub cells: Vec<MkProfileCell>,
+}
+
+impl MkConfigProfile {
+    /// Creates a profile from the configuration read from a device.
+    pub fn from_device_config(device_config: &MkDeviceConfig) -> MkConfigProfile {
+        MkConfigProfile {
+            version: CONFIG_PROFILE_VERSION,
+            model: device_config.model.clone(),
+            hw_version: device_config.hw_version.clone(),
+            firmware_version: device_config.firmware_version.clone(),
+            cells: device_config
+                .cells
+                .iter()
+                .map(|cell| MkProfileCell {
+                    address: cell.address,
+                    name: cell.name.clone(),
+                    value: cell.current_value,
+                })
+                .collect(),
+        }
+    }
+
+    /// Parses a profile from its JSON representation.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON string of the profile
+    ///
+    /// # Returns
+    /// A `Result` containing the profile, or a `String` if the JSON is invalid or the version is not supported.
+    pub fn from_json(json: &str) -> Result<MkConfigProfile, String> {
+        let profile: MkConfigProfile =
+            serde_json::from_str(json).map_err(|err| format!("Invalid profile: {}", err))?;
+        if profile.version > CONFIG_PROFILE_VERSION {
+            return Err(format!(
+                "Profile version {} is not supported (latest is {})",
+                profile.version, CONFIG_PROFILE_VERSION
+            ));
+        }
+        Ok(profile)
+    }
+
+    /// Returns the pretty-printed JSON representation of the profile.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|err| err.to_string())
+    }
+
+    /// Reads a profile from a JSON file.
+    pub fn load(file_path: &Path) -> Result<MkConfigProfile, String> {
+        let json = std::fs::read_to_string(file_path).map_err(|err| err.to_string())?;
+        MkConfigProfile::from_json(&json)
+    }
+
+    /// Writes the profile to a JSON file.
+    pub fn save(&self, file_path: &Path) -> Result<(), String> {
+        std::fs::write(file_path, self.to_json()?).map_err(|err| err.to_string())
+    }
+
+    /// Returns a copy of the profile without the cells at the given addresses,
+    /// e.g. to leave the per-unit cells of a module untouched.
+    pub fn without_cells(&self, addresses: &[usize]) -> MkConfigProfile {
+        MkConfigProfile {
+            cells: self
+                .cells
+                .iter()
+                .filter(|cell| !addresses.contains(&cell.address))
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// This function builds the configuration stored in the profile, with the cell definitions of
+    /// the configuration read from the connected device, e.g. to compare the profile with the device.
+    /// Unlike `apply_to`, every cell of the profile is used, including the locked cells.
+    ///
+    /// # Arguments
+    /// * `device_config` - The configuration read from the connected device
+    ///
+    /// # Returns
+    /// The configuration of the profile, or a `String` if the profile was made for another model.
+    pub fn to_device_config(
+        &self,
+        device_config: &MkDeviceConfig,
+    ) -> Result<MkDeviceConfig, String> {
+        if self.model != device_config.model {
+            return Err(format!(
+                "The profile is for {}, but the connected device is {}",
+                self.model, device_config.model
+            ));
+        }
+        let mut config = MkDeviceConfig {
+            hw_version: self.hw_version.clone(),
+            firmware_version: self.firmware_version.clone(),
+            ..device_config.clone()
+        };
+        for profile_cell in &self.cells {
+            if let Some(cell) = config
+                .cells
+                .iter_mut()
+                .find(|cell| cell.address == profile_cell.address)
+            {
+                cell.current_value = profile_cell.value;
+            }
+        }
+        config.typed_values = decode_typed_values(&config.cells);
+        config.inactive_cells = find_inactive_cells(&config.cells);
+        Ok(config)
+    }
+
+    /// This function applies the profile to the configuration read from the connected device.
+    /// Cells that are not editable (see `is_cell_editable`) keep the value of the device.
+    ///
+    /// # Arguments
+    /// * `device_config` - The configuration read from the connected device
+    ///
+    /// # Returns
+    /// The cells of `device_config` with the values of the profile, ready to be passed to `set_device_config_on_device`,
+    /// or a `String` if the profile was made for another model.
+    pub fn apply_to(&self, device_config: &MkDeviceConfig) -> Result<Vec<MkDeviceCell>, String> {
+        if self.model != device_config.model {
+            return Err(format!(
+                "The profile is for {}, but the connected device is {}",
+                self.model, device_config.model
+            ));
+        }
+        let mut cells = device_config.cells.clone();
+        for profile_cell in &self.cells {
+            if !is_cell_editable(device_config, profile_cell.address) {
+                continue;
+            }
+            if let Some(cell) = cells
+                .iter_mut()
+                .find(|cell| cell.address == profile_cell.address)
+            {
+                cell.current_value = profile_cell.value;
+            }
+        }
+        Ok(cells)
+    }
+}
+
+/// Returns true if the cell at `address` may be written, i.e. it is not locked
+/// and it is listed in the editable cells (if the RMD file has such a list).
+pub fn is_cell_editable(device_config: &MkDeviceConfig, address: usize) -> bool {
+    if device_config.locked_cells.contains(&address) {
+        return false;
+    }
+    device_config.editable_cells.is_empty() || device_config.editable_cells.contains(&address)
+}
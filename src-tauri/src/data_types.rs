@@ -1,14 +1,19 @@
 //! Data types used in the app backend
 
+use std::fmt;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use serialport::SerialPort;
 use tauri::async_runtime::JoinHandle;
 
+use crate::cell_validation::CellValidationError;
+use crate::port_arbiter::SharedPort;
+use crate::rmd_registry::RmdRegistry;
+
 /// Data type for the testmode sequence.
 /// Conceptually, both testmode and quickmode sequence datatypes are the same.
 /// Both are used for the same purpose, but segregated in the frontend.
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
 pub struct MkDeviceTestMode {
     pub testmode_id: usize,
     pub name: String,
@@ -20,7 +25,7 @@ pub struct MkDeviceTestMode {
 /// Data type for the quickmode sequence.
 /// Conceptually, both testmode and quickmode sequence datatypes are the same.
 /// Both are used for the same purpose, but segregated in the frontend.
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
 pub struct MkDeviceQuickMode {
     pub testmode_id: usize,
     pub name: String,
@@ -30,7 +35,7 @@ pub struct MkDeviceQuickMode {
 }
 
 /// Data type for the device configuration cell
-#[derive(Clone, Default, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct MkDeviceCell {
     pub address: usize,
     pub name: String,
@@ -40,12 +45,394 @@ pub struct MkDeviceCell {
     pub allowed_values: Vec<u8>,
     pub default_value: u8,
     pub current_value: u8,
+    /// The frequency of the channel in MHz, only set for the RF Channel cell
+    #[serde(default)]
+    pub frequency_mhz: Option<f64>,
+    /// Set on the first cell of a value spanning several addresses (`SIZE` and `ENDIAN`)
+    #[serde(default)]
+    pub multi_byte: Option<MkMultiByte>,
+    /// The named bits of a bit-packed option byte (`BITS`)
+    #[serde(default)]
+    pub bits: Vec<MkCellBits>,
+    /// The meaning of the values of the cell (`ENUM`, or the `N : label` table of the `HINT`)
+    #[serde(default)]
+    pub enum_values: Vec<MkCellEnumValue>,
+    /// The conversion of the raw value to an engineering value (`SCALE`, `OFFSET` and `UNIT`)
+    #[serde(default)]
+    pub scaling: Option<MkCellScaling>,
+    /// The conditions under which the cell is used by the module, all of them must hold (`ACTIVE_IF`)
+    #[serde(default)]
+    pub active_if: Vec<MkCellCondition>,
+    /// The rules the values of the cells must follow (`REQUIRE`)
+    #[serde(default)]
+    pub rules: Vec<MkCellRule>,
+}
+
+/// The comparison of a `MkCellCondition`
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum MkConditionOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl MkConditionOperator {
+    /// The operators in their RMD spelling
+    pub const ALL: [(&'static str, MkConditionOperator); 6] = [
+        ("==", MkConditionOperator::Equal),
+        ("!=", MkConditionOperator::NotEqual),
+        ("<", MkConditionOperator::Less),
+        ("<=", MkConditionOperator::LessOrEqual),
+        (">", MkConditionOperator::Greater),
+        (">=", MkConditionOperator::GreaterOrEqual),
+    ];
+}
+
+impl fmt::Display for MkConditionOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (spelling, _) = MkConditionOperator::ALL
+            .iter()
+            .find(|(_, operator)| operator == self)
+            .expect("every operator has a spelling");
+        write!(f, "{}", spelling)
+    }
+}
+
+/// A comparison of the value of a cell of the same memory with a constant, e.g. `0x0E == 2`
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MkCellCondition {
+    pub address: usize,
+    pub operator: MkConditionOperator,
+    pub value: u8,
+}
+
+impl MkCellCondition {
+    /// Returns true if the condition holds for the value of its cell.
+    pub fn holds_for(&self, value: u8) -> bool {
+        match self.operator {
+            MkConditionOperator::Equal => value == self.value,
+            MkConditionOperator::NotEqual => value != self.value,
+            MkConditionOperator::Less => value < self.value,
+            MkConditionOperator::LessOrEqual => value <= self.value,
+            MkConditionOperator::Greater => value > self.value,
+            MkConditionOperator::GreaterOrEqual => value >= self.value,
+        }
+    }
+}
+
+impl fmt::Display for MkCellCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:02X} {} {}", self.address, self.operator, self.value)
+    }
+}
+
+/// A rule that the values of the cells must follow: if all the `when` conditions hold,
+/// all the `conditions` must hold too. A rule without `when` conditions always applies.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MkCellRule {
+    pub conditions: Vec<MkCellCondition>,
+    pub when: Vec<MkCellCondition>,
+}
+
+impl fmt::Display for MkCellRule {
+    /// Formats the rule in its RMD spelling, e.g. `0x0E != 3 IF 0x5D == 1`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |conditions: &[MkCellCondition]| {
+            conditions
+                .iter()
+                .map(|condition| condition.to_string())
+                .collect::<Vec<String>>()
+                .join(" AND ")
+        };
+        write!(f, "{}", join(&self.conditions))?;
+        if !self.when.is_empty() {
+            write!(f, " IF {}", join(&self.when))?;
+        }
+        Ok(())
+    }
+}
+
+/// The conversion of a raw cell value to an engineering value: `raw * scale + offset`, in `unit`
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MkCellScaling {
+    pub scale: f64,
+    pub offset: f64,
+    pub unit: Option<String>,
+}
+
+impl Default for MkCellScaling {
+    /// The raw value itself, for cells with a `UNIT` but no `SCALE` or `OFFSET`
+    fn default() -> Self {
+        MkCellScaling {
+            scale: 1.0,
+            offset: 0.0,
+            unit: None,
+        }
+    }
+}
+
+/// The label of a cell value, e.g. `-20` `dBm` for the value 1 of RF Power
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MkCellEnumValue {
+    pub value: u8,
+    pub label: String,
+    pub unit: Option<String>,
+}
+
+impl MkDeviceCell {
+    /// Returns the label of the value, followed by its unit, if the cell has one for the value.
+    pub fn label_of(&self, value: u8) -> Option<String> {
+        let enum_value = self.enum_values.iter().find(|enum_value| enum_value.value == value)?;
+        match &enum_value.unit {
+            Some(unit) => Some(format!("{} {}", enum_value.label, unit)),
+            None => Some(enum_value.label.clone()),
+        }
+    }
+
+    /// Returns the unit of the engineering value, if the RMD file declares one.
+    pub fn unit(&self) -> Option<&str> {
+        self.scaling.as_ref()?.unit.as_deref()
+    }
+
+    /// Converts a raw value of the cell (or of the multi-byte value starting at the cell) to its engineering value.
+    /// Cells without `SCALE` and `OFFSET` return the raw value.
+    pub fn to_engineering(&self, raw: u32) -> f64 {
+        match &self.scaling {
+            Some(scaling) => f64::from(raw) * scaling.scale + scaling.offset,
+            None => f64::from(raw),
+        }
+    }
+
+    /// Converts an engineering value to the raw value of the cell.
+    ///
+    /// # Returns
+    /// The raw value, or a `String` if the value is not a whole number of steps or doesn't fit in the cell.
+    pub fn from_engineering(&self, value: f64) -> Result<u32, String> {
+        let scaling = self.scaling.clone().unwrap_or_default();
+        let raw = (value - scaling.offset) / scaling.scale;
+        let rounded = raw.round();
+        // allow for the rounding errors of decimal steps, e.g. 0.3 / 0.1
+        if (raw - rounded).abs() > 1e-6 {
+            return Err(format!(
+                "{} can't be set in cell 0x{:02X} ({}), its steps are {}",
+                self.format_engineering(value),
+                self.address,
+                self.name,
+                self.format_engineering(scaling.scale)
+            ));
+        }
+        let size = self.multi_byte.map(|multi_byte| multi_byte.size).unwrap_or(1);
+        let max_raw = if size >= 4 {
+            f64::from(u32::MAX)
+        } else {
+            f64::from((1u32 << (8 * size)) - 1)
+        };
+        if rounded < 0.0 || rounded > max_raw {
+            return Err(format!(
+                "{} is outside {}..={} for cell 0x{:02X} ({})",
+                self.format_engineering(value),
+                self.format_engineering(self.to_engineering(0)),
+                self.format_engineering(self.to_engineering(max_raw as u32)),
+                self.address,
+                self.name
+            ));
+        }
+        Ok(rounded as u32)
+    }
+
+    /// Formats an engineering value of the cell with its unit, e.g. `-10 dBm`.
+    pub fn format_engineering(&self, value: f64) -> String {
+        match self.unit() {
+            Some(unit) => format!("{} {}", format_engineering_number(value), unit),
+            None => format_engineering_number(value),
+        }
+    }
+
+    /// Formats a raw value of the cell for people: the label of the value, if the cell has one,
+    /// or else the engineering value with its unit.
+    pub fn format_value(&self, raw: u32) -> String {
+        u8::try_from(raw)
+            .ok()
+            .filter(|_| self.multi_byte.is_none())
+            .and_then(|raw| self.label_of(raw))
+            .unwrap_or_else(|| self.format_engineering(self.to_engineering(raw)))
+    }
+
+    /// Parses an engineering value entered by the user, e.g. `5 s`, `-10 dBm` or a label of the cell
+    /// like `Transparent mode`, and converts it to the raw value of the cell.
+    /// A unit, if given, must be the unit of the cell.
+    ///
+    /// # Returns
+    /// The raw value, or a `String` describing why the value is not accepted.
+    pub fn parse_engineering(&self, input: &str) -> Result<u32, String> {
+        let input = input.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if let Some(enum_value) = self.enum_values.iter().find(|enum_value| {
+            enum_value.label.eq_ignore_ascii_case(&input)
+                || self
+                    .label_of(enum_value.value)
+                    .is_some_and(|label| label.eq_ignore_ascii_case(&input))
+        }) {
+            return Ok(u32::from(enum_value.value));
+        }
+
+        let (number, unit) = match input.rsplit_once(' ') {
+            Some((number, unit)) if number.parse::<f64>().is_ok() => (number, Some(unit)),
+            _ => (input.as_str(), None),
+        };
+        let value = number.parse::<f64>().map_err(|_| {
+            format!(
+                "'{}' is not a value of cell 0x{:02X} ({})",
+                input, self.address, self.name
+            )
+        })?;
+        if let Some(unit) = unit {
+            if self.unit() != Some(unit) {
+                return Err(format!(
+                    "'{}' has the unit {}, cell 0x{:02X} ({}) is in {}",
+                    input,
+                    unit,
+                    self.address,
+                    self.name,
+                    self.unit().unwrap_or("raw values")
+                ));
+            }
+        }
+        self.from_engineering(value)
+    }
+}
+
+/// Formats a number without trailing zeros, rounded to 6 decimals to hide floating point errors.
+fn format_engineering_number(value: f64) -> String {
+    let formatted = format!("{:.6}", value);
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+    if formatted == "-0" {
+        return "0".to_string();
+    }
+    formatted.to_string()
+}
+
+/// The byte order of a value spanning several addresses
+#[derive(Clone, Copy, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum MkEndianness {
+    /// The first address holds the most significant byte, like the GPIO trigger levels of the TinyMesh modules
+    #[default]
+    Big,
+    /// The first address holds the least significant byte
+    Little,
+}
+
+/// A value spanning `size` consecutive addresses, declared on its first cell
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MkMultiByte {
+    /// The number of addresses, 2 to 4
+    pub size: usize,
+    pub endianness: MkEndianness,
+}
+
+impl MkMultiByte {
+    /// Combines the bytes of the value, starting with the byte at the first address.
+    pub fn decode(&self, bytes: &[u8]) -> u32 {
+        let mut bytes = bytes[..self.size.min(bytes.len())].to_vec();
+        if self.endianness == MkEndianness::Little {
+            bytes.reverse();
+        }
+        bytes
+            .iter()
+            .fold(0, |value, byte| (value << 8) | u32::from(*byte))
+    }
+
+    /// Splits the value into its bytes, starting with the byte of the first address.
+    /// Returns `None` if the value doesn't fit in `size` bytes.
+    pub fn encode(&self, value: u32) -> Option<Vec<u8>> {
+        if self.size < 4 && value >> (8 * self.size) != 0 {
+            return None;
+        }
+        let mut bytes = value.to_be_bytes()[4 - self.size.min(4)..].to_vec();
+        if self.endianness == MkEndianness::Little {
+            bytes.reverse();
+        }
+        Some(bytes)
+    }
+}
+
+/// A named bit, or range of bits, of an option byte
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MkCellBits {
+    pub name: String,
+    /// The lowest bit, 0 is the least significant bit
+    pub first_bit: u8,
+    /// The highest bit, equal to `first_bit` for a single bit
+    pub last_bit: u8,
+}
+
+impl MkCellBits {
+    /// The mask of the bits within the byte
+    pub fn mask(&self) -> u8 {
+        let width = self.last_bit - self.first_bit + 1;
+        (((1u16 << width) - 1) << self.first_bit) as u8
+    }
+
+    /// Reads the value of the bits from the byte, shifted down to bit 0.
+    pub fn extract(&self, byte: u8) -> u8 {
+        (byte & self.mask()) >> self.first_bit
+    }
+
+    /// Writes the value of the bits into the byte, leaving the other bits unchanged.
+    /// Returns `None` if the value doesn't fit in the bits.
+    pub fn insert(&self, byte: u8, value: u8) -> Option<u8> {
+        let shifted = u16::from(value) << self.first_bit;
+        if shifted & !u16::from(self.mask()) != 0 {
+            return None;
+        }
+        Some((byte & !self.mask()) | shifted as u8)
+    }
+}
+
+/// The value of a multi-byte or bit-packed cell, decoded from the bytes of the configuration memory
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct MkTypedValue {
+    /// The first address of the value
+    pub address: usize,
+    pub name: String,
+    /// The number of addresses holding the value
+    pub size: usize,
+    pub value: u32,
+    /// The values of the named bits, empty for values spanning several addresses
+    pub bits: Vec<MkBitsValue>,
+}
+
+/// The value of a named bit, or range of bits, of an option byte
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct MkBitsValue {
+    pub name: String,
+    pub first_bit: u8,
+    pub last_bit: u8,
+    pub value: u8,
+}
+
+/// The operating mode of the connected module, as far as the backend knows
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize)]
+pub enum DeviceMode {
+    /// The backend has not switched the mode itself (e.g. the CONFIG pin was pulled by hand).
+    /// The module is assumed to be at the configuration prompt, and the mode is never restored.
+    #[default]
+    Unknown,
+    /// Configuration mode, commands are answered with the `>` prompt
+    Config,
+    /// Transparent / packet mode, bytes are sent over the air
+    Transparent,
 }
 
 /// DeviceEntity contains the state of the program
 pub struct DeviceEntity {
-    /// The device serial port connection that can be shared across threads
-    pub port: Arc<Mutex<Option<Box<dyn SerialPort>>>>,
+    /// The device connection (a serial port in production) that can be shared across threads.
+    /// Request / response exchanges take it through `port_arbiter::lock_device`.
+    pub port: SharedPort,
+    /// The mode the connected device was last switched to
+    pub device_mode: Arc<Mutex<DeviceMode>>,
 
     /// Tokio tasks for streaming RSSI in spectrum analyzer mode and background communication
     pub rssi_task: Mutex<Option<JoinHandle<()>>>,
@@ -57,6 +444,8 @@ pub struct DeviceEntity {
     pub device_config: Arc<Mutex<Option<MkDeviceConfig>>>,
     /// Device calibration is stored inside the state of the program
     pub device_calib: Arc<Mutex<Option<MkDeviceCalib>>>,
+    /// The RMD files found in the module search directories
+    pub rmd_registry: Arc<Mutex<RmdRegistry>>,
 }
 
 /// EventPayload contains the data that is sent to the frontend logging panel
@@ -76,7 +465,73 @@ pub struct MkDeviceConfig {
     pub test_modes: Vec<MkDeviceTestMode>,
     pub quick_modes: Vec<MkDeviceQuickMode>,
     pub editable_cells: Vec<usize>,
-    pub locked_cells: Vec<usize>
+    pub locked_cells: Vec<usize>,
+    pub rssi_conversion: MkRssiConversion,
+    pub band_plan: Option<MkBandPlan>,
+    /// The RMD file describing the device
+    pub rmd_file_path: Option<PathBuf>,
+    /// Set if the RMD file was not made for the firmware or hardware version of the device
+    pub rmd_warning: Option<String>,
+    /// The multi-byte and bit-packed values, decoded from `cells`
+    pub typed_values: Vec<MkTypedValue>,
+    /// The cells that are not used by the module with the current values (see `MkDeviceCell::active_if`)
+    pub inactive_cells: Vec<usize>,
+}
+
+/// The conversion of raw RSSI readings to dBm, declared per model in the RMD file
+/// by the `RSSI_OFFSET`, `RSSI_SCALE_FACTOR` and `RSSI_SATURATION` keys
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct MkRssiConversion {
+    pub offset: f64,
+    pub scale_factor: f64,
+    /// The raw reading at which the receiver saturates, if the RMD file declares one
+    pub saturation: Option<u8>,
+}
+
+impl Default for MkRssiConversion {
+    /// The conversion used by TinyMesh modules, for RMD files without RSSI keys
+    fn default() -> Self {
+        MkRssiConversion {
+            offset: 0.0,
+            scale_factor: -0.5,
+            saturation: None,
+        }
+    }
+}
+
+impl MkRssiConversion {
+    /// Returns true if the raw reading is at or beyond the saturation level,
+    /// i.e. the signal is too strong for the reading to be accurate.
+    pub fn is_saturated(&self, raw: u8) -> bool {
+        match self.saturation {
+            // with a negative scale factor, stronger signals give lower raw readings
+            Some(saturation) if self.scale_factor < 0.0 => raw <= saturation,
+            Some(saturation) => raw >= saturation,
+            None => false,
+        }
+    }
+
+    /// Converts a raw reading to a `MkRssiReading`. Saturated readings have no dBm value.
+    pub fn convert(&self, raw: u8) -> MkRssiReading {
+        let saturated = self.is_saturated(raw);
+        MkRssiReading {
+            raw,
+            dbm: if saturated {
+                None
+            } else {
+                Some(raw as f64 * self.scale_factor + self.offset)
+            },
+            saturated,
+        }
+    }
+}
+
+/// A RSSI reading converted with the `MkRssiConversion` of the model
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct MkRssiReading {
+    pub raw: u8,
+    pub dbm: Option<f64>,
+    pub saturated: bool,
 }
 
 /// This struct represents the decoded device calib fetched from device
@@ -87,3 +542,208 @@ pub struct MkDeviceCalib {
     pub c_editable_cells: Vec<usize>,
     pub c_locked_cells: Vec<usize>
 }
+
+/// The outcome of writing a single cell, found by reading the memory back after the write
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub enum MkCellWriteStatus {
+    /// The memory now holds the requested value
+    Written,
+    /// The memory holds neither the requested nor the previous value
+    Failed,
+    /// The module kept the previous value
+    Ignored,
+}
+
+/// The read-back result of a single written cell
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct MkCellWriteResult {
+    pub address: usize,
+    pub name: String,
+    /// None if the address is unknown to the model
+    pub previous_value: Option<u8>,
+    pub requested_value: u8,
+    pub read_back_value: Option<u8>,
+    pub status: MkCellWriteStatus,
+}
+
+/// This struct represents the outcome of the `ACTIVE_IF` and `REQUIRE` rules for the edited cells
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct MkCellRulesReport {
+    /// The cells that are not used by the module with the edited values
+    pub inactive_cells: Vec<usize>,
+    /// The rules broken by the edited values
+    pub violations: Vec<CellValidationError>,
+}
+
+/// This struct represents the per-address report of a verified config or calibration write
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct MkWriteReport {
+    pub cells: Vec<MkCellWriteResult>,
+}
+
+impl MkWriteReport {
+    /// Returns true if every requested cell was written.
+    pub fn is_successful(&self) -> bool {
+        self.cells
+            .iter()
+            .all(|cell| cell.status == MkCellWriteStatus::Written)
+    }
+}
+
+/// The result of cloning a configuration to a single module
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct MkCloneUnitReport {
+    pub port_name: String,
+    /// The model read from the module, if it could be read
+    pub model: Option<String>,
+    pub passed: bool,
+    /// The reason the unit failed, if it failed before the write could be verified
+    pub error: Option<String>,
+    pub write_report: MkWriteReport,
+}
+
+/// This struct represents the per-unit report of cloning a configuration to several modules
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct MkCloneReport {
+    pub units: Vec<MkCloneUnitReport>,
+}
+
+impl MkCloneReport {
+    /// Returns true if every unit passed.
+    pub fn is_successful(&self) -> bool {
+        self.units.iter().all(|unit| unit.passed)
+    }
+}
+
+/// The band plan of a model, declared in the RMD file by the `FREQUENCY_CHANNEL_FIRST_CENTER`
+/// and `FREQUENCY_CHANNEL_WIDTH` keys. The channels are the range of the RF Channel cell.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct MkBandPlan {
+    /// The center frequency of `first_channel` in MHz
+    pub first_center_mhz: f64,
+    /// The distance between two channels in kHz
+    pub channel_width_khz: f64,
+    pub first_channel: u8,
+    pub last_channel: u8,
+}
+
+impl MkBandPlan {
+    /// Returns the center frequency of the channel in MHz, or `None` if the channel is not part of the band plan.
+    pub fn frequency_of(&self, channel: u8) -> Option<f64> {
+        if channel < self.first_channel || channel > self.last_channel {
+            return None;
+        }
+        let offset_mhz = (channel - self.first_channel) as f64 * self.channel_width_khz / 1000.0;
+        // round to kHz to get rid of floating point noise
+        Some(((self.first_center_mhz + offset_mhz) * 1000.0).round() / 1000.0)
+    }
+
+    /// Returns the channel whose center is closest to the frequency,
+    /// or `None` if the frequency is more than half a channel away from every channel.
+    pub fn channel_of(&self, frequency_mhz: f64) -> Option<u8> {
+        let channel_width_mhz = self.channel_width_khz / 1000.0;
+        if channel_width_mhz <= 0.0 {
+            return None;
+        }
+        let channel_count = self.last_channel.checked_sub(self.first_channel)?;
+        let offset = ((frequency_mhz - self.first_center_mhz) / channel_width_mhz).round();
+        if offset < 0.0 || offset > channel_count as f64 {
+            return None;
+        }
+        let channel = self.first_channel + offset as u8;
+        let distance_mhz = (self.frequency_of(channel)? - frequency_mhz).abs();
+        if distance_mhz > channel_width_mhz / 2.0 {
+            return None;
+        }
+        Some(channel)
+    }
+}
+
+/// The firmware and hardware versions a RMD file was made for, declared by the `FIRMWARE_VERSION_MIN_MAX`
+/// (e.g. `1.50 1.54`) and `HW_VERSIONS` (e.g. `1.00 1.01`) keys. A RMD file without these keys is made for every version.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct MkRmdCompatibility {
+    /// The lowest and highest supported firmware version, inclusive
+    pub firmware_versions: Option<(String, String)>,
+    /// The supported hardware revisions, empty for every revision
+    pub hw_versions: Vec<String>,
+}
+
+impl MkRmdCompatibility {
+    /// Returns true if the firmware version is in the supported range, or `None` if the RMD file declares no range.
+    pub fn supports_firmware(&self, firmware_version: &str) -> Option<bool> {
+        let (min, max) = self.firmware_versions.as_ref()?;
+        let version = version_numbers(firmware_version);
+        Some(version >= version_numbers(min) && version <= version_numbers(max))
+    }
+
+    /// Returns how far the firmware version is from the supported range, 0 if it is in the range
+    /// or the RMD file declares no range.
+    pub fn firmware_distance(&self, firmware_version: &str) -> u64 {
+        let (min, max) = match &self.firmware_versions {
+            Some(firmware_versions) => firmware_versions,
+            None => return 0,
+        };
+        let version = version_value(firmware_version);
+        let (min, max) = (version_value(min), version_value(max));
+        if version < min {
+            return min - version;
+        }
+        version.saturating_sub(max)
+    }
+
+    /// Returns true if the hardware revision is supported, or `None` if the RMD file declares no revisions.
+    pub fn supports_hw(&self, hw_version: &str) -> Option<bool> {
+        if self.hw_versions.is_empty() {
+            return None;
+        }
+        let version = version_numbers(hw_version);
+        Some(self.hw_versions.iter().any(|hw| version_numbers(hw) == version))
+    }
+
+    /// Returns a warning if the RMD file was not made for the versions of the device, `None` if it was.
+    pub fn check(&self, hw_version: &str, firmware_version: &str) -> Option<String> {
+        let mut problems = vec![];
+        if let (Some(false), Some((min, max))) =
+            (self.supports_firmware(firmware_version), &self.firmware_versions)
+        {
+            problems.push(format!(
+                "firmware {} (the RMD file is for {} to {})",
+                firmware_version, min, max
+            ));
+        }
+        if self.supports_hw(hw_version) == Some(false) {
+            problems.push(format!(
+                "hardware {} (the RMD file is for {})",
+                hw_version,
+                self.hw_versions.join(", ")
+            ));
+        }
+        if problems.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "No RMD file was made for {}, the closest match is used",
+            problems.join(" and ")
+        ))
+    }
+}
+
+/// Splits a version like `1.53` into its numbers, so `1.9` sorts before `1.10`.
+/// Parts that are not numbers count as 0.
+fn version_numbers(version: &str) -> Vec<u32> {
+    version
+        .trim()
+        .split('.')
+        .map(|part| part.parse::<u32>().unwrap_or(0))
+        .collect()
+}
+
+/// Returns the version as a single number (up to three parts), to measure the distance between versions.
+fn version_value(version: &str) -> u64 {
+    let mut numbers = version_numbers(version);
+    numbers.resize(3, 0);
+    numbers
+        .iter()
+        .fold(0, |value, number| value * 1000 + *number as u64)
+}
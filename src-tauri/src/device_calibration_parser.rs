@@ -3,7 +3,7 @@ use std::path::Path;
 use tauri::AppHandle;
 
 use crate::data_types::{MkDeviceCell, MkDeviceCalib};
-use crate::mk_module_description::MkModuleDescription;
+use crate::mk_module_description::{format_rmd_diagnostics, MkModuleDescription};
 
 /// This function parses the device calibration and returns a struct representing the decoded device calibration
 ///
@@ -28,6 +28,7 @@ pub fn parse_device_calib(
         let file_contents =
             std::fs::read_to_string(rmd_file_path).map_err(|err| err.to_string())?;
         MkModuleDescription::new(&file_contents)
+            .map_err(|diagnostics| format_rmd_diagnostics(&diagnostics))?
     } else {
         if app_handle.is_none() {
             return Err("App handle is None".to_string());
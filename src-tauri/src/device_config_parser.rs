@@ -1,10 +1,12 @@
 use std::cmp::min;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use tauri::AppHandle;
 
+use crate::cell_rules::find_inactive_cells;
+use crate::cell_values::decode_typed_values;
 use crate::data_types::{MkDeviceCell, MkDeviceConfig};
-use crate::mk_module_description::MkModuleDescription;
+use crate::mk_module_description::{format_rmd_diagnostics, MkModuleDescription};
 
 /// This function parses the device config and returns a struct representing the decoded device config
 ///
@@ -15,7 +17,7 @@ use crate::mk_module_description::MkModuleDescription;
 ///
 /// # Returns
 /// A `Result` containing a `MkDeviceConfig` object if parsing is successful, or a `String` containing an error message if parsing fails
-use log::info;
+use log::{info, warn};
 
 pub fn parse_device_config(
     data: &[u8],
@@ -26,35 +28,108 @@ pub fn parse_device_config(
     // info!("\ndevice_config_parser::parse_device_config---> data = {:?}", data);
     // info!("Data we get-> {:?}", data);
     let (model, hw_version, firmware_version) = get_device_information(data)?;
-    // from the modules folder in the current working directory, read the contents of a file named <model>.rmd
-    let module_description = if let Some(rmd_file_path) = rmd_file_path {
+    let (module_description, rmd_file_path, rmd_warning) = if let Some(rmd_file_path) = rmd_file_path {
         let file_contents =
             std::fs::read_to_string(rmd_file_path).map_err(|err| err.to_string())?;
-        MkModuleDescription::new(&file_contents)
+        let module_description = MkModuleDescription::new(&file_contents)
+            .map_err(|diagnostics| format_rmd_diagnostics(&diagnostics))?;
+        let rmd_warning = module_description
+            .compatibility
+            .check(&hw_version, &firmware_version);
+        (module_description, rmd_file_path.to_path_buf(), rmd_warning)
     } else {
         if app_handle.is_none() {
             return Err("App handle is None".to_string());
         }
-        MkModuleDescription::new_from_device_model(&model, app_handle.unwrap())?
+        // the RMD file is selected by model and versions, from the module search directories
+        let (module_description, selection) = MkModuleDescription::new_from_device_information(
+            &model,
+            &hw_version,
+            &firmware_version,
+            app_handle.unwrap(),
+        )?;
+        (module_description, selection.file.file_path, selection.warning)
     };
+    if let Some(rmd_warning) = &rmd_warning {
+        warn!("{}", rmd_warning);
+    }
 
-    let cells = read_unlocked_cells(data, &module_description);
-    let test_modes = module_description.testmodes;
-    let quick_modes = module_description.quickmodes;
-    let editable_cells = module_description.editable_cells;
-    let locked_cells = module_description.locked_cells;
+    Ok(build_device_config(
+        data,
+        (model, hw_version, firmware_version),
+        module_description,
+        Some(rmd_file_path),
+        rmd_warning,
+    ))
+}
 
-    let result = MkDeviceConfig {
+/// This function maps a cached device config onto the cell definitions of a new module description,
+/// e.g. after its RMD file was changed, without reading the device again.
+///
+/// # Arguments
+/// * `device_config` - The device config read from the device with the old module description
+/// * `module_description` - The new module description
+///
+/// # Returns
+/// The device config with the current values of `device_config` and the definitions of `module_description`.
+pub fn remap_device_config(
+    device_config: &MkDeviceConfig,
+    module_description: MkModuleDescription,
+) -> MkDeviceConfig {
+    let data: Vec<u8> = device_config
+        .cells
+        .iter()
+        .map(|cell| cell.current_value)
+        .collect();
+    let rmd_warning = module_description
+        .compatibility
+        .check(&device_config.hw_version, &device_config.firmware_version);
+    build_device_config(
+        &data,
+        (
+            device_config.model.clone(),
+            device_config.hw_version.clone(),
+            device_config.firmware_version.clone(),
+        ),
+        module_description,
+        device_config.rmd_file_path.clone(),
+        rmd_warning,
+    )
+}
+
+fn build_device_config(
+    data: &[u8],
+    (model, hw_version, firmware_version): (String, String, String),
+    module_description: MkModuleDescription,
+    rmd_file_path: Option<PathBuf>,
+    rmd_warning: Option<String>,
+) -> MkDeviceConfig {
+    let mut cells = read_unlocked_cells(data, &module_description);
+    let band_plan = module_description.band_plan;
+    if let Some(band_plan) = &band_plan {
+        if let Some(channel) = cells.iter_mut().find(|cell| cell.name == "RF Channel") {
+            channel.frequency_mhz = band_plan.frequency_of(channel.current_value);
+        }
+    }
+    let typed_values = decode_typed_values(&cells);
+    let inactive_cells = find_inactive_cells(&cells);
+
+    MkDeviceConfig {
         model,
         hw_version,
         firmware_version,
         cells,
-        test_modes,
-        quick_modes,
-        editable_cells,
-        locked_cells
-    };
-    Ok(result)
+        test_modes: module_description.testmodes,
+        quick_modes: module_description.quickmodes,
+        editable_cells: module_description.editable_cells,
+        locked_cells: module_description.locked_cells,
+        rssi_conversion: module_description.rssi_conversion,
+        band_plan,
+        rmd_file_path,
+        rmd_warning,
+        typed_values,
+        inactive_cells,
+    }
 }
 
 // Maheep sirs implementation, changed since unlocked cells arent required to be passed, but all cells must be passed & locked will be disabled
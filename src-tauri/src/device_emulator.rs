@@ -0,0 +1,365 @@
+//! This module contains a software emulator of a TinyMesh module in configuration mode.
+//! The emulator is driven by a RMD module description and a configuration memory image,
+//! and answers the same command bytes the backend sends to a real module.
+//! It is meant for developing and regression testing without hardware attached.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::device_transport::MemoryTransport;
+use crate::mk_module_description::{format_rmd_diagnostics, MkModuleDescription};
+use crate::device_protocol::{
+    extract_send_recv_seq, COMMAND_OPCODES, ENTER_CONFIG_MODE, EXIT_CONFIG_MODE, FACTORY_RESET,
+    GET_ANALOG, GET_DIGITAL, GET_RSSI, GET_TEMPERATURE, GET_VOLTAGE, MEMORY_WRITE_TERMINATOR,
+    PROMPT, READ_CALIBRATION, READ_CONFIG, SET_CHANNEL, WRITE_CALIBRATION, WRITE_CONFIG,
+};
+
+/// Which memory a `M` / `HW` write sequence is targeting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MemoryKind {
+    Config,
+    Calibration,
+}
+
+/// The state of the emulator's command parser.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ParserState {
+    /// Waiting for a command at the `>` prompt.
+    Command,
+    /// Waiting for the channel number after the `C` command.
+    Channel,
+    /// Waiting for the address of the next memory write (or the `0xFF` terminator).
+    WriteAddress(MemoryKind),
+    /// Waiting for the value to write at the given address.
+    WriteValue(MemoryKind, u8),
+}
+
+struct EmulatorState {
+    config_memory: Vec<u8>,
+    calibration_memory: Vec<u8>,
+    default_config_memory: Vec<u8>,
+    locked_cells: Vec<usize>,
+    mode_sequences: HashMap<Vec<u8>, Vec<u8>>,
+    channel: u8,
+    rssi: HashMap<u8, u8>,
+    default_rssi: u8,
+    analog: Vec<u8>,
+    digital: u8,
+    temperature: u8,
+    voltage: u8,
+    config_mode: bool,
+    parser_state: ParserState,
+    pending: Vec<u8>,
+    received_commands: Vec<Vec<u8>>,
+    transparent_data: Vec<u8>,
+}
+
+/// A simulated TinyMesh module.
+/// Clones share the same state, so a test can keep one handle for inspecting the
+/// emulated memories while the backend talks to the transport returned by `transport`.
+#[derive(Clone)]
+pub struct TinyMeshEmulator {
+    state: Arc<Mutex<EmulatorState>>,
+}
+
+/// Parses a whitespace-separated hex dump (e.g. `resources/tests/config_response.txt`) into bytes.
+///
+/// # Arguments
+/// * `input` - The hex dump to parse.
+///
+/// # Returns
+/// A `Result` containing the bytes, or a `String` describing the first invalid token.
+pub fn parse_hex_dump(input: &str) -> Result<Vec<u8>, String> {
+    input
+        .split_whitespace()
+        .map(|s| {
+            u8::from_str_radix(s.trim_start_matches("0x"), 16)
+                .map_err(|_| format!("Invalid hex byte in dump: '{}'", s))
+        })
+        .collect()
+}
+
+impl TinyMeshEmulator {
+    /// Creates a new emulator.
+    ///
+    /// # Arguments
+    /// * `module_description` - The module description of the emulated model.
+    /// * `config_memory` - The contents of the configuration memory, as returned by the `0` command
+    ///   (a trailing `>` prompt is ignored).
+    ///
+    /// # Returns
+    /// A `TinyMeshEmulator` sitting at the configuration mode prompt.
+    pub fn new(module_description: &MkModuleDescription, config_memory: &[u8]) -> TinyMeshEmulator {
+        let mut config_memory = config_memory.to_vec();
+        if config_memory.last() == Some(&PROMPT) {
+            config_memory.pop();
+        }
+        let default_config_memory = module_description
+            .cells
+            .iter()
+            .take(config_memory.len())
+            .map(|cell| cell.default_value)
+            .collect();
+        let calibration_memory = module_description
+            .calibration_cells
+            .iter()
+            .take(config_memory.len())
+            .map(|cell| cell.default_value)
+            .collect();
+
+        let mut mode_sequences = HashMap::new();
+        let sequences = module_description
+            .testmodes
+            .iter()
+            .flat_map(|mode| [&mode.sequence_on, &mode.sequence_off])
+            .chain(
+                module_description
+                    .quickmodes
+                    .iter()
+                    .flat_map(|mode| [&mode.sequence_on, &mode.sequence_off]),
+            );
+        for sequence in sequences {
+            if let Some((send, recv)) = extract_send_recv_seq(sequence) {
+                if !send.is_empty() && !COMMAND_OPCODES.contains(&send.as_slice()) {
+                    mode_sequences.insert(send, recv);
+                }
+            }
+        }
+
+        TinyMeshEmulator {
+            state: Arc::new(Mutex::new(EmulatorState {
+                config_memory,
+                calibration_memory,
+                default_config_memory,
+                locked_cells: module_description.locked_cells.clone(),
+                mode_sequences,
+                channel: 1,
+                rssi: HashMap::new(),
+                default_rssi: 200,
+                analog: vec![0x00, 0x00, 0x00, 0x00],
+                digital: 0xFF,
+                temperature: 153,
+                voltage: 110,
+                config_mode: true,
+                parser_state: ParserState::Command,
+                pending: vec![],
+                received_commands: vec![],
+                transparent_data: vec![],
+            })),
+        }
+    }
+
+    /// Creates a new emulator from the contents of a RMD file and a hex dump of the configuration memory.
+    ///
+    /// # Arguments
+    /// * `rmd` - The RMD string of the emulated model.
+    /// * `config_hex_dump` - The configuration memory as a hex dump, see `parse_hex_dump`.
+    ///
+    /// # Returns
+    /// A `Result` containing the emulator, or a `String` if the RMD file or the hex dump is invalid.
+    pub fn from_rmd_and_hex_dump(rmd: &str, config_hex_dump: &str) -> Result<TinyMeshEmulator, String> {
+        let module_description = MkModuleDescription::new(rmd)
+            .map_err(|diagnostics| format_rmd_diagnostics(&diagnostics))?;
+        let config_memory = parse_hex_dump(config_hex_dump)?;
+        Ok(TinyMeshEmulator::new(&module_description, &config_memory))
+    }
+
+    /// Returns a transport connected to this emulator.
+    pub fn transport(&self) -> MemoryTransport {
+        let emulator = self.clone();
+        MemoryTransport::with_responder(Box::new(move |bytes| emulator.handle_bytes(bytes)))
+    }
+
+    /// Feeds bytes sent by the host into the emulator and returns the bytes the module answers with.
+    pub fn handle_bytes(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut state = self.lock_state();
+        let mut response = vec![];
+        for byte in bytes {
+            state.handle_byte(*byte, &mut response);
+        }
+        response
+    }
+
+    /// Returns a copy of the emulated configuration memory.
+    pub fn config_memory(&self) -> Vec<u8> {
+        self.lock_state().config_memory.clone()
+    }
+
+    /// Returns a copy of the emulated calibration memory.
+    pub fn calibration_memory(&self) -> Vec<u8> {
+        self.lock_state().calibration_memory.clone()
+    }
+
+    /// Replaces the emulated calibration memory, e.g. with a dump taken from a customer's module.
+    pub fn set_calibration_memory(&self, calibration_memory: &[u8]) {
+        self.lock_state().calibration_memory = calibration_memory.to_vec();
+    }
+
+    /// Returns the RF channel last selected with the `C` command.
+    pub fn channel(&self) -> u8 {
+        self.lock_state().channel
+    }
+
+    /// Sets the raw RSSI value reported by the `S` command while on the given channel.
+    pub fn set_rssi(&self, channel: u8, rssi: u8) {
+        self.lock_state().rssi.insert(channel, rssi);
+    }
+
+    /// Sets the raw values reported by the `A`, `D`, `U` and `V` commands.
+    pub fn set_sensor_values(&self, analog: &[u8], digital: u8, temperature: u8, voltage: u8) {
+        let mut state = self.lock_state();
+        state.analog = analog.to_vec();
+        state.digital = digital;
+        state.temperature = temperature;
+        state.voltage = voltage;
+    }
+
+    /// Returns whether the emulated module is in configuration mode.
+    pub fn is_in_config_mode(&self) -> bool {
+        self.lock_state().config_mode
+    }
+
+    /// Switches the emulated module between configuration and transparent mode,
+    /// as pulling or releasing the CONFIG pin by hand would.
+    pub fn set_config_mode(&self, config_mode: bool) {
+        let mut state = self.lock_state();
+        state.config_mode = config_mode;
+        state.parser_state = ParserState::Command;
+        state.pending.clear();
+    }
+
+    /// Returns every byte received in transparent mode, i.e. the data that would have been sent over the air.
+    pub fn transparent_data(&self) -> Vec<u8> {
+        self.lock_state().transparent_data.clone()
+    }
+
+    /// Returns every complete command received so far, in order.
+    pub fn received_commands(&self) -> Vec<Vec<u8>> {
+        self.lock_state().received_commands.clone()
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, EmulatorState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl EmulatorState {
+    fn handle_byte(&mut self, byte: u8, response: &mut Vec<u8>) {
+        if !self.config_mode {
+            if [byte] == ENTER_CONFIG_MODE {
+                self.config_mode = true;
+                response.push(PROMPT);
+            } else {
+                self.transparent_data.push(byte);
+            }
+            return;
+        }
+        match self.parser_state {
+            ParserState::Channel => {
+                self.channel = byte;
+                self.parser_state = ParserState::Command;
+                response.push(PROMPT);
+            }
+            ParserState::WriteAddress(kind) => {
+                if byte == MEMORY_WRITE_TERMINATOR {
+                    self.parser_state = ParserState::Command;
+                    response.push(PROMPT);
+                } else {
+                    self.parser_state = ParserState::WriteValue(kind, byte);
+                }
+            }
+            ParserState::WriteValue(kind, address) => {
+                let memory = match kind {
+                    MemoryKind::Config => &mut self.config_memory,
+                    MemoryKind::Calibration => &mut self.calibration_memory,
+                };
+                // the 'M' command overrides locking, so locked cells are written as well
+                if let Some(cell) = memory.get_mut(address as usize) {
+                    *cell = byte;
+                }
+                self.parser_state = ParserState::WriteAddress(kind);
+            }
+            ParserState::Command => {
+                self.pending.push(byte);
+                let pending = self.pending.clone();
+                let is_known = |command: &[u8]| command == pending.as_slice();
+                let is_prefix = |command: &[u8]| {
+                    command.len() > pending.len() && command.starts_with(&pending)
+                };
+                if COMMAND_OPCODES.iter().any(|c| is_known(c)) || self.mode_sequences.contains_key(&pending) {
+                    self.pending.clear();
+                    self.received_commands.push(pending.clone());
+                    self.execute_command(&pending, response);
+                } else if !COMMAND_OPCODES.iter().any(|c| is_prefix(c))
+                    && !self.mode_sequences.keys().any(|c| is_prefix(c))
+                {
+                    // unknown commands are silently ignored by the module
+                    self.pending.clear();
+                }
+            }
+        }
+    }
+
+    fn execute_command(&mut self, command: &[u8], response: &mut Vec<u8>) {
+        match command {
+            READ_CONFIG => {
+                response.extend_from_slice(&self.config_memory);
+                response.push(PROMPT);
+            }
+            READ_CALIBRATION => {
+                response.extend_from_slice(&self.calibration_memory);
+                response.push(PROMPT);
+            }
+            WRITE_CONFIG => {
+                self.parser_state = ParserState::WriteAddress(MemoryKind::Config);
+                response.push(PROMPT);
+            }
+            WRITE_CALIBRATION => {
+                self.parser_state = ParserState::WriteAddress(MemoryKind::Calibration);
+                response.push(PROMPT);
+            }
+            FACTORY_RESET => {
+                for (address, default_value) in self.default_config_memory.iter().enumerate() {
+                    if !self.locked_cells.contains(&address) {
+                        self.config_memory[address] = *default_value;
+                    }
+                }
+                response.push(PROMPT);
+            }
+            GET_RSSI => {
+                let rssi = *self.rssi.get(&self.channel).unwrap_or(&self.default_rssi);
+                response.push(rssi);
+                response.push(PROMPT);
+            }
+            GET_ANALOG => {
+                response.extend_from_slice(&self.analog);
+                response.push(PROMPT);
+            }
+            GET_DIGITAL => {
+                response.push(self.digital);
+                response.push(PROMPT);
+            }
+            GET_TEMPERATURE => {
+                response.push(self.temperature);
+                response.push(PROMPT);
+            }
+            GET_VOLTAGE => {
+                response.push(self.voltage);
+                response.push(PROMPT);
+            }
+            EXIT_CONFIG_MODE => {
+                // the module leaves configuration mode without answering
+                self.config_mode = false;
+            }
+            SET_CHANNEL => {
+                self.parser_state = ParserState::Channel;
+                response.push(PROMPT);
+            }
+            _ => {
+                if let Some(recv) = self.mode_sequences.get(command) {
+                    response.extend_from_slice(recv);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! This module contains the TinyMesh configuration mode protocol.
+//! Every command understood by the module is described by the `Command` enum,
+//! which knows how to encode its request bytes and decode the response, including the
+//! trailing `>` prompt and the `0xFF` terminator of memory writes.
+//! The Tauri commands in the `tinymesh_*` modules are thin wrappers around `execute_command`.
+
+use tauri::AppHandle;
+
+use crate::device_transport::DeviceTransport;
+use crate::tinymesh_serial_util::{
+    clear_output_buffer_of_device, read_bytes_from_device_to_buffer,
+    read_bytes_till_3e_from_device_to_buffer, send_bytes_to_device, DeviceError,
+};
+
+/// The prompt sent by the module when it is ready for the next command.
+pub const PROMPT: u8 = b'>';
+
+/// Terminates the address/value pairs of a memory write.
+pub const MEMORY_WRITE_TERMINATOR: u8 = 0xFF;
+
+pub const READ_CONFIG: &[u8] = b"0";
+pub const READ_CALIBRATION: &[u8] = b"r";
+pub const WRITE_CONFIG: &[u8] = b"M";
+pub const WRITE_CALIBRATION: &[u8] = b"HW";
+pub const FACTORY_RESET: &[u8] = b"@TM";
+pub const GET_RSSI: &[u8] = b"S";
+pub const GET_ANALOG: &[u8] = b"A";
+pub const GET_DIGITAL: &[u8] = b"D";
+pub const GET_TEMPERATURE: &[u8] = b"U";
+pub const GET_VOLTAGE: &[u8] = b"V";
+pub const SET_CHANNEL: &[u8] = b"C";
+pub const EXIT_CONFIG_MODE: &[u8] = b"X";
+
+/// Sent in transparent / packet mode to request the configuration mode prompt.
+pub const ENTER_CONFIG_MODE: &[u8] = &[0x00];
+
+/// All the fixed command opcodes, e.g. for recognising commands on the device side.
+pub const COMMAND_OPCODES: [&[u8]; 12] = [
+    READ_CONFIG,
+    READ_CALIBRATION,
+    WRITE_CONFIG,
+    WRITE_CALIBRATION,
+    FACTORY_RESET,
+    GET_RSSI,
+    GET_ANALOG,
+    GET_DIGITAL,
+    GET_TEMPERATURE,
+    GET_VOLTAGE,
+    SET_CHANNEL,
+    EXIT_CONFIG_MODE,
+];
+
+/// A command understood by a TinyMesh module in configuration mode.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// `0`: dump the configuration memory.
+    ReadConfig,
+    /// `r`: dump the calibration memory.
+    ReadCalibration,
+    /// `M` followed by address/value pairs and `0xFF`: write the configuration memory.
+    WriteConfig(Vec<(u8, u8)>),
+    /// `HW` followed by address/value pairs and `0xFF`: write the calibration memory.
+    WriteCalibration(Vec<(u8, u8)>),
+    /// `@TM`: restore the factory default configuration.
+    FactoryReset,
+    /// `S`: read the RSSI of the current channel.
+    GetRssi,
+    /// `A`: read the analog inputs.
+    GetAnalog,
+    /// `D`: read the digital inputs.
+    GetDigital,
+    /// `U`: read the temperature.
+    GetTemperature,
+    /// `V`: read the power supply voltage.
+    GetVoltage,
+    /// `C` followed by the channel number: switch the RF channel.
+    SetChannel(u8),
+    /// `0x00` sent in transparent mode: enter configuration mode.
+    /// Any data received before the `>` prompt is transparent traffic and is discarded.
+    EnterConfigMode,
+    /// `X`: leave configuration mode and return to transparent / packet mode.
+    /// The module does not answer this command.
+    ExitConfigMode,
+    /// A testmode / quickmode sequence from the RMD file, e.g. `aG #>`.
+    ModeSequence { send: Vec<u8>, expect: Vec<u8> },
+}
+
+/// What the module is expected to answer to one step of a command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExpectedResponse {
+    /// Only the `>` prompt.
+    Prompt,
+    /// Some data followed by the `>` prompt.
+    Data,
+    /// Exactly these bytes, without a prompt. Read until the module stops sending.
+    Exactly(Vec<u8>),
+    /// No response at all.
+    Nothing,
+}
+
+/// One request / response step of a command.
+/// Commands such as `M` consist of several steps, since the module answers each part with a prompt.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Exchange {
+    pub request: Vec<u8>,
+    pub expected_response: ExpectedResponse,
+}
+
+/// The decoded response to a command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Response {
+    /// The command was acknowledged with the `>` prompt.
+    Ack,
+    /// A memory dump (`0`, `r`).
+    Memory(Vec<u8>),
+    /// A single byte value (`S`, `D`, `U`, `V`).
+    Byte(u8),
+    /// A multi byte value (`A`).
+    Bytes(Vec<u8>),
+}
+
+impl Response {
+    /// Returns the bytes of a `Memory` or `Bytes` response.
+    pub fn into_bytes(self) -> Result<Vec<u8>, DeviceError> {
+        match self {
+            Response::Memory(bytes) | Response::Bytes(bytes) => Ok(bytes),
+            Response::Byte(byte) => Ok(vec![byte]),
+            Response::Ack => Ok(vec![]),
+        }
+    }
+
+    /// Returns the value of a `Byte` response.
+    pub fn into_byte(self) -> Result<u8, DeviceError> {
+        match self {
+            Response::Byte(byte) => Ok(byte),
+            other => Err(DeviceError::UnexpectedResponse(other.into_bytes()?)),
+        }
+    }
+}
+
+fn encode_memory_write(pairs: &[(u8, u8)]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = pairs
+        .iter()
+        .flat_map(|(address, value)| [*address, *value])
+        .collect();
+    bytes.push(MEMORY_WRITE_TERMINATOR);
+    bytes
+}
+
+impl Command {
+    /// Creates a `ModeSequence` command from a RMD sequence string such as `aG #>`.
+    pub fn from_mode_sequence(sequence_str: &str) -> Option<Command> {
+        extract_send_recv_seq(sequence_str)
+            .map(|(send, expect)| Command::ModeSequence { send, expect })
+    }
+
+    /// Encodes the command into the request / response steps sent to the module.
+    pub fn encode(&self) -> Vec<Exchange> {
+        let step = |request: &[u8], expected_response: ExpectedResponse| Exchange {
+            request: request.to_vec(),
+            expected_response,
+        };
+        match self {
+            Command::ReadConfig => vec![step(READ_CONFIG, ExpectedResponse::Data)],
+            Command::ReadCalibration => vec![step(READ_CALIBRATION, ExpectedResponse::Data)],
+            Command::WriteConfig(pairs) => vec![
+                step(WRITE_CONFIG, ExpectedResponse::Prompt),
+                step(&encode_memory_write(pairs), ExpectedResponse::Prompt),
+            ],
+            Command::WriteCalibration(pairs) => vec![
+                step(WRITE_CALIBRATION, ExpectedResponse::Prompt),
+                step(&encode_memory_write(pairs), ExpectedResponse::Prompt),
+            ],
+            Command::FactoryReset => vec![step(FACTORY_RESET, ExpectedResponse::Prompt)],
+            Command::GetRssi => vec![step(GET_RSSI, ExpectedResponse::Data)],
+            Command::GetAnalog => vec![step(GET_ANALOG, ExpectedResponse::Data)],
+            Command::GetDigital => vec![step(GET_DIGITAL, ExpectedResponse::Data)],
+            Command::GetTemperature => vec![step(GET_TEMPERATURE, ExpectedResponse::Data)],
+            Command::GetVoltage => vec![step(GET_VOLTAGE, ExpectedResponse::Data)],
+            Command::SetChannel(channel) => vec![
+                step(SET_CHANNEL, ExpectedResponse::Prompt),
+                step(&[*channel], ExpectedResponse::Prompt),
+            ],
+            Command::EnterConfigMode => vec![step(ENTER_CONFIG_MODE, ExpectedResponse::Data)],
+            Command::ExitConfigMode => vec![step(EXIT_CONFIG_MODE, ExpectedResponse::Nothing)],
+            Command::ModeSequence { send, expect } => match expect.strip_suffix(&[PROMPT]) {
+                // the expected bytes before the prompt are checked while decoding
+                Some(_) => vec![step(send, ExpectedResponse::Data)],
+                None => vec![step(send, ExpectedResponse::Exactly(expect.clone()))],
+            },
+        }
+    }
+
+    /// Decodes the data received for the last step of the command (without the `>` prompt).
+    pub fn decode(&self, data: Vec<u8>) -> Result<Response, DeviceError> {
+        match self {
+            Command::ReadConfig | Command::ReadCalibration => Ok(Response::Memory(data)),
+            Command::GetRssi | Command::GetDigital | Command::GetTemperature | Command::GetVoltage => {
+                if data.len() == 1 {
+                    return Ok(Response::Byte(data[0]));
+                }
+                Err(DeviceError::UnexpectedResponse(data))
+            }
+            Command::GetAnalog => {
+                if data.len() > 1 {
+                    return Ok(Response::Bytes(data));
+                }
+                Err(DeviceError::UnexpectedResponse(data))
+            }
+            Command::EnterConfigMode | Command::ExitConfigMode => Ok(Response::Ack),
+            Command::ModeSequence { expect, .. } => {
+                let expected = expect.strip_suffix(&[PROMPT]).unwrap_or(expect);
+                if data == expected {
+                    return Ok(Response::Ack);
+                }
+                Err(DeviceError::UnexpectedResponse(data))
+            }
+            Command::WriteConfig(_)
+            | Command::WriteCalibration(_)
+            | Command::FactoryReset
+            | Command::SetChannel(_) => {
+                if data.is_empty() {
+                    return Ok(Response::Ack);
+                }
+                Err(DeviceError::UnexpectedResponse(data))
+            }
+        }
+    }
+}
+
+/// Splits a mode sequence string (e.g. `aG #>`) into the bytes to send and the bytes expected back.
+/// Returns `None` if the sequence does not contain both parts.
+pub fn extract_send_recv_seq(sequence_str: &str) -> Option<(Vec<u8>, Vec<u8>)> {
+    if let [send_seq, recv_seq] = sequence_str
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .as_slice()
+    {
+        let send = send_seq.trim_start_matches('a').as_bytes().to_vec();
+        let recv = recv_seq.trim_start_matches('#').as_bytes().to_vec();
+        return Some((send, recv));
+    }
+    None
+}
+
+/// Sends a command to the device and decodes its response.
+///
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `command` - The command to execute
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// The decoded `Response`, or a `DeviceError` if any step of the exchange failed.
+pub fn execute_command(
+    device: &mut dyn DeviceTransport,
+    command: &Command,
+    app_handle: Option<&AppHandle>,
+) -> Result<Response, DeviceError> {
+    let mut data = vec![];
+    for exchange in command.encode() {
+        data.clear();
+        clear_output_buffer_of_device(device)?;
+        send_bytes_to_device(device, &exchange.request, app_handle)?;
+        match exchange.expected_response {
+            ExpectedResponse::Prompt => {
+                read_bytes_till_3e_from_device_to_buffer(device, &mut data, app_handle)?;
+                if !data.is_empty() {
+                    return Err(DeviceError::UnexpectedResponse(data));
+                }
+            }
+            ExpectedResponse::Data => {
+                read_bytes_till_3e_from_device_to_buffer(device, &mut data, app_handle)?;
+            }
+            ExpectedResponse::Exactly(_) => {
+                read_bytes_from_device_to_buffer(device, &mut data, app_handle);
+            }
+            ExpectedResponse::Nothing => {}
+        }
+    }
+    command.decode(data)
+}
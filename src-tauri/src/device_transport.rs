@@ -0,0 +1,276 @@
+//! This module contains the transport abstraction used to talk to TinyMesh devices.
+//! Every command module works against `DeviceTransport` instead of a concrete serial port,
+//! so the same code paths can be driven by a real serial port or by an in-memory transport in tests.
+
+use serialport::{ClearBuffer, SerialPort};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A byte-oriented, bidirectional connection to a TinyMesh device.
+/// Reads must honour the configured timeout and fail with `io::ErrorKind::TimedOut`
+/// when no data arrives in time, the same way a serial port does.
+pub trait DeviceTransport: Read + Write + Send {
+    /// Returns a human readable name of the connection (e.g. the serial port name).
+    fn name(&self) -> Option<String>;
+
+    /// Discards pending data in the given buffer(s).
+    fn clear(&mut self, buffer_to_clear: ClearBuffer) -> io::Result<()>;
+
+    /// Returns the read timeout of the connection.
+    fn timeout(&self) -> Duration;
+
+    /// Sets the read timeout of the connection.
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()>;
+
+    /// Creates a second handle to the same underlying connection.
+    fn try_clone(&self) -> io::Result<Box<dyn DeviceTransport>>;
+
+    /// Drives the CONFIG pin of the module (`true` pulls it to the active, low level).
+    /// Connections without a CONFIG line return `io::ErrorKind::Unsupported`.
+    fn set_config_pin(&mut self, _asserted: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "The connection has no CONFIG line",
+        ))
+    }
+}
+
+impl<T: DeviceTransport + ?Sized> DeviceTransport for Box<T> {
+    fn name(&self) -> Option<String> {
+        (**self).name()
+    }
+
+    fn clear(&mut self, buffer_to_clear: ClearBuffer) -> io::Result<()> {
+        (**self).clear(buffer_to_clear)
+    }
+
+    fn timeout(&self) -> Duration {
+        (**self).timeout()
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        (**self).set_timeout(timeout)
+    }
+
+    fn try_clone(&self) -> io::Result<Box<dyn DeviceTransport>> {
+        (**self).try_clone()
+    }
+
+    fn set_config_pin(&mut self, asserted: bool) -> io::Result<()> {
+        (**self).set_config_pin(asserted)
+    }
+}
+
+/// `DeviceTransport` backed by a serial port opened via the `serialport` crate.
+pub struct SerialTransport {
+    port: Box<dyn SerialPort>,
+    config_pin: bool,
+}
+
+impl SerialTransport {
+    pub fn new(port: Box<dyn SerialPort>) -> SerialTransport {
+        SerialTransport {
+            port,
+            config_pin: false,
+        }
+    }
+
+    /// Enables driving the CONFIG pin of the module through the RTS line of the port.
+    /// Only adapters that wire RTS to the CONFIG pin should enable it: on any other adapter the module never
+    /// answers the pin change, so every switch to configuration mode would wait for a prompt that never comes.
+    pub fn with_config_pin(self, config_pin: bool) -> SerialTransport {
+        SerialTransport { config_pin, ..self }
+    }
+}
+
+impl Read for SerialTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buf)
+    }
+}
+
+impl Write for SerialTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+impl DeviceTransport for SerialTransport {
+    fn name(&self) -> Option<String> {
+        self.port.name()
+    }
+
+    fn clear(&mut self, buffer_to_clear: ClearBuffer) -> io::Result<()> {
+        self.port.clear(buffer_to_clear).map_err(io::Error::from)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.port.timeout()
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.port.set_timeout(timeout).map_err(io::Error::from)
+    }
+
+    fn try_clone(&self) -> io::Result<Box<dyn DeviceTransport>> {
+        let port = self.port.try_clone().map_err(io::Error::from)?;
+        Ok(Box::new(
+            SerialTransport::new(port).with_config_pin(self.config_pin),
+        ))
+    }
+
+    /// The CONFIG pin is driven through the RTS line, if the connection was opened `with_config_pin`.
+    /// Otherwise the connection has no CONFIG line and entering configuration mode uses the `0x00` sequence.
+    fn set_config_pin(&mut self, asserted: bool) -> io::Result<()> {
+        if !self.config_pin {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "The CONFIG pin is not enabled for the connection",
+            ));
+        }
+        self.port
+            .write_request_to_send(asserted)
+            .map_err(io::Error::from)
+    }
+}
+
+/// Callback invoked by `MemoryTransport` with every chunk of written bytes.
+/// The returned bytes are queued as if the device had sent them back.
+pub type MemoryResponder = Box<dyn FnMut(&[u8]) -> Vec<u8> + Send>;
+
+#[derive(Default)]
+struct MemoryTransportState {
+    rx: VecDeque<u8>,
+    tx: Vec<u8>,
+    responder: Option<MemoryResponder>,
+    disconnected: bool,
+}
+
+/// In-memory `DeviceTransport` for running the backend without a physical module attached.
+/// Bytes queued with `push_rx` (or produced by the responder) are returned by reads,
+/// and everything written is recorded and can be inspected with `take_tx`.
+/// Clones share the same buffers, like `try_clone` on a serial port.
+#[derive(Clone)]
+pub struct MemoryTransport {
+    name: String,
+    timeout: Duration,
+    state: Arc<Mutex<MemoryTransportState>>,
+}
+
+impl Default for MemoryTransport {
+    fn default() -> Self {
+        MemoryTransport::new()
+    }
+}
+
+impl MemoryTransport {
+    pub fn new() -> MemoryTransport {
+        MemoryTransport {
+            name: "memory".to_string(),
+            timeout: Duration::from_millis(10),
+            state: Default::default(),
+        }
+    }
+
+    /// Creates a transport that answers every write with the bytes returned by `responder`.
+    pub fn with_responder(responder: MemoryResponder) -> MemoryTransport {
+        let transport = MemoryTransport::new();
+        transport.lock_state().responder = Some(responder);
+        transport
+    }
+
+    /// Queues bytes to be read from the transport.
+    pub fn push_rx(&self, bytes: &[u8]) {
+        self.lock_state().rx.extend(bytes);
+    }
+
+    /// Simulates unplugging the device: every following read and write fails with `BrokenPipe`.
+    pub fn disconnect(&self) {
+        self.lock_state().disconnected = true;
+    }
+
+    /// Returns and clears all the bytes written to the transport so far.
+    pub fn take_tx(&self) -> Vec<u8> {
+        std::mem::take(&mut self.lock_state().tx)
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, MemoryTransportState> {
+        // a panicking test must not poison the transport for the other clones
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Read for MemoryTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut state = self.lock_state();
+        if state.disconnected {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "Device disconnected"));
+        }
+        if state.rx.is_empty() {
+            drop(state);
+            // behave like a serial port that waits for data until its timeout expires
+            std::thread::sleep(self.timeout);
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "Operation timed out"));
+        }
+        let count = buf.len().min(state.rx.len());
+        for (slot, byte) in buf.iter_mut().zip(state.rx.drain(..count)) {
+            *slot = byte;
+        }
+        Ok(count)
+    }
+}
+
+impl Write for MemoryTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.lock_state();
+        if state.disconnected {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "Device disconnected"));
+        }
+        state.tx.extend_from_slice(buf);
+        if let Some(responder) = state.responder.as_mut() {
+            let response = responder(buf);
+            state.rx.extend(response);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl DeviceTransport for MemoryTransport {
+    fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+
+    fn clear(&mut self, buffer_to_clear: ClearBuffer) -> io::Result<()> {
+        // written bytes are delivered immediately, so only the input side holds pending data
+        if let ClearBuffer::Input | ClearBuffer::All = buffer_to_clear {
+            self.lock_state().rx.clear();
+        }
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn try_clone(&self) -> io::Result<Box<dyn DeviceTransport>> {
+        Ok(Box::new(self.clone()))
+    }
+}
@@ -1,13 +1,27 @@
+pub mod cell_rules;
+pub mod cell_validation;
+pub mod cell_values;
+pub mod config_diff;
+pub mod config_profile;
 pub mod data_types;
 pub mod device_config_parser;
 pub mod device_calibration_parser;
+pub mod device_emulator;
+pub mod device_protocol;
+pub mod device_transport;
 pub mod input_processing;
 pub mod mk_module_description;
 pub mod module_description_parser;
+pub mod port_arbiter;
+pub mod rmd_lint;
+pub mod rmd_registry;
+pub mod rmd_watcher;
+pub mod rmd_writer;
 
 // Modules containing functions for communicating with Tauri frontend
 pub mod tinymesh_comm_mod;
 pub mod tinymesh_config_mod;
 pub mod tinymesh_calibration_mod;
 pub mod tinymesh_device_info_mod;
+pub mod tinymesh_rmd_mod;
 pub mod tinymesh_serial_util;
@@ -2,12 +2,16 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use std::sync::{Arc, Mutex};
+use tauri::Manager;
 use tauri_plugin_log::{LogTarget, RotationStrategy, TimezoneStrategy};
 use tinymesh_cc_tool::data_types::DeviceEntity;
+use tinymesh_cc_tool::rmd_registry::rmd_directories;
+use tinymesh_cc_tool::rmd_watcher::start_rmd_watcher;
 use tinymesh_cc_tool::tinymesh_comm_mod::*;
 use tinymesh_cc_tool::tinymesh_config_mod::*;
 use tinymesh_cc_tool::tinymesh_calibration_mod::*;
 use tinymesh_cc_tool::tinymesh_device_info_mod::*;
+use tinymesh_cc_tool::tinymesh_rmd_mod::*;
 use tinymesh_cc_tool::tinymesh_serial_util::*;
 
 #[cfg(debug_assertions)]
@@ -27,12 +31,29 @@ fn main() {
         )
         .manage(DeviceEntity {
             port: Default::default(),
+            device_mode: Default::default(),
             rssi_task: Default::default(),
             is_rssi_task_running: Arc::new(Mutex::new(false)),
             communication_task: Default::default(),
             is_communication_task_running: Arc::new(Mutex::new(false)),
             device_config: Arc::new(Mutex::new(None)),
             device_calib: Arc::new(Mutex::new(None)),
+            rmd_registry: Default::default(),
+        })
+        .setup(|app| {
+            // the search directories depend on the app's config and resource directories
+            let rmd_directories = rmd_directories(&app.handle());
+            let device_entity = app.state::<DeviceEntity>();
+            if let Ok(mut rmd_registry) = device_entity.rmd_registry.lock() {
+                rmd_registry.set_directories(rmd_directories);
+            }
+            // reload RMD files edited while the app is running
+            start_rmd_watcher(
+                app.handle(),
+                device_entity.rmd_registry.clone(),
+                device_entity.device_config.clone(),
+            );
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             // communication functions
@@ -41,15 +62,29 @@ fn main() {
             // config functions
             get_device_config,
             set_device_config,
+            set_and_verify_device_config,
+            set_device_typed_value,
+            set_device_engineering_value,
+            check_device_cell_rules,
+            get_config_diff,
+            export_config_diff,
+            export_config_profile,
+            import_config_profile,
+            clone_config_to_devices,
             execute_mode_sequence,
             factory_reset,
+            enter_config_mode,
+            exit_config_mode,
+            get_device_mode,
             // calibration functions
             get_device_calib,
             set_device_calib,
+            set_and_verify_device_calib,
             // serial functions
             reset_program_state,
             get_devices,
             connect_to_device,
+            connect_to_emulated_device,
             disconnect_from_device,
             send_bytes,
             read_bytes,
@@ -63,6 +98,10 @@ fn main() {
             get_device_voltage,
             start_rssi_stream,
             stop_rssi_stream,
+            convert_channel_frequency,
+            // RMD functions
+            lint_rmd_file,
+            list_rmd_models,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
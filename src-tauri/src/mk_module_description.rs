@@ -1,14 +1,62 @@
 //! This module servers as a high-level RMD file parser.
 //! It contains the `MkModuleDescription` struct that holds all the data from the RMD module description.
 use log::info;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 
-use crate::data_types::{MkDeviceCell, MkDeviceQuickMode, MkDeviceTestMode};
-use crate::module_description_parser::parse_module_description;
+use crate::data_types::{
+    DeviceEntity, MkBandPlan, MkCellBits, MkCellCondition, MkCellEnumValue, MkCellRule,
+    MkConditionOperator, MkDeviceCell, MkDeviceQuickMode, MkDeviceTestMode, MkEndianness,
+    MkMultiByte, MkRmdCompatibility, MkRssiConversion,
+};
+use crate::module_description_parser::parse_rmd_document;
+use crate::rmd_registry::MkRmdSelection;
 use std::collections::HashMap;
+use std::fmt;
+
+/// The severity of a problem found in a RMD file
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub enum MkRmdSeverity {
+    /// The RMD file can't be used
+    Error,
+    /// The RMD file can be used, but a value was ignored or replaced by its default
+    Warning,
+}
+
+/// A problem found in a RMD file
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct MkRmdDiagnostic {
+    pub severity: MkRmdSeverity,
+    /// The section the problem was found in
+    pub section: String,
+    /// The line of the section content, if the section is in the RMD file
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for MkRmdDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            MkRmdSeverity::Error => "error",
+            MkRmdSeverity::Warning => "warning",
+        };
+        match self.line {
+            Some(line) => write!(f, "RMD {} at line {}: {}", severity, line, self.message),
+            None => write!(f, "RMD {} in [{}]: {}", severity, self.section, self.message),
+        }
+    }
+}
+
+/// Joins the diagnostics into a message that can be shown to the user, one diagnostic per line.
+pub fn format_rmd_diagnostics(diagnostics: &[MkRmdDiagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
 
 /// This struct holds all the data from the RMD module description.
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug, serde::Serialize)]
 pub struct MkModuleDescription {
     pub device_model: String,
     pub number_of_testmodes: usize,
@@ -22,15 +70,56 @@ pub struct MkModuleDescription {
     pub locked_cells: Vec<usize>,
     pub c_editable_cells: Vec<usize>,
     pub c_locked_cells: Vec<usize>,
+    /// The sections the cell lists above were read from
+    pub cell_list_sections: MkCellListSections,
+
+    pub rssi_conversion: MkRssiConversion,
+    pub band_plan: Option<MkBandPlan>,
+    /// The firmware and hardware versions the RMD file was made for
+    pub compatibility: MkRmdCompatibility,
+
+    /// The non-fatal problems found while parsing
+    pub warnings: Vec<MkRmdDiagnostic>,
+    /// The source line of every section, used for the diagnostics
+    pub section_lines: HashMap<String, usize>,
 
     pub unknown_data: HashMap<String, String>,
 }
 
+/// Two module descriptions are equal if they describe the same module.
+/// Where the description was read from (`cell_list_sections`, `section_lines`) and the warnings are not compared.
+impl PartialEq for MkModuleDescription {
+    fn eq(&self, other: &Self) -> bool {
+        self.device_model == other.device_model
+            && self.number_of_testmodes == other.number_of_testmodes
+            && self.testmodes == other.testmodes
+            && self.number_of_quickmodes == other.number_of_quickmodes
+            && self.quickmodes == other.quickmodes
+            && self.cells == other.cells
+            && self.calibration_cells == other.calibration_cells
+            && self.editable_cells == other.editable_cells
+            && self.locked_cells == other.locked_cells
+            && self.c_editable_cells == other.c_editable_cells
+            && self.c_locked_cells == other.c_locked_cells
+            && self.rssi_conversion == other.rssi_conversion
+            && self.band_plan == other.band_plan
+            && self.compatibility == other.compatibility
+            && self.unknown_data == other.unknown_data
+    }
+}
+
 fn get_number_of_testmodes_and_remove_from_unknown(
     module_description: &mut MkModuleDescription,
 ) -> usize {
     if let Some(number_of_testmodes) = module_description.unknown_data.remove("TESTMODE NUMBER") {
-        return number_of_testmodes.parse::<usize>().unwrap();
+        if let Ok(number_of_testmodes) = number_of_testmodes.trim().parse::<usize>() {
+            return number_of_testmodes;
+        }
+        module_description.report(
+            MkRmdSeverity::Error,
+            "TESTMODE NUMBER",
+            format!("TESTMODE NUMBER '{}' is not a number", number_of_testmodes),
+        );
     }
     return 0;
 }
@@ -39,81 +128,105 @@ fn get_number_of_quickmodes_and_remove_from_unknown(
     module_description: &mut MkModuleDescription,
 ) -> usize {
     if let Some(number_of_quickmodes) = module_description.unknown_data.remove("QUICKMODE NUMBER") {
-        return number_of_quickmodes.parse::<usize>().unwrap();
+        if let Ok(number_of_quickmodes) = number_of_quickmodes.trim().parse::<usize>() {
+            return number_of_quickmodes;
+        }
+        module_description.report(
+            MkRmdSeverity::Error,
+            "QUICKMODE NUMBER",
+            format!("QUICKMODE NUMBER '{}' is not a number", number_of_quickmodes),
+        );
     }
     return 0;
 }
 
-fn get_editable_cells_and_remove_from_unknown(
+/// The RMD sections the cell lists were read from, `None` if the RMD file has no such list.
+/// The configuration lists may use the `M ` prefixed section names or the legacy Radiocrafts
+/// spellings without prefix (e.g. `[EDITABLE_CELLS]`), the calibration lists always use the `C ` prefix.
+#[derive(Clone, Default, Debug, PartialEq, serde::Serialize)]
+pub struct MkCellListSections {
+    pub editable_cells: Option<String>,
+    pub locked_cells: Option<String>,
+    pub c_editable_cells: Option<String>,
+    pub c_locked_cells: Option<String>,
+}
+
+/// Removes the first of `section_names` found in the unknown data and parses it as a list of addresses.
+/// The other section names are removed as well, so they don't end up in the unknown data.
+fn get_cell_list_and_remove_from_unknown(
     module_description: &mut MkModuleDescription,
-) -> Vec<usize> {
-    if let Some(editable_cells) = module_description.unknown_data.remove("M EDITABLE_CELLS") {
-        return editable_cells
-            .split_whitespace()
-            .filter_map(|s| {
-                if s.starts_with("0x") {
+    section_names: &[&str],
+) -> (Vec<usize>, Option<String>) {
+    let mut result = (vec![], None);
+    for section_name in section_names {
+        if let Some(cell_list) = module_description.unknown_data.remove(*section_name) {
+            if let Some(used_section_name) = &result.1 {
+                info!(
+                    "Ignoring [{}], the cell list was already read from [{}]",
+                    section_name, used_section_name
+                );
+                continue;
+            }
+            let mut cells = vec![];
+            for s in cell_list.split_whitespace() {
+                let address = if s.starts_with("0x") {
                     usize::from_str_radix(&s.trim_start_matches("0x"), 16).ok()
                 } else {
                     s.parse::<usize>().ok()
+                };
+                match address {
+                    Some(address) => cells.push(address),
+                    None => module_description.report(
+                        MkRmdSeverity::Warning,
+                        section_name,
+                        format!("'{}' is not a cell address and is ignored", s),
+                    ),
                 }
-            })
-            .collect();
+            }
+            result = (cells, Some(section_name.to_string()));
+        }
     }
-    return vec![];
+    return result;
+}
+
+fn get_editable_cells_and_remove_from_unknown(
+    module_description: &mut MkModuleDescription,
+) -> Vec<usize> {
+    let (editable_cells, section_name) = get_cell_list_and_remove_from_unknown(
+        module_description,
+        &["M EDITABLE_CELLS", "EDITABLE_CELLS"],
+    );
+    module_description.cell_list_sections.editable_cells = section_name;
+    return editable_cells;
 }
 
 fn get_locked_cells_and_remove_from_unknown(
     module_description: &mut MkModuleDescription,
 ) -> Vec<usize> {
-    if let Some(locked_cells) = module_description.unknown_data.remove("M LOCKED_CELLS") {
-        return locked_cells
-            .split_whitespace()
-            .filter_map(|s| {
-                if s.starts_with("0x") {
-                    usize::from_str_radix(&s.trim_start_matches("0x"), 16).ok()
-                } else {
-                    s.parse::<usize>().ok()
-                }
-            })
-            .collect();
-    }
-    return vec![];
+    let (locked_cells, section_name) = get_cell_list_and_remove_from_unknown(
+        module_description,
+        &["M LOCKED_CELLS", "LOCKED_CELLS"],
+    );
+    module_description.cell_list_sections.locked_cells = section_name;
+    return locked_cells;
 }
 
 fn get_c_editable_cells_and_remove_from_unknown(
     module_description: &mut MkModuleDescription,
 ) -> Vec<usize> {
-    if let Some(c_editable_cells) = module_description.unknown_data.remove("C EDITABLE_CELLS") {
-        return c_editable_cells
-            .split_whitespace()
-            .filter_map(|s| {
-                if s.starts_with("0x") {
-                    usize::from_str_radix(&s.trim_start_matches("0x"), 16).ok()
-                } else {
-                    s.parse::<usize>().ok()
-                }
-            })
-            .collect();
-    }
-    return vec![];
+    let (c_editable_cells, section_name) =
+        get_cell_list_and_remove_from_unknown(module_description, &["C EDITABLE_CELLS"]);
+    module_description.cell_list_sections.c_editable_cells = section_name;
+    return c_editable_cells;
 }
 
 fn get_c_locked_cells_and_remove_from_unknown(
     module_description: &mut MkModuleDescription,
 ) -> Vec<usize> {
-    if let Some(c_locked_cells) = module_description.unknown_data.remove("C LOCKED_CELLS") {
-        return c_locked_cells
-            .split_whitespace()
-            .filter_map(|s| {
-                if s.starts_with("0x") {
-                    usize::from_str_radix(&s.trim_start_matches("0x"), 16).ok()
-                } else {
-                    s.parse::<usize>().ok()
-                }
-            })
-            .collect();
-    }
-    return vec![];
+    let (c_locked_cells, section_name) =
+        get_cell_list_and_remove_from_unknown(module_description, &["C LOCKED_CELLS"]);
+    module_description.cell_list_sections.c_locked_cells = section_name;
+    return c_locked_cells;
 }
 
 fn get_device_model_and_remove_from_unknown(
@@ -122,9 +235,145 @@ fn get_device_model_and_remove_from_unknown(
     if let Some(device_model) = module_description.unknown_data.remove("DEVICE_MODEL") {
         return device_model.clone();
     }
+    module_description.report(
+        MkRmdSeverity::Warning,
+        "DEVICE_MODEL",
+        "The RMD file has no DEVICE_MODEL".to_string(),
+    );
     return String::new();
 }
 
+/// Removes the section from the unknown data and parses it as a number.
+/// A value that can't be parsed is reported as a warning and ignored.
+fn get_number_and_remove_from_unknown<T: std::str::FromStr>(
+    module_description: &mut MkModuleDescription,
+    section_name: &str,
+) -> Option<T> {
+    let value = module_description.unknown_data.remove(section_name)?;
+    let number = value.trim().parse::<T>().ok();
+    if number.is_none() {
+        module_description.report(
+            MkRmdSeverity::Warning,
+            section_name,
+            format!("{} '{}' is not a valid number and is ignored", section_name, value),
+        );
+    }
+    return number;
+}
+
+fn get_rssi_conversion_and_remove_from_unknown(
+    module_description: &mut MkModuleDescription,
+) -> MkRssiConversion {
+    let mut result = MkRssiConversion::default();
+    if let Some(offset) = get_number_and_remove_from_unknown(module_description, "RSSI_OFFSET") {
+        result.offset = offset;
+    }
+    if let Some(scale_factor) =
+        get_number_and_remove_from_unknown(module_description, "RSSI_SCALE_FACTOR")
+    {
+        result.scale_factor = scale_factor;
+    }
+    result.saturation = get_number_and_remove_from_unknown(module_description, "RSSI_SATURATION");
+    return result;
+}
+
+fn get_band_plan_and_remove_from_unknown(
+    module_description: &mut MkModuleDescription,
+) -> Option<MkBandPlan> {
+    let has_band_plan = module_description
+        .unknown_data
+        .contains_key("FREQUENCY_CHANNEL_FIRST_CENTER")
+        || module_description
+            .unknown_data
+            .contains_key("FREQUENCY_CHANNEL_WIDTH");
+    let first_center_mhz =
+        get_number_and_remove_from_unknown(module_description, "FREQUENCY_CHANNEL_FIRST_CENTER");
+    let channel_width_khz =
+        get_number_and_remove_from_unknown(module_description, "FREQUENCY_CHANNEL_WIDTH");
+    // the channels are the range of the RF Channel cell, so the cells must be parsed first
+    let channel_cell = module_description
+        .cells
+        .iter()
+        .find(|cell| cell.name == "RF Channel");
+    if let (Some(first_center_mhz), Some(channel_width_khz), Some(channel_cell)) =
+        (first_center_mhz, channel_width_khz, channel_cell)
+    {
+        if channel_cell.min_value > channel_cell.max_value {
+            module_description.report(
+                MkRmdSeverity::Warning,
+                "FREQUENCY_CHANNEL_FIRST_CENTER",
+                "The band plan needs a RF Channel MIN_MAX with a minimum not above its maximum, and is ignored".to_string(),
+            );
+            return None;
+        }
+        return Some(MkBandPlan {
+            first_center_mhz,
+            channel_width_khz,
+            first_channel: channel_cell.min_value,
+            last_channel: channel_cell.max_value,
+        });
+    }
+    if has_band_plan {
+        module_description.report(
+            MkRmdSeverity::Warning,
+            "FREQUENCY_CHANNEL_FIRST_CENTER",
+            "The band plan needs FREQUENCY_CHANNEL_FIRST_CENTER, FREQUENCY_CHANNEL_WIDTH and a RF Channel cell, and is ignored".to_string(),
+        );
+    }
+    return None;
+}
+
+/// This function reads the `FIRMWARE_VERSION_MIN_MAX` and `HW_VERSIONS` sections of a RMD file.
+/// It only needs the sections, so the RMD registry can use it without parsing the whole module description.
+///
+/// # Arguments
+/// * `sections` - The sections of the RMD file, as returned by `parse_module_description`
+///
+/// # Returns
+/// The compatibility, and the problems found as pairs of section name and message. Invalid sections are ignored.
+pub fn parse_compatibility(
+    sections: &HashMap<String, String>,
+) -> (MkRmdCompatibility, Vec<(&'static str, String)>) {
+    let mut result = MkRmdCompatibility::default();
+    let mut problems = vec![];
+    if let Some(firmware_versions) = sections.get("FIRMWARE_VERSION_MIN_MAX") {
+        let parts: Vec<&str> = firmware_versions.split_whitespace().collect();
+        if parts.len() == 2 {
+            result.firmware_versions = Some((parts[0].to_string(), parts[1].to_string()));
+        } else {
+            problems.push((
+                "FIRMWARE_VERSION_MIN_MAX",
+                format!(
+                    "FIRMWARE_VERSION_MIN_MAX '{}' needs two versions and is ignored",
+                    firmware_versions
+                ),
+            ));
+        }
+    }
+    if let Some(hw_versions) = sections.get("HW_VERSIONS") {
+        result.hw_versions = hw_versions.split_whitespace().map(str::to_string).collect();
+    }
+    return (result, problems);
+}
+
+fn get_compatibility_and_remove_from_unknown(
+    module_description: &mut MkModuleDescription,
+) -> MkRmdCompatibility {
+    let (compatibility, problems) = parse_compatibility(&module_description.unknown_data);
+    for (section_name, message) in problems {
+        module_description.report(MkRmdSeverity::Warning, section_name, message);
+    }
+    module_description.unknown_data.remove("FIRMWARE_VERSION_MIN_MAX");
+    module_description.unknown_data.remove("HW_VERSIONS");
+    return compatibility;
+}
+
+/// The attribute sections of a configuration or calibration cell
+pub const CELL_ATTRIBUTES: [&str; 14] = [
+    "NAME", "HINT", "DEF", "MIN_MAX", "ALLOW", "SIZE", "ENDIAN", "BITS", "ENUM", "SCALE", "OFFSET", "UNIT",
+    "ACTIVE_IF", "REQUIRE",
+];
+
 fn check_cell_key(starting_str: &str, input: &str) -> Result<(usize, String), ()> {
     let parts: Vec<&str> = input.split_whitespace().collect();
     if parts.len() == 3 && parts[0] == starting_str && parts[1].starts_with("0x") {
@@ -135,99 +384,376 @@ fn check_cell_key(starting_str: &str, input: &str) -> Result<(usize, String), ()
     Err(())
 }
 
-fn get_cells_and_remove_from_unknown(
-    module_description: &mut MkModuleDescription,
-) -> Vec<MkDeviceCell> {
-    info!("\n\nmk_module_description::get_cells_and_remove_from_unknown(module_description)\n");
-    // info!("\nmk_module_description::get_cells_and_remove_from_unknown---> module_description = {:?}\n", module_description);
-
-    // find all keys of the format "M 0x<some hex number> <some text>"
-    let mut result: Vec<MkDeviceCell> = vec![Default::default(); 256];
-    for i in 0..result.len() {
-        result[i].address = i;
+/// This function parses a single attribute section of a cell (e.g. `[M 0x01 MIN_MAX]`) into the cell.
+///
+/// # Returns
+/// An `Ok(())` if the value was parsed, or the message of the problem.
+fn parse_cell_attribute(cell: &mut MkDeviceCell, attribute: &str, value: &str) -> Result<(), String> {
+    let parse_value = |s: &str| {
+        s.parse::<u8>().map_err(|_| {
+            format!(
+                "{} '{}' contains '{}', which is not a value between 0 and 255",
+                attribute, value, s
+            )
+        })
+    };
+    if attribute == "NAME" {
+        cell.name = value.to_string();
+    } else if attribute == "HINT" {
+        cell.description = value.to_string();
+    } else if attribute == "DEF" {
+        cell.default_value = parse_value(value.trim())?;
+        cell.current_value = cell.default_value;
+    } else if attribute == "MIN_MAX" {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        if parts.len() != 2 {
+            return Err(format!("MIN_MAX '{}' needs two values", value));
+        }
+        cell.min_value = parse_value(parts[0])?;
+        cell.max_value = parse_value(parts[1])?;
+    } else if attribute == "ALLOW" {
+        cell.allowed_values = value
+            .split_whitespace()
+            .map(parse_value)
+            .collect::<Result<Vec<u8>, String>>()?;
+    } else if attribute == "SIZE" {
+        let size = match value.trim().parse::<usize>() {
+            Ok(size) if (2..=4).contains(&size) => size,
+            _ => return Err(format!("SIZE '{}' is not a number between 2 and 4", value)),
+        };
+        let endianness = cell.multi_byte.map(|multi_byte| multi_byte.endianness).unwrap_or_default();
+        cell.multi_byte = Some(MkMultiByte { size, endianness });
+    } else if attribute == "ENDIAN" {
+        let endianness = match value.trim() {
+            "BIG" => MkEndianness::Big,
+            "LITTLE" => MkEndianness::Little,
+            _ => return Err(format!("ENDIAN '{}' is neither BIG nor LITTLE", value)),
+        };
+        // the size is checked once all the attributes of the cell are parsed
+        let size = cell.multi_byte.map(|multi_byte| multi_byte.size).unwrap_or(1);
+        cell.multi_byte = Some(MkMultiByte { size, endianness });
+    } else if attribute == "BITS" {
+        cell.bits = value
+            .lines()
+            .map(parse_cell_bits)
+            .collect::<Result<Vec<MkCellBits>, String>>()?;
+    } else if attribute == "ENUM" {
+        cell.enum_values = value
+            .lines()
+            .map(parse_cell_enum_value)
+            .collect::<Result<Vec<MkCellEnumValue>, String>>()?;
+    } else if attribute == "SCALE" || attribute == "OFFSET" {
+        let number = value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+            .ok_or(format!("{} '{}' is not a number", attribute, value))?;
+        let scaling = cell.scaling.get_or_insert_with(Default::default);
+        if attribute == "OFFSET" {
+            scaling.offset = number;
+        } else if number == 0.0 {
+            return Err("SCALE must not be 0".to_string());
+        } else {
+            scaling.scale = number;
+        }
+    } else if attribute == "UNIT" {
+        cell.scaling.get_or_insert_with(Default::default).unit = Some(value.trim().to_string());
+    } else if attribute == "ACTIVE_IF" {
+        let mut conditions = vec![];
+        for line in value.lines() {
+            conditions.extend(parse_cell_conditions(attribute, line, line)?);
+        }
+        cell.active_if = conditions;
+    } else if attribute == "REQUIRE" {
+        cell.rules = value
+            .lines()
+            .map(parse_cell_rule)
+            .collect::<Result<Vec<MkCellRule>, String>>()?;
     }
-    for (key, value) in &module_description.unknown_data {
-        // use regex matching on key. the format of the key is "M 0x<some hex number> <some text>"
-        // we want to extract the hex number and some text
-        if let Ok((address, name)) = check_cell_key("M", key) {
-            // resize vector to fit the address
-            if address >= result.len() {
-                result.resize(address as usize + 1, Default::default());
-                result[address as usize].address = address;
+    Ok(())
+}
+
+/// This function parses a line of a `REQUIRE` section, i.e. conditions that must hold,
+/// optionally followed by `IF` and the conditions under which they must hold (e.g. `0x0E != 3 IF 0x5D == 1`).
+fn parse_cell_rule(line: &str) -> Result<MkCellRule, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (conditions, when) = match words.iter().position(|word| *word == "IF") {
+        Some(position) => (words[..position].join(" "), words[position + 1..].join(" ")),
+        None => (words.join(" "), String::new()),
+    };
+    let when = if when.is_empty() && words.contains(&"IF") {
+        return Err(format!("REQUIRE '{}' has no condition after IF", line));
+    } else if when.is_empty() {
+        vec![]
+    } else {
+        parse_cell_conditions("REQUIRE", line, &when)?
+    };
+    Ok(MkCellRule {
+        conditions: parse_cell_conditions("REQUIRE", line, &conditions)?,
+        when,
+    })
+}
+
+/// This function parses conditions joined by `AND`, e.g. `0x0E == 2 AND 0x03 != 0`.
+///
+/// # Arguments
+/// * `attribute` - The attribute of the section, for the messages
+/// * `line` - The line of the section, for the messages
+/// * `text` - The conditions
+fn parse_cell_conditions(
+    attribute: &str,
+    line: &str,
+    text: &str,
+) -> Result<Vec<MkCellCondition>, String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut result = vec![];
+    for condition in words.split(|word| *word == "AND") {
+        let (address, operator, value) = match condition {
+            [address, operator, value] => (address, operator, value),
+            _ => {
+                return Err(format!(
+                    "{} '{}' contains '{}', which is not a condition like '0x0E == 2'",
+                    attribute,
+                    line.trim(),
+                    condition.join(" ")
+                ))
             }
-            if name == "NAME" {
-                result[address as usize].name = value.clone();
-            } else if name == "HINT" {
-                result[address as usize].description = value.clone();
-            } else if name == "DEF" {
-                result[address as usize].default_value = value.parse::<u8>().unwrap();
-                result[address as usize].current_value = result[address as usize].default_value;
-            } else if name == "MIN_MAX" {
-                let (min, max) = value.split_once(' ').unwrap();
-                result[address as usize].min_value = min.parse::<u8>().unwrap();
-                result[address as usize].max_value = max.parse::<u8>().unwrap();
-            } else if name == "ALLOW" {
-                result[address as usize].allowed_values = value
-                    .split_whitespace()
-                    .map(|s| s.parse::<u8>().unwrap())
-                    .collect();
+        };
+        let address = address
+            .strip_prefix("0x")
+            .and_then(|hex_number| usize::from_str_radix(hex_number, 16).ok())
+            .ok_or(format!(
+                "{} '{}' contains '{}', which is not a cell address",
+                attribute,
+                line.trim(),
+                address
+            ))?;
+        let operator = MkConditionOperator::ALL
+            .iter()
+            .find(|(spelling, _)| spelling == operator)
+            .map(|(_, operator)| *operator)
+            .ok_or(format!(
+                "{} '{}' contains '{}', which is not one of == != < <= > >=",
+                attribute,
+                line.trim(),
+                operator
+            ))?;
+        let parsed_value = match value.strip_prefix("0x") {
+            Some(hex_number) => u8::from_str_radix(hex_number, 16).ok(),
+            None => value.parse::<u8>().ok(),
+        };
+        let value = parsed_value.ok_or(format!(
+            "{} '{}' contains '{}', which is not a value between 0 and 255",
+            attribute,
+            line.trim(),
+            value
+        ))?;
+        result.push(MkCellCondition {
+            address,
+            operator,
+            value,
+        });
+    }
+    Ok(result)
+}
+
+/// This function parses a line of a `BITS` section, i.e. a bit or an inclusive range of bits and its name
+/// (e.g. `0 Sleep enable` or `4-7 Wake-up interval`).
+fn parse_cell_bits(line: &str) -> Result<MkCellBits, String> {
+    let (bits, name) = line.trim().split_once(char::is_whitespace).unwrap_or((line.trim(), ""));
+    let (first_bit, last_bit) = bits.split_once('-').unwrap_or((bits, bits));
+    let parse_bit = |s: &str| match s.parse::<u8>() {
+        Ok(bit) if bit < 8 => Ok(bit),
+        _ => Err(format!("BITS '{}' contains '{}', which is not a bit between 0 and 7", line, s)),
+    };
+    let (first_bit, last_bit) = (parse_bit(first_bit)?, parse_bit(last_bit)?);
+    if first_bit > last_bit {
+        return Err(format!("BITS '{}' has a first bit above its last bit", line));
+    }
+    if name.trim().is_empty() {
+        return Err(format!("BITS '{}' has no name", line));
+    }
+    Ok(MkCellBits {
+        name: name.trim().to_string(),
+        first_bit,
+        last_bit,
+    })
+}
+
+/// This function parses a line of an `ENUM` section, i.e. a value, its label and optionally its unit,
+/// separated by colons (e.g. `1 : -20 : dBm` or `0 : Packet mode`).
+fn parse_cell_enum_value(line: &str) -> Result<MkCellEnumValue, String> {
+    let parts: Vec<&str> = line.split(':').map(str::trim).collect();
+    if parts.len() < 2 || parts.len() > 3 || parts[1].is_empty() {
+        return Err(format!("ENUM '{}' is not of the form 'value : label : unit'", line));
+    }
+    let value = parts[0].parse::<u8>().map_err(|_| {
+        format!(
+            "ENUM '{}' contains '{}', which is not a value between 0 and 255",
+            line, parts[0]
+        )
+    })?;
+    Ok(MkCellEnumValue {
+        value,
+        label: parts[1].to_string(),
+        unit: parts.get(2).filter(|unit| !unit.is_empty()).map(|unit| unit.to_string()),
+    })
+}
+
+/// This function extracts the `N : label` table of a cell HINT, for RMD files without `ENUM` sections, e.g.
+/// ```text
+/// 1 :  -20 dBm
+/// 5 :   11 dBm (Default)
+/// ```
+/// Tables with several columns separated by tabs are read column by column. Labels made of a number and
+/// a unit are split into both, and the `(Default)` note is left out.
+///
+/// # Arguments
+/// * `hint` - The HINT of the cell
+///
+/// # Returns
+/// The values of the table, or an empty vector if the HINT has no table of at least two distinct values.
+pub fn parse_hint_enum_values(hint: &str) -> Vec<MkCellEnumValue> {
+    // the values and the words of their labels
+    let mut entries: Vec<(u8, Vec<&str>)> = vec![];
+    for line in hint.lines() {
+        let mut in_entry = false;
+        for column in line.split('\t') {
+            let entry = column
+                .split_once(':')
+                .and_then(|(value, label)| Some((value.trim().parse::<u8>().ok()?, label)));
+            if let Some((value, label)) = entry {
+                if entries.iter().any(|(other, _)| *other == value) {
+                    return vec![];
+                }
+                entries.push((value, label.split_whitespace().collect()));
+                in_entry = true;
+            } else if in_entry {
+                // e.g. the unit of "1 :\t1 200\tbit/s"
+                if let Some((_, words)) = entries.last_mut() {
+                    words.extend(column.split_whitespace());
+                }
             }
         }
     }
 
-    // remove all keys starting from "M "
-    module_description
-        .unknown_data
-        .retain(|k, _| !k.starts_with("M "));
-    // info!("\nmk_module_description::get_cells_and_remove_from_unknown---> result = {:?}\n", result);
+    let is_number = |word: &&str| word.chars().all(|c| c.is_ascii_digit() || "+-.,".contains(c));
+    let mut result: Vec<MkCellEnumValue> = entries
+        .into_iter()
+        .filter_map(|(value, mut words)| {
+            words.retain(|word| *word != "(Default)");
+            let (label, unit) = match words.split_last() {
+                Some((unit, number))
+                    if !number.is_empty() && number.iter().all(is_number) && !is_number(unit) =>
+                {
+                    (number.join(" "), Some(unit.to_string()))
+                }
+                _ => (words.join(" "), None),
+            };
+            if label.is_empty() {
+                return None;
+            }
+            Some(MkCellEnumValue { value, label, unit })
+        })
+        .collect();
+    if result.len() < 2 {
+        return vec![];
+    }
+    result.sort_by_key(|enum_value| enum_value.value);
     return result;
 }
 
-fn get_calibration_cells_and_remove_from_unknown(
+/// This function parses the cells of the configuration (`prefix` "M") or calibration (`prefix` "C") memory
+/// and removes all the keys starting with the prefix from the unknown data.
+fn get_cells_and_remove_from_unknown(
     module_description: &mut MkModuleDescription,
+    prefix: &str,
 ) -> Vec<MkDeviceCell> {
-    info!("\n\nmk_module_description::get_calibration_cells_and_remove_from_unknown(module_description)\n");
-    // info!("\nmk_module_description::get_calibration_cells_and_remove_from_unknown---> module_description = {:?}\n", module_description);
+    info!("\n\nmk_module_description::get_cells_and_remove_from_unknown(module_description, {})\n", prefix);
 
-    // find all keys of the format "C 0x<some hex number> <some text>"
+    // find all keys of the format "<prefix> 0x<some hex number> <some text>"
     let mut result: Vec<MkDeviceCell> = vec![Default::default(); 256];
     for i in 0..result.len() {
         result[i].address = i;
     }
-    for (key, value) in &module_description.unknown_data {
-        // use regex matching on key. the format of the key is "C 0x<some hex number> <some text>"
+    let key_prefix = format!("{} ", prefix);
+    let mut keys: Vec<String> = module_description
+        .unknown_data
+        .keys()
+        .filter(|key| key.starts_with(&key_prefix))
+        .cloned()
+        .collect();
+    // report the problems in the order of the file
+    keys.sort_by_key(|key| module_description.section_lines.get(key).copied());
+    // the ENDIAN and BITS sections, checked against the other attributes of the cell below
+    let mut type_keys: HashMap<(usize, String), String> = HashMap::new();
+    for key in keys {
+        let value = module_description.unknown_data.remove(&key).unwrap_or_default();
+        // the format of the key is "<prefix> 0x<some hex number> <some text>"
         // we want to extract the hex number and some text
-        if let Ok((address, name)) = check_cell_key("C", key) {
-            // resize vector to fit the address
-            if address >= result.len() {
-                result.resize(address as usize + 1, Default::default());
-                result[address as usize].address = address;
-            }
-            if name == "NAME" {
-                result[address as usize].name = value.clone();
-            } else if name == "HINT" {
-                result[address as usize].description = value.clone();
-            } else if name == "DEF" {
-                result[address as usize].default_value = value.parse::<u8>().unwrap();
-                result[address as usize].current_value = result[address as usize].default_value;
-            } else if name == "MIN_MAX" {
-                let (min, max) = value.split_once(' ').unwrap();
-                result[address as usize].min_value = min.parse::<u8>().unwrap();
-                result[address as usize].max_value = max.parse::<u8>().unwrap();
-            } else if name == "ALLOW" {
-                result[address as usize].allowed_values = value
-                    .split_whitespace()
-                    .map(|s| s.parse::<u8>().unwrap())
-                    .collect();
+        let (address, name) = match check_cell_key(prefix, &key) {
+            Ok(cell_key) => cell_key,
+            Err(()) => {
+                module_description.report(
+                    MkRmdSeverity::Warning,
+                    &key,
+                    format!("[{}] is not a cell section and is ignored", key),
+                );
+                continue;
             }
+        };
+        if !CELL_ATTRIBUTES.contains(&name.as_str()) {
+            module_description.report(
+                MkRmdSeverity::Warning,
+                &key,
+                format!("{} is not a cell attribute and is ignored", name),
+            );
+            continue;
+        }
+        // resize vector to fit the address
+        if address >= result.len() {
+            result.resize(address + 1, Default::default());
+            result[address].address = address;
+        }
+        if let Err(message) = parse_cell_attribute(&mut result[address], &name, &value) {
+            module_description.report(MkRmdSeverity::Error, &key, message);
+        } else if name == "MIN_MAX" && result[address].min_value > result[address].max_value {
+            module_description.report(
+                MkRmdSeverity::Warning,
+                &key,
+                format!("MIN_MAX '{}' has a minimum above its maximum", value),
+            );
+        } else if name == "ENDIAN" || name == "BITS" {
+            type_keys.insert((address, name), key);
         }
     }
 
-    // remove all keys starting from "C "
-    module_description
-        .unknown_data
-        .retain(|k, _| !k.starts_with("C "));
-    // info!("\nmk_module_description::get_calibration_cells_and_remove_from_unknown---> result = {:?}\n", result);
+    for cell in result.iter_mut() {
+        if cell.enum_values.is_empty() {
+            cell.enum_values = parse_hint_enum_values(&cell.description);
+        }
+        let multi_byte = cell.multi_byte;
+        if multi_byte.is_some_and(|multi_byte| multi_byte.size < 2) {
+            cell.multi_byte = None;
+            if let Some(key) = type_keys.get(&(cell.address, "ENDIAN".to_string())) {
+                module_description.report(
+                    MkRmdSeverity::Warning,
+                    key,
+                    "ENDIAN is ignored, the cell has no SIZE".to_string(),
+                );
+            }
+        } else if multi_byte.is_some() && !cell.bits.is_empty() {
+            cell.bits.clear();
+            if let Some(key) = type_keys.get(&(cell.address, "BITS".to_string())) {
+                module_description.report(
+                    MkRmdSeverity::Warning,
+                    key,
+                    "BITS is ignored, the cell spans several addresses".to_string(),
+                );
+            }
+        }
+    }
     return result;
 }
 
@@ -299,66 +825,101 @@ impl MkModuleDescription {
     /// * `input` - The RMD string to parse.
     ///
     /// # Returns
-    /// A `MkModuleDescription` struct containing the parsed data.
-    pub fn new(input: &str) -> MkModuleDescription {
+    /// A `Result` containing the `MkModuleDescription` struct with the parsed data and the warnings,
+    /// or all the diagnostics (errors and warnings) if the RMD string has errors.
+    pub fn new(input: &str) -> Result<MkModuleDescription, Vec<MkRmdDiagnostic>> {
         let mut result: MkModuleDescription = Default::default();
-        result.unknown_data = parse_module_description(input);
+        let document = parse_rmd_document(input);
+        result.unknown_data = document.to_map();
+        result.section_lines = document.section_lines();
         // info!("\n\nUNKNOWN DATA --->   {:?}\n\n", result.unknown_data);?
         result.editable_cells = get_editable_cells_and_remove_from_unknown(&mut result);
         result.locked_cells = get_locked_cells_and_remove_from_unknown(&mut result);
         result.c_editable_cells = get_c_editable_cells_and_remove_from_unknown(&mut result);
         result.c_locked_cells = get_c_locked_cells_and_remove_from_unknown(&mut result);
+        info!("Cell lists read from {:?}", result.cell_list_sections);
         result.device_model = get_device_model_and_remove_from_unknown(&mut result);
+        result.compatibility = get_compatibility_and_remove_from_unknown(&mut result);
+        result.rssi_conversion = get_rssi_conversion_and_remove_from_unknown(&mut result);
         result.number_of_testmodes = get_number_of_testmodes_and_remove_from_unknown(&mut result);
         result.testmodes = get_testmodes_and_remove_from_unknown(&mut result);
         result.number_of_quickmodes = get_number_of_quickmodes_and_remove_from_unknown(&mut result);
         result.quickmodes = get_quick_modes_and_remove_from_unknown(&mut result);
-        result.cells = get_cells_and_remove_from_unknown(&mut result);
-        result.calibration_cells = get_calibration_cells_and_remove_from_unknown(&mut result);
-        result
+        result.cells = get_cells_and_remove_from_unknown(&mut result, "M");
+        result.calibration_cells = get_cells_and_remove_from_unknown(&mut result, "C");
+        result.band_plan = get_band_plan_and_remove_from_unknown(&mut result);
+        if result
+            .warnings
+            .iter()
+            .any(|diagnostic| diagnostic.severity == MkRmdSeverity::Error)
+        {
+            return Err(result.warnings);
+        }
+        for warning in &result.warnings {
+            info!("{}", warning);
+        }
+        Ok(result)
+    }
+
+    /// Adds a diagnostic for the section, at the line of the section.
+    fn report(&mut self, severity: MkRmdSeverity, section: &str, message: String) {
+        self.warnings.push(MkRmdDiagnostic {
+            severity,
+            section: section.to_string(),
+            line: self.section_lines.get(section).copied(),
+            message,
+        });
+    }
+
+    /// Creates a new MkModuleDescription from the RMD file that matches the device best
+    /// (see `RmdRegistry::select_file`).
+    ///
+    /// # Arguments
+    /// * `model` - The model of the device.
+    /// * `hw_version` - The hardware revision of the device.
+    /// * `firmware_version` - The firmware version of the device.
+    /// * `app_handle` - The app handle (reference passed manually to access the RMD registry).
+    ///
+    /// # Returns
+    /// The module description and the selected RMD file, with a warning if the file was not made for the
+    /// versions of the device, or a `String` if no RMD file describes the model or the file has errors.
+    pub fn new_from_device_information(
+        model: &str,
+        hw_version: &str,
+        firmware_version: &str,
+        app_handle: &AppHandle,
+    ) -> Result<(MkModuleDescription, MkRmdSelection), String> {
+        let device_entity = app_handle.state::<DeviceEntity>();
+        let mut rmd_registry = device_entity
+            .rmd_registry
+            .lock()
+            .map_err(|err| err.to_string())?;
+        rmd_registry.get_module_description_for_device(model, hw_version, firmware_version)
     }
 
-    /// Creates a new MkModuleDescription from RMD file.
-    /// Reads the contents of the RMD file matching the model of the device and calls `new`.
+    /// Creates a new MkModuleDescription from the first RMD file of the model, whatever versions it was made for.
+    /// The file is found by the RMD registry of the program (see `rmd_registry`), which indexes
+    /// the RMD files of the module search directories by their `DEVICE_MODEL`.
     ///
     /// # Arguments
     /// * `model` - The model of the device.
-    /// * `app_handle` - The app handle (reference passed manually to resolve RMD file path).
+    /// * `app_handle` - The app handle (reference passed manually to access the RMD registry).
     ///
     /// # Returns
     /// A Result Ok containing a `MkModuleDescription` struct containing the parsed data if the parsing was successful.
-    /// An error of type `String` if the parsing failed.
+    /// An error of type `String` if no RMD file describes the model, or the file could not be read or has errors
+    /// (see `format_rmd_diagnostics`).
     pub fn new_from_device_model(
         model: &str,
-        _app_handle: &AppHandle,
+        app_handle: &AppHandle,
     ) -> Result<MkModuleDescription, String> {
         info!("\nmk_module_description::new_from_device_model(model, app_handle)\n");
 
-        // let file_path = app_handle
-        //     .path_resolver()
-        //     .resolve_resource(format!("/modules/{}.rmd", model))
-        //     .ok_or("File not found".to_string())?;
-        // // info!("\nmk_module_description::new_from_device_model---> File path: {:?}\n", file_path);
-        // let file_contents = std::fs::read_to_string(file_path).map_err(|err| err.to_string())?;
-        // // info!("\nmk_module_description::new_from_device_model---> File contents: {:?}\n", file_contents);
-
-
-        let exe_path = std::env::current_exe().map_err(|err| err.to_string())?;
-    
-        // Construct the path to the resource directory relative to the binary
-        let resource_dir = exe_path
-            .parent()
-            .ok_or("Failed to determine parent directory of executable".to_string())?
-            .join("modules");
-    
-        // Append the filename to the resource directory path
-        let file_path = resource_dir.join(format!("{}.rmd", model)); // Assuming `model` is defined elsewhere
-        println!("file path --> {:?}", file_path);
-
-        // Read the contents of the file
-        let file_contents = std::fs::read_to_string(&file_path)
-            .map_err(|err| format!("Failed to read file '{}': {}", file_path.display(), err))?;
-
-        Ok(MkModuleDescription::new(&file_contents))
+        let device_entity = app_handle.state::<DeviceEntity>();
+        let mut rmd_registry = device_entity
+            .rmd_registry
+            .lock()
+            .map_err(|err| err.to_string())?;
+        rmd_registry.get_module_description(model)
     }
 }
@@ -1,10 +1,88 @@
 //! This is a low-level parser module contains code that parses the module description from the RMD string.
+//! The RMD string is parsed into a `MkRmdDocument`, an ordered list of its sections with their source lines
+//! and comments. The key-value map used by `mk_module_description` is derived from the document.
 use std::collections::HashMap;
 
-/// Parses the module description from the RMD string.
+/// The lines of a section in the RMD string, 1-based and inclusive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MkRmdSpan {
+    /// The line of the section name
+    pub start_line: usize,
+    /// The last content line, or the line of the section name if the section is empty
+    pub end_line: usize,
+}
+
+/// A section of the RMD string, in the order of the file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MkRmdSection {
+    /// The name between the square brackets, empty for the `[]` sections that only end the previous section
+    pub name: String,
+    /// The content without comments and blank lines
+    pub content: String,
+    pub span: MkRmdSpan,
+    /// The first content line, `None` if the section is empty
+    pub content_line: Option<usize>,
+    /// The comment lines between the previous section and the section name, as written in the file.
+    /// Comments at the end of a line are not kept.
+    pub comments: Vec<String>,
+}
+
+/// The RMD string as an ordered list of sections.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MkRmdDocument {
+    pub sections: Vec<MkRmdSection>,
+    /// The comment lines after the last section
+    pub trailing_comments: Vec<String>,
+    /// The lines with content that doesn't belong to any section (before the first section or after `[]`),
+    /// which is ignored
+    pub stray_lines: Vec<usize>,
+}
+
+impl MkRmdDocument {
+    /// Returns the sections as a key-value map of section names and their contents.
+    /// If a section appears more than once, the last one is used. `[]` sections are left out.
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.sections
+            .iter()
+            .filter(|section| !section.name.is_empty())
+            .map(|section| (section.name.clone(), section.content.clone()))
+            .collect()
+    }
+
+    /// Returns the source line of every section, as used by the key-value map.
+    /// The line is the first line of the section content (or the line of the section name, if the section is empty),
+    /// so problems found in the content can be reported at the line that holds it.
+    pub fn section_lines(&self) -> HashMap<String, usize> {
+        self.sections
+            .iter()
+            .filter(|section| !section.name.is_empty())
+            .map(|section| {
+                let line = section.content_line.unwrap_or(section.span.start_line);
+                (section.name.clone(), line)
+            })
+            .collect()
+    }
+
+    /// Returns the sections that repeat an earlier section, each with the first section of that name.
+    pub fn duplicates(&self) -> Vec<(&MkRmdSection, &MkRmdSection)> {
+        let mut first_sections: HashMap<&str, &MkRmdSection> = HashMap::new();
+        let mut result = vec![];
+        for section in self.sections.iter().filter(|section| !section.name.is_empty()) {
+            match first_sections.get(section.name.as_str()) {
+                Some(first_section) => result.push((*first_section, section)),
+                None => {
+                    first_sections.insert(&section.name, section);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Parses the RMD string into a `MkRmdDocument`.
 /// Follows the following rules while parsing:
 /// - Sections are delimited by square brackets.
-/// - Empty sections are ignored, but they are considered as end of previous section content.
+/// - Empty sections (`[]`) have no content, they only end the previous section.
 /// - Anything starting after `//` is considered a comment.
 /// - Blank lines are ignored.
 ///
@@ -12,37 +90,66 @@ use std::collections::HashMap;
 /// `input` - The RMD string to parse.
 ///
 /// # Returns
-/// A key-value map of section names as keys and their contents as values.
-pub fn parse_module_description(input: &str) -> HashMap<String, String> {
-    let mut result = HashMap::new();
-    let mut section_name = String::new();
-    let mut section_content = String::new();
+/// The sections of the RMD string in the order of the file.
+pub fn parse_rmd_document(input: &str) -> MkRmdDocument {
+    let mut result = MkRmdDocument::default();
+    let mut comments = vec![];
 
-    for line in input.lines() {
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
         let line_without_comment = match line.find("//") {
-            Some(index) => &line[..index].trim_end(),
+            Some(index) => line[..index].trim_end(),
             None => line,
         };
 
         if line_without_comment.is_empty() {
+            if line.trim_start().starts_with("//") {
+                comments.push(line.trim_end().to_string());
+            }
             continue; // Ignore empty lines
-        } else if line_without_comment.starts_with("[") {
-            if !section_name.is_empty() {
-                result.insert(section_name.clone(), section_content.trim_end().to_string());
-                section_content.clear();
+        } else if line_without_comment.starts_with('[') {
+            if let Some(section) = result.sections.last_mut() {
+                section.content = section.content.trim_end().to_string();
             }
-            section_name = line_without_comment
-                .trim_matches(|c| c == '[' || c == ']')
-                .to_string();
+            result.sections.push(MkRmdSection {
+                name: line_without_comment
+                    .trim_matches(|c| c == '[' || c == ']')
+                    .to_string(),
+                content: String::new(),
+                span: MkRmdSpan {
+                    start_line: line_number,
+                    end_line: line_number,
+                },
+                content_line: None,
+                comments: std::mem::take(&mut comments),
+            });
         } else {
-            section_content.push_str(line_without_comment);
-            section_content.push('\n');
+            match result.sections.last_mut() {
+                Some(section) if !section.name.is_empty() => {
+                    section.content.push_str(line_without_comment);
+                    section.content.push('\n');
+                    section.span.end_line = line_number;
+                    section.content_line.get_or_insert(line_number);
+                }
+                _ => result.stray_lines.push(line_number),
+            }
         }
     }
 
-    if !section_name.is_empty() && !section_content.is_empty() {
-        result.insert(section_name, section_content.trim_end().to_string());
+    if let Some(section) = result.sections.last_mut() {
+        section.content = section.content.trim_end().to_string();
     }
-
+    result.trailing_comments = comments;
     result
 }
+
+/// Parses the module description from the RMD string, following the rules of `parse_rmd_document`.
+///
+/// # Arguments
+/// `input` - The RMD string to parse.
+///
+/// # Returns
+/// A key-value map of section names as keys and their contents as values.
+pub fn parse_module_description(input: &str) -> HashMap<String, String> {
+    parse_rmd_document(input).to_map()
+}
@@ -0,0 +1,97 @@
+//! This module arbitrates the device connection between the background communication task
+//! and the request / response exchanges of the Tauri commands.
+//! An exchange holds the port for its whole duration through `lock_device`, which suspends the
+//! background reader until the exchange is done, so the reader can never steal the `>` prompt of a response.
+//! The background reader only polls the port while no exchange is holding it.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tauri::AppHandle;
+
+use crate::device_transport::DeviceTransport;
+use crate::tinymesh_serial_util::{
+    read_pending_bytes_with_limits, DeviceError, DEFAULT_MAX_RESPONSE_LENGTH,
+    DEFAULT_PROMPT_TIMEOUT,
+};
+
+/// The device connection shared between the Tauri commands and the background tasks.
+pub type SharedPort = Arc<Mutex<Option<Box<dyn DeviceTransport>>>>;
+
+/// Exclusive access to the connected device, held for the duration of an exchange.
+/// The background reader is suspended until this is dropped.
+pub struct ExclusiveDevice<'a> {
+    guard: MutexGuard<'a, Option<Box<dyn DeviceTransport>>>,
+}
+
+impl Deref for ExclusiveDevice<'_> {
+    type Target = dyn DeviceTransport + 'static;
+
+    fn deref(&self) -> &Self::Target {
+        // `lock_device` only hands out a guard for a connected device
+        self.guard.as_deref().expect("device is connected")
+    }
+}
+
+impl DerefMut for ExclusiveDevice<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard.as_deref_mut().expect("device is connected")
+    }
+}
+
+/// This function takes exclusive access to the connected device for a request / response exchange.
+/// Bytes that arrived while nobody was reading are read first, so they reach the
+/// `exchange_bytes_event` log instead of being mistaken for the response.
+/// The read is bounded like a response, so a module that keeps streaming data cannot stall the exchange.
+///
+/// # Arguments
+/// * `port` - The shared device connection
+/// * `app_handle` - An optional Tauri application handle, used for emitting the pending bytes
+///
+/// # Returns
+/// An `ExclusiveDevice` guard, or `DeviceError::NotConnected` if no device is connected.
+pub fn lock_device<'a>(
+    port: &'a SharedPort,
+    app_handle: Option<&AppHandle>,
+) -> Result<ExclusiveDevice<'a>, DeviceError> {
+    let guard = port
+        .lock()
+        .map_err(|_| DeviceError::PortGone("The device connection is poisoned".to_string()))?;
+    if guard.is_none() {
+        return Err(DeviceError::NotConnected);
+    }
+    let mut device = ExclusiveDevice { guard };
+    read_pending_bytes_with_limits(
+        &mut *device,
+        &mut vec![],
+        DEFAULT_PROMPT_TIMEOUT,
+        DEFAULT_MAX_RESPONSE_LENGTH,
+        app_handle,
+    );
+    Ok(device)
+}
+
+/// This function reads the bytes received by the device in the background and emits them to the log.
+/// It does nothing while an exchange is holding the device, the exchange reads (and logs) the bytes itself.
+///
+/// # Arguments
+/// * `port` - The shared device connection
+/// * `app_handle` - An optional Tauri application handle, used for emitting the read bytes
+///
+/// # Returns
+/// The bytes that were read, empty if the device is busy or not connected.
+pub fn poll_background_bytes(port: &SharedPort, app_handle: Option<&AppHandle>) -> Vec<u8> {
+    let mut buffer = vec![];
+    if let Ok(mut device) = port.try_lock() {
+        if let Some(device) = device.as_mut() {
+            read_pending_bytes_with_limits(
+                device,
+                &mut buffer,
+                DEFAULT_PROMPT_TIMEOUT,
+                DEFAULT_MAX_RESPONSE_LENGTH,
+                app_handle,
+            );
+        }
+    }
+    buffer
+}
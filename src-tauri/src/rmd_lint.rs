@@ -0,0 +1,287 @@
+//! This module contains the RMD file linter.
+//! It finds mistakes in hand-edited RMD files that the parser accepts, but that make the module description
+//! wrong or incomplete, so they are found before the file is used with a device.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::cell_validation::validate_cell_value;
+use crate::mk_module_description::{MkModuleDescription, MkRmdDiagnostic, MkRmdSeverity};
+use crate::module_description_parser::parse_rmd_document;
+
+/// Sections that are part of the RMD format, but are not used by the module description.
+const IGNORED_SECTIONS: [&str; 2] = ["FILE_VERSION", "FILES_SPECIFICATION_VERSION"];
+
+/// This function checks a RMD string for mistakes.
+/// Besides the problems reported by `MkModuleDescription::new`, it reports:
+/// - sections that appear more than once (only the last one is used)
+/// - content outside of any section
+/// - cells with a NAME but neither MIN_MAX nor ALLOW
+/// - cells whose DEF, or a value of their ENUM, is outside MIN_MAX or not in ALLOW
+/// - test modes and quick modes missing from, or beyond, `TESTMODE NUMBER` / `QUICKMODE NUMBER`
+/// - cells that are both editable and locked
+/// - multi-byte cells (`SIZE`) that overlap another multi-byte cell, and named bits (`BITS`) that overlap
+/// - `ACTIVE_IF` and `REQUIRE` conditions on cells that are not described
+/// - sections that are not part of the RMD format
+///
+/// # Arguments
+/// * `input` - The RMD string to check
+///
+/// # Returns
+/// The diagnostics, ordered by line. The RMD string is usable if none of them is an error.
+pub fn lint_rmd(input: &str) -> Vec<MkRmdDiagnostic> {
+    let document = parse_rmd_document(input);
+    let sections = document.to_map();
+    let section_lines = document.section_lines();
+    let diagnostic = |severity: MkRmdSeverity, section: &str, message: String| MkRmdDiagnostic {
+        severity,
+        section: section.to_string(),
+        line: section_lines.get(section).copied(),
+        message,
+    };
+    let mut diagnostics = vec![];
+
+    // sections that appear more than once
+    for (first_section, section) in document.duplicates() {
+        diagnostics.push(MkRmdDiagnostic {
+            severity: MkRmdSeverity::Warning,
+            section: section.name.clone(),
+            line: Some(section.span.start_line),
+            message: format!(
+                "[{}] was already defined at line {}, only the last definition is used",
+                section.name, first_section.span.start_line
+            ),
+        });
+    }
+
+    for line in &document.stray_lines {
+        diagnostics.push(MkRmdDiagnostic {
+            severity: MkRmdSeverity::Warning,
+            section: String::new(),
+            line: Some(*line),
+            message: "The line is not part of a section and is ignored".to_string(),
+        });
+    }
+
+    let module_description = match MkModuleDescription::new(input) {
+        Ok(module_description) => module_description,
+        Err(errors) => {
+            // the content can't be checked further
+            diagnostics.extend(errors);
+            sort_by_line(&mut diagnostics);
+            return diagnostics;
+        }
+    };
+    diagnostics.extend(module_description.warnings.iter().cloned());
+
+    // the attribute sections of every cell, by prefix and address
+    let mut cell_sections: HashMap<(&str, usize), HashMap<&str, &str>> = HashMap::new();
+    for key in sections.keys() {
+        let parts: Vec<&str> = key.split_whitespace().collect();
+        if parts.len() != 3 || !(parts[0] == "M" || parts[0] == "C") {
+            continue;
+        }
+        if let Some(hex_number) = parts[1].strip_prefix("0x") {
+            if let Ok(address) = usize::from_str_radix(hex_number, 16) {
+                cell_sections
+                    .entry((parts[0], address))
+                    .or_default()
+                    .insert(parts[2], key.as_str());
+            }
+        }
+    }
+    for (prefix, cells) in [
+        ("M", &module_description.cells),
+        ("C", &module_description.calibration_cells),
+    ] {
+        for cell in cells.iter() {
+            let attributes = match cell_sections.get(&(prefix, cell.address)) {
+                Some(attributes) if attributes.contains_key("NAME") => attributes,
+                _ => continue,
+            };
+            if !attributes.contains_key("MIN_MAX") && !attributes.contains_key("ALLOW") {
+                diagnostics.push(diagnostic(
+                    MkRmdSeverity::Warning,
+                    attributes["NAME"],
+                    format!("Cell 0x{:02X} ({}) has no MIN_MAX or ALLOW", cell.address, cell.name),
+                ));
+            } else if let Some(def_section) = attributes.get("DEF") {
+                if let Err(err) = validate_cell_value(cell, cell.default_value) {
+                    diagnostics.push(diagnostic(
+                        MkRmdSeverity::Warning,
+                        def_section,
+                        format!("DEF: {}", err),
+                    ));
+                }
+            }
+            // the tables of HINTs often describe values that are not accepted, e.g. reserved values
+            if let Some(enum_section) = attributes.get("ENUM") {
+                for enum_value in &cell.enum_values {
+                    if let Err(err) = validate_cell_value(cell, enum_value.value) {
+                        diagnostics.push(diagnostic(
+                            MkRmdSeverity::Warning,
+                            enum_section,
+                            format!("ENUM: {}", err),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    for (prefix, cells) in [
+        ("M", &module_description.cells),
+        ("C", &module_description.calibration_cells),
+    ] {
+        // the end of the last multi-byte cell, and its address
+        let mut covered: Option<(usize, usize)> = None;
+        for cell in cells.iter() {
+            let section = |attribute: &str| {
+                cell_sections
+                    .get(&(prefix, cell.address))
+                    .and_then(|attributes| attributes.get(attribute))
+                    .copied()
+                    .unwrap_or_default()
+            };
+            if let Some(multi_byte) = &cell.multi_byte {
+                if let Some((end, address)) = covered.filter(|(end, _)| cell.address < *end) {
+                    diagnostics.push(diagnostic(
+                        MkRmdSeverity::Warning,
+                        section("SIZE"),
+                        format!(
+                            "Cell 0x{:02X} ({}) overlaps the {} bytes of cell 0x{:02X}",
+                            cell.address,
+                            cell.name,
+                            end - address,
+                            address
+                        ),
+                    ));
+                }
+                covered = Some((cell.address + multi_byte.size, cell.address));
+            }
+            for (i, bits) in cell.bits.iter().enumerate() {
+                if let Some(other) = cell.bits[..i].iter().find(|other| other.mask() & bits.mask() != 0) {
+                    diagnostics.push(diagnostic(
+                        MkRmdSeverity::Warning,
+                        section("BITS"),
+                        format!(
+                            "Bits '{}' of cell 0x{:02X} overlap bits '{}'",
+                            bits.name, cell.address, other.name
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    for (prefix, cells) in [
+        ("M", &module_description.cells),
+        ("C", &module_description.calibration_cells),
+    ] {
+        for cell in cells.iter() {
+            let rule_conditions = cell
+                .rules
+                .iter()
+                .flat_map(|rule| rule.conditions.iter().chain(&rule.when));
+            for (attribute, condition) in cell
+                .active_if
+                .iter()
+                .map(|condition| ("ACTIVE_IF", condition))
+                .chain(rule_conditions.map(|condition| ("REQUIRE", condition)))
+            {
+                let is_described = cell_sections
+                    .get(&(prefix, condition.address))
+                    .is_some_and(|attributes| attributes.contains_key("NAME"));
+                if !is_described {
+                    let section = cell_sections
+                        .get(&(prefix, cell.address))
+                        .and_then(|attributes| attributes.get(attribute))
+                        .copied()
+                        .unwrap_or_default();
+                    diagnostics.push(diagnostic(
+                        MkRmdSeverity::Warning,
+                        section,
+                        format!(
+                            "{} of cell 0x{:02X} refers to cell 0x{:02X}, which is not described",
+                            attribute, cell.address, condition.address
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    for (kind, number) in [
+        ("TESTMODE", module_description.number_of_testmodes),
+        ("QUICKMODE", module_description.number_of_quickmodes),
+    ] {
+        let defined: HashSet<usize> = sections
+            .keys()
+            .filter_map(|key| {
+                let parts: Vec<&str> = key.split_whitespace().collect();
+                if parts.len() == 3 && parts[0] == kind {
+                    return parts[1].parse::<usize>().ok();
+                }
+                None
+            })
+            .collect();
+        let number_section = format!("{} NUMBER", kind);
+        for i in 1..=number {
+            if !defined.contains(&i) {
+                diagnostics.push(diagnostic(
+                    MkRmdSeverity::Warning,
+                    &number_section,
+                    format!("{} {} is missing, {} is {}", kind, i, number_section, number),
+                ));
+            }
+        }
+        let mut beyond: Vec<&usize> = defined.iter().filter(|i| **i == 0 || **i > number).collect();
+        beyond.sort();
+        for i in beyond {
+            diagnostics.push(diagnostic(
+                MkRmdSeverity::Warning,
+                &format!("{} {} NAME", kind, i),
+                format!("{} {} is ignored, {} is {}", kind, i, number_section, number),
+            ));
+        }
+    }
+
+    for (editable_cells, locked_cells, section) in [
+        (
+            &module_description.editable_cells,
+            &module_description.locked_cells,
+            &module_description.cell_list_sections.locked_cells,
+        ),
+        (
+            &module_description.c_editable_cells,
+            &module_description.c_locked_cells,
+            &module_description.cell_list_sections.c_locked_cells,
+        ),
+    ] {
+        for address in editable_cells.iter().filter(|address| locked_cells.contains(address)) {
+            diagnostics.push(diagnostic(
+                MkRmdSeverity::Warning,
+                section.as_deref().unwrap_or_default(),
+                format!("Cell 0x{:02X} is both editable and locked", address),
+            ));
+        }
+    }
+
+    for key in module_description.unknown_data.keys() {
+        let is_mode_section = key.starts_with("TESTMODE ") || key.starts_with("QUICKMODE ");
+        if !IGNORED_SECTIONS.contains(&key.as_str()) && !is_mode_section {
+            diagnostics.push(diagnostic(
+                MkRmdSeverity::Warning,
+                key,
+                format!("[{}] is not part of the RMD format and is ignored", key),
+            ));
+        }
+    }
+
+    sort_by_line(&mut diagnostics);
+    diagnostics
+}
+
+fn sort_by_line(diagnostics: &mut [MkRmdDiagnostic]) {
+    // the sort is stable, so diagnostics of the same line keep their order
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line.unwrap_or(usize::MAX));
+}
@@ -0,0 +1,351 @@
+//! This module contains the RMD registry, which finds the RMD file describing a module.
+//! The registry scans an ordered list of directories for RMD files and indexes them by their `DEVICE_MODEL`
+//! section, so the name of the file doesn't matter. Parsed module descriptions are cached until the file changes.
+//! A model can have several RMD files, e.g. one per firmware version, see `RmdRegistry::select_file`.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use log::{info, warn};
+use tauri::AppHandle;
+
+use crate::data_types::MkRmdCompatibility;
+use crate::mk_module_description::{format_rmd_diagnostics, parse_compatibility, MkModuleDescription};
+use crate::module_description_parser::parse_rmd_document;
+
+/// The environment variable with extra RMD directories (separated like `PATH`), searched before all others.
+pub const RMD_PATH_ENV_VAR: &str = "TINYMESH_RMD_PATH";
+
+/// A RMD file found by the registry.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct MkRmdFile {
+    pub device_model: String,
+    pub file_path: PathBuf,
+    /// The firmware and hardware versions the file was made for
+    pub compatibility: MkRmdCompatibility,
+}
+
+/// The RMD file selected for a device.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct MkRmdSelection {
+    pub file: MkRmdFile,
+    /// Set if no RMD file was made for the firmware and hardware versions of the device
+    pub warning: Option<String>,
+}
+
+/// The RMD files of the search directories, indexed by their `DEVICE_MODEL`.
+#[derive(Default)]
+pub struct RmdRegistry {
+    /// The search directories, the first directory containing a model wins
+    directories: Vec<PathBuf>,
+    /// The RMD files in the order of the search directories, and by file name within a directory
+    files: Vec<MkRmdFile>,
+    /// The parsed module descriptions with the modification time of their file
+    cache: HashMap<PathBuf, (Option<SystemTime>, MkModuleDescription)>,
+    /// The modification time of every RMD file at the last scan, to find the files changed since
+    modified: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl RmdRegistry {
+    /// Creates a registry searching the given directories and scans them.
+    pub fn new(directories: Vec<PathBuf>) -> RmdRegistry {
+        let mut registry = RmdRegistry {
+            directories,
+            ..Default::default()
+        };
+        registry.scan();
+        registry
+    }
+
+    /// Returns the search directories, in the order they are searched.
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
+    }
+
+    /// Replaces the search directories and scans them.
+    pub fn set_directories(&mut self, directories: Vec<PathBuf>) {
+        self.directories = directories;
+        self.scan();
+    }
+
+    /// This function scans the search directories again, to find RMD files that were added, changed or removed.
+    /// Directories that don't exist and files without a `DEVICE_MODEL` are skipped.
+    pub fn scan(&mut self) {
+        for directory in self.directories.iter().filter(|directory| !directory.is_dir()) {
+            info!("Skipping RMD directory {}, it doesn't exist", directory.display());
+        }
+        let rmd_files = self.list_rmd_files();
+        self.files.clear();
+        for (file_path, _) in &rmd_files {
+            match read_rmd_file(file_path) {
+                Ok(file) => self.files.push(file),
+                Err(err) => info!("Skipping RMD file {}: {}", file_path.display(), err),
+            }
+        }
+        self.modified = rmd_files.into_iter().collect();
+        info!(
+            "Found {} RMD files in {:?}",
+            self.files.len(),
+            self.directories
+        );
+    }
+
+    /// This function finds the RMD files that were added, changed or removed since the last scan,
+    /// and scans the search directories again if there are any.
+    ///
+    /// # Returns
+    /// The paths of the changed files, sorted.
+    pub fn poll_changes(&mut self) -> Vec<PathBuf> {
+        let rmd_files: HashMap<PathBuf, Option<SystemTime>> =
+            self.list_rmd_files().into_iter().collect();
+        let mut changed: Vec<PathBuf> = rmd_files
+            .iter()
+            .filter(|(file_path, modified)| self.modified.get(*file_path) != Some(*modified))
+            .map(|(file_path, _)| file_path.clone())
+            .collect();
+        for file_path in self.modified.keys() {
+            if !rmd_files.contains_key(file_path) {
+                self.cache.remove(file_path);
+                changed.push(file_path.clone());
+            }
+        }
+        if !changed.is_empty() {
+            self.scan();
+        }
+        changed.sort();
+        changed
+    }
+
+    /// Lists the RMD files of the search directories with their modification time,
+    /// in the order of the search directories and by file name within a directory.
+    fn list_rmd_files(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let mut result = vec![];
+        for directory in &self.directories {
+            let mut file_paths: Vec<PathBuf> = match std::fs::read_dir(directory) {
+                Ok(entries) => entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| is_rmd_file(path))
+                    .collect(),
+                Err(_) => continue,
+            };
+            file_paths.sort();
+            for file_path in file_paths {
+                let modified = std::fs::metadata(&file_path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
+                result.push((file_path, modified));
+            }
+        }
+        result
+    }
+
+    /// Returns the RMD files that can be used, sorted by model.
+    /// If several files describe the same model for the same versions, the first one found is used.
+    pub fn models(&self) -> Vec<MkRmdFile> {
+        let mut result: Vec<MkRmdFile> = vec![];
+        for file in &self.files {
+            if !result.iter().any(|used| {
+                used.device_model == file.device_model && used.compatibility == file.compatibility
+            }) {
+                result.push(file.clone());
+            }
+        }
+        // the sort is stable, so the files of a model keep the order of the search directories
+        result.sort_by(|a, b| a.device_model.cmp(&b.device_model));
+        result
+    }
+
+    /// Returns the first RMD file of the model, if any, whatever versions it was made for.
+    pub fn find_file(&self, device_model: &str) -> Option<&MkRmdFile> {
+        self.files.iter().find(|file| file.device_model == device_model)
+    }
+
+    /// This function selects the RMD file that matches the device best.
+    /// A file made for the firmware version of the device is preferred over a file without a firmware range,
+    /// which is preferred over the file made for the closest other firmware versions. The hardware revision is
+    /// compared the same way, but matters less than the firmware. Among equal matches the first file found is used.
+    ///
+    /// # Arguments
+    /// * `device_model` - The model of the device
+    /// * `hw_version` - The hardware revision of the device
+    /// * `firmware_version` - The firmware version of the device
+    ///
+    /// # Returns
+    /// The selected file, with a warning if it was not made for the versions of the device,
+    /// or `None` if no file describes the model.
+    pub fn select_file(
+        &self,
+        device_model: &str,
+        hw_version: &str,
+        firmware_version: &str,
+    ) -> Option<MkRmdSelection> {
+        let score = |supported: Option<bool>| match supported {
+            Some(true) => 2,
+            None => 1,
+            Some(false) => 0,
+        };
+        let file = self
+            .files
+            .iter()
+            .filter(|file| file.device_model == device_model)
+            // `max_by_key` returns the last of equal matches, so the files are searched in reverse
+            .rev()
+            .max_by_key(|file| {
+                (
+                    score(file.compatibility.supports_firmware(firmware_version)),
+                    Reverse(file.compatibility.firmware_distance(firmware_version)),
+                    score(file.compatibility.supports_hw(hw_version)),
+                )
+            })?;
+        Some(MkRmdSelection {
+            file: file.clone(),
+            warning: file.compatibility.check(hw_version, firmware_version),
+        })
+    }
+
+    /// This function returns the module description of the first RMD file of the model, for when the versions
+    /// of the device are not known. If no file describes the model, the directories are scanned again before giving up,
+    /// so RMD files added while the app is running are found.
+    ///
+    /// # Arguments
+    /// * `device_model` - The model of the device, as read from the configuration memory
+    ///
+    /// # Returns
+    /// The module description, or a `String` if no RMD file describes the model or the file has errors.
+    pub fn get_module_description(&mut self, device_model: &str) -> Result<MkModuleDescription, String> {
+        if self.find_file(device_model).is_none() {
+            self.scan();
+        }
+        let file_path = match self.find_file(device_model) {
+            Some(file) => file.file_path.clone(),
+            None => {
+                return Err(format!(
+                    "No RMD file for {} found in {:?}",
+                    device_model, self.directories
+                ))
+            }
+        };
+        self.load(&file_path)
+    }
+
+    /// This function returns the module description of the RMD file selected for the device (see `select_file`).
+    /// If no file describes the model, the directories are scanned again before giving up.
+    ///
+    /// # Arguments
+    /// * `device_model` - The model of the device, as read from the configuration memory
+    /// * `hw_version` - The hardware revision of the device
+    /// * `firmware_version` - The firmware version of the device
+    ///
+    /// # Returns
+    /// The module description and the selected file, or a `String` if no RMD file describes the model
+    /// or the file has errors.
+    pub fn get_module_description_for_device(
+        &mut self,
+        device_model: &str,
+        hw_version: &str,
+        firmware_version: &str,
+    ) -> Result<(MkModuleDescription, MkRmdSelection), String> {
+        if self.find_file(device_model).is_none() {
+            self.scan();
+        }
+        let selection = self
+            .select_file(device_model, hw_version, firmware_version)
+            .ok_or(format!(
+                "No RMD file for {} found in {:?}",
+                device_model, self.directories
+            ))?;
+        if let Some(warning) = &selection.warning {
+            warn!("{}: {}", selection.file.file_path.display(), warning);
+        }
+        let module_description = self.load(&selection.file.file_path)?;
+        Ok((module_description, selection))
+    }
+
+    /// This function returns the module description of a RMD file, parsing the file only if it changed
+    /// since it was last loaded.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the RMD file
+    ///
+    /// # Returns
+    /// The module description, or a `String` if the file could not be read or has errors.
+    pub fn load(&mut self, file_path: &Path) -> Result<MkModuleDescription, String> {
+        let modified = std::fs::metadata(file_path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if let Some((cached_modified, module_description)) = self.cache.get(file_path) {
+            if modified.is_some() && *cached_modified == modified {
+                return Ok(module_description.clone());
+            }
+        }
+        let file_contents = std::fs::read_to_string(file_path)
+            .map_err(|err| format!("Failed to read file '{}': {}", file_path.display(), err))?;
+        let module_description = MkModuleDescription::new(&file_contents)
+            .map_err(|diagnostics| format_rmd_diagnostics(&diagnostics))?;
+        self.cache.insert(
+            file_path.to_path_buf(),
+            (modified, module_description.clone()),
+        );
+        Ok(module_description)
+    }
+}
+
+/// This function returns the directories searched for RMD files, in this order:
+/// - the directories of the `TINYMESH_RMD_PATH` environment variable
+/// - the `modules` directory in the app config directory, for the user's own RMD files
+/// - the bundled `modules` resource directory
+/// - the `modules` directory next to the executable
+/// - in debug builds, the `modules` directory of the source tree
+///
+/// # Arguments
+/// * `app_handle` - The app handle, used to resolve the app config and resource directories
+pub fn rmd_directories(app_handle: &AppHandle) -> Vec<PathBuf> {
+    let mut result: Vec<PathBuf> = vec![];
+    if let Some(rmd_path) = std::env::var_os(RMD_PATH_ENV_VAR) {
+        result.extend(std::env::split_paths(&rmd_path));
+    }
+    let path_resolver = app_handle.path_resolver();
+    if let Some(app_config_dir) = path_resolver.app_config_dir() {
+        result.push(app_config_dir.join("modules"));
+    }
+    if let Some(resource_dir) = path_resolver.resource_dir() {
+        result.push(resource_dir.join("modules"));
+    }
+    if let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe_path| exe_path.parent().map(Path::to_path_buf))
+    {
+        result.push(exe_dir.join("modules"));
+    }
+    if cfg!(debug_assertions) {
+        result.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("modules"));
+    }
+    result.dedup();
+    result
+}
+
+fn is_rmd_file(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("rmd"))
+}
+
+/// Reads the `DEVICE_MODEL` and the versions of a RMD file, without parsing the rest of the description.
+fn read_rmd_file(file_path: &Path) -> Result<MkRmdFile, String> {
+    let file_contents = std::fs::read_to_string(file_path).map_err(|err| err.to_string())?;
+    let sections = parse_rmd_document(&file_contents).to_map();
+    let device_model = match sections.get("DEVICE_MODEL").map(|device_model| device_model.trim()) {
+        Some(device_model) if !device_model.is_empty() => device_model.to_string(),
+        _ => return Err("The RMD file has no DEVICE_MODEL".to_string()),
+    };
+    // problems with the versions are reported when the file is loaded
+    let (compatibility, _) = parse_compatibility(&sections);
+    Ok(MkRmdFile {
+        device_model,
+        file_path: file_path.to_path_buf(),
+        compatibility,
+    })
+}
@@ -0,0 +1,113 @@
+//! This module reloads the RMD files that are changed while the app is running.
+//! The search directories of the RMD registry are polled for changes, which works the same on every
+//! platform and file system (including network drives) without an extra dependency.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{error, info};
+use tauri::{AppHandle, Manager};
+
+use crate::data_types::MkDeviceConfig;
+use crate::device_config_parser::remap_device_config;
+use crate::mk_module_description::{MkModuleDescription, MkRmdDiagnostic};
+use crate::rmd_lint::lint_rmd;
+use crate::rmd_registry::RmdRegistry;
+
+/// How often the search directories are checked for changed RMD files.
+pub const RMD_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The payload of the `rmd_changed_event`, emitted for every RMD file that was added, changed or removed.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RmdChangedEvent {
+    pub file_path: PathBuf,
+    /// The new module description, `None` if the file was removed or has errors
+    pub module_description: Option<MkModuleDescription>,
+    /// The problems found in the file (see `rmd_lint::lint_rmd`)
+    pub diagnostics: Vec<MkRmdDiagnostic>,
+    /// The device config of the program mapped onto the new module description, if it was decoded with this file
+    pub device_config: Option<MkDeviceConfig>,
+}
+
+/// This function reloads the RMD files changed since the last scan of the registry.
+/// If the device config of the program was decoded with a changed file, it is mapped onto the new cell definitions
+/// (see `device_config_parser::remap_device_config`), without reading the device again.
+///
+/// # Arguments
+/// * `rmd_registry` - The RMD registry of the program
+/// * `device_config` - The device config of the program
+///
+/// # Returns
+/// An event for every changed file.
+pub fn reload_changed_rmd_files(
+    rmd_registry: &Mutex<RmdRegistry>,
+    device_config: &Mutex<Option<MkDeviceConfig>>,
+) -> Vec<RmdChangedEvent> {
+    // the registry is released before the device config is locked, like in `get_device_config`
+    let reloaded: Vec<(PathBuf, Option<MkModuleDescription>, Vec<MkRmdDiagnostic>)> = {
+        let mut rmd_registry = match rmd_registry.lock() {
+            Ok(rmd_registry) => rmd_registry,
+            Err(_) => return vec![],
+        };
+        rmd_registry
+            .poll_changes()
+            .into_iter()
+            .map(|file_path| match std::fs::read_to_string(&file_path) {
+                Ok(input) => {
+                    let module_description = rmd_registry.load(&file_path).ok();
+                    (file_path, module_description, lint_rmd(&input))
+                }
+                Err(_) => (file_path, None, vec![]),
+            })
+            .collect()
+    };
+
+    let mut result = vec![];
+    for (file_path, module_description, diagnostics) in reloaded {
+        info!("RMD file {} changed", file_path.display());
+        let mut remapped_config = None;
+        if let (Some(module_description), Ok(mut device_config)) =
+            (&module_description, device_config.lock())
+        {
+            if let Some(device_config) = device_config.as_mut() {
+                if device_config.rmd_file_path.as_ref() == Some(&file_path) {
+                    *device_config = remap_device_config(device_config, module_description.clone());
+                    remapped_config = Some(device_config.clone());
+                }
+            }
+        }
+        result.push(RmdChangedEvent {
+            file_path,
+            module_description,
+            diagnostics,
+            device_config: remapped_config,
+        });
+    }
+    result
+}
+
+/// This function starts the background thread that reloads changed RMD files and emits an `rmd_changed_event`
+/// for every one of them. The thread runs as long as the app.
+///
+/// # Arguments
+/// * `app_handle` - The app handle, used for emitting the events
+/// * `rmd_registry` - The RMD registry of the program
+/// * `device_config` - The device config of the program
+pub fn start_rmd_watcher(
+    app_handle: AppHandle,
+    rmd_registry: Arc<Mutex<RmdRegistry>>,
+    device_config: Arc<Mutex<Option<MkDeviceConfig>>>,
+) {
+    // the reload reads files and blocks while sleeping, so it gets its own thread instead of an async task
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(RMD_WATCH_INTERVAL);
+            for event in reload_changed_rmd_files(&rmd_registry, &device_config) {
+                app_handle
+                    .emit_all("rmd_changed_event", event)
+                    .unwrap_or_else(|e| error!("Error emitting: {}", e));
+            }
+        }
+    });
+}
@@ -0,0 +1,275 @@
+//! This module writes a `MkModuleDescription` back to RMD text, e.g. to generate the RMD file for a new firmware version.
+//! The written text is parsed back into an equal description, i.e. `MkModuleDescription::new(&write_module_description(&md))`
+//! equals `md` for every description read from a RMD file.
+
+use crate::data_types::{MkDeviceCell, MkEndianness};
+use crate::mk_module_description::{parse_hint_enum_values, MkModuleDescription};
+
+/// This function writes the module description as RMD text.
+/// Sections read from a RMD file are written in the order of that file (see `section_lines`),
+/// the other sections follow in the usual order of the TinyMesh RMD files:
+/// device model, firmware and hardware versions, unknown sections, RSSI and band plan keys, cell lists,
+/// quickmodes, testmodes, configuration cells and calibration cells.
+///
+/// The comments of the RMD file are not part of the description and are not written.
+/// Cells with a `current_value` other than their `default_value`, and band plans whose channels differ from
+/// the range of the RF Channel cell, can't be expressed in RMD and are read back with the values of the file.
+///
+/// # Arguments
+/// * `module_description` - The module description to write. The values must not contain `//`, blank lines
+///   or lines starting with `[`, which is always true for values read from a RMD file, and the labels and units
+///   of the enumerated values must not contain `:`.
+///
+/// # Returns
+/// The RMD text, terminated by an empty `[]` section.
+pub fn write_module_description(module_description: &MkModuleDescription) -> String {
+    let mut sections: Vec<(String, String)> = vec![];
+
+    if !module_description.device_model.is_empty() {
+        sections.push((
+            "DEVICE_MODEL".to_string(),
+            module_description.device_model.clone(),
+        ));
+    }
+
+    let compatibility = &module_description.compatibility;
+    if let Some((min, max)) = &compatibility.firmware_versions {
+        sections.push((
+            "FIRMWARE_VERSION_MIN_MAX".to_string(),
+            format!("{} {}", min, max),
+        ));
+    }
+    if !compatibility.hw_versions.is_empty() {
+        sections.push(("HW_VERSIONS".to_string(), compatibility.hw_versions.join(" ")));
+    }
+
+    let mut unknown_keys: Vec<&String> = module_description.unknown_data.keys().collect();
+    unknown_keys.sort();
+    for key in unknown_keys {
+        sections.push((key.clone(), module_description.unknown_data[key].clone()));
+    }
+
+    let rssi_conversion = &module_description.rssi_conversion;
+    sections.push(("RSSI_OFFSET".to_string(), rssi_conversion.offset.to_string()));
+    if let Some(saturation) = rssi_conversion.saturation {
+        sections.push(("RSSI_SATURATION".to_string(), saturation.to_string()));
+    }
+    sections.push((
+        "RSSI_SCALE_FACTOR".to_string(),
+        rssi_conversion.scale_factor.to_string(),
+    ));
+
+    if let Some(band_plan) = &module_description.band_plan {
+        sections.push((
+            "FREQUENCY_CHANNEL_FIRST_CENTER".to_string(),
+            band_plan.first_center_mhz.to_string(),
+        ));
+        sections.push((
+            "FREQUENCY_CHANNEL_WIDTH".to_string(),
+            band_plan.channel_width_khz.to_string(),
+        ));
+    }
+
+    let cell_list_sections = &module_description.cell_list_sections;
+    push_cell_list(
+        &mut sections,
+        &cell_list_sections.editable_cells,
+        "M EDITABLE_CELLS",
+        &module_description.editable_cells,
+    );
+    push_cell_list(
+        &mut sections,
+        &cell_list_sections.locked_cells,
+        "M LOCKED_CELLS",
+        &module_description.locked_cells,
+    );
+    push_cell_list(
+        &mut sections,
+        &cell_list_sections.c_editable_cells,
+        "C EDITABLE_CELLS",
+        &module_description.c_editable_cells,
+    );
+    push_cell_list(
+        &mut sections,
+        &cell_list_sections.c_locked_cells,
+        "C LOCKED_CELLS",
+        &module_description.c_locked_cells,
+    );
+
+    if module_description.number_of_quickmodes > 0 {
+        sections.push((
+            "QUICKMODE NUMBER".to_string(),
+            module_description.number_of_quickmodes.to_string(),
+        ));
+    }
+    for quickmode in &module_description.quickmodes {
+        push_mode(
+            &mut sections,
+            &format!("QUICKMODE {}", quickmode.testmode_id),
+            [
+                &quickmode.name,
+                &quickmode.sequence_on,
+                &quickmode.sequence_off,
+                &quickmode.description,
+            ],
+        );
+    }
+
+    if module_description.number_of_testmodes > 0 {
+        sections.push((
+            "TESTMODE NUMBER".to_string(),
+            module_description.number_of_testmodes.to_string(),
+        ));
+    }
+    for testmode in &module_description.testmodes {
+        push_mode(
+            &mut sections,
+            &format!("TESTMODE {}", testmode.testmode_id),
+            [
+                &testmode.name,
+                &testmode.sequence_on,
+                &testmode.sequence_off,
+                &testmode.description,
+            ],
+        );
+    }
+
+    for cell in &module_description.cells {
+        push_cell(&mut sections, "M", cell);
+    }
+    for cell in &module_description.calibration_cells {
+        push_cell(&mut sections, "C", cell);
+    }
+
+    // the sort is stable, so the sections that were not read from a file keep the order above
+    sections.sort_by_key(|(section_name, _)| {
+        module_description
+            .section_lines
+            .get(section_name)
+            .copied()
+            .unwrap_or(usize::MAX)
+    });
+
+    let mut result = String::new();
+    for (section_name, content) in sections {
+        result.push_str(&format!("[{}]\n", section_name));
+        if !content.is_empty() {
+            result.push_str(&content);
+            result.push('\n');
+        }
+        result.push('\n');
+    }
+    // terminates the content of the last section
+    result.push_str("[]\n");
+    return result;
+}
+
+/// Adds a cell list, written under the section it was read from or under `default_section_name`.
+/// Lists that are empty and were not read from a file are left out.
+fn push_cell_list(
+    sections: &mut Vec<(String, String)>,
+    section_name: &Option<String>,
+    default_section_name: &str,
+    cells: &[usize],
+) {
+    if section_name.is_none() && cells.is_empty() {
+        return;
+    }
+    let addresses: Vec<String> = cells.iter().map(|address| format!("0x{:02X}", address)).collect();
+    // 16 addresses per line, like the lists of the TinyMesh RMD files
+    let lines: Vec<String> = addresses.chunks(16).map(|chunk| chunk.join(" ")).collect();
+    sections.push((
+        section_name
+            .clone()
+            .unwrap_or(default_section_name.to_string()),
+        lines.join("\n"),
+    ));
+}
+
+/// Adds the NAME, SEQUENCE_ON, SEQUENCE_OFF and HINT sections of a testmode or quickmode, leaving out empty values.
+fn push_mode(sections: &mut Vec<(String, String)>, prefix: &str, values: [&String; 4]) {
+    for (attribute, value) in ["NAME", "SEQUENCE_ON", "SEQUENCE_OFF", "HINT"].iter().zip(values) {
+        if !value.is_empty() {
+            sections.push((format!("{} {}", prefix, attribute), value.clone()));
+        }
+    }
+}
+
+/// Adds the sections of a configuration (`prefix` "M") or calibration (`prefix` "C") cell,
+/// leaving out the attributes that have the value of an undescribed cell.
+fn push_cell(sections: &mut Vec<(String, String)>, prefix: &str, cell: &MkDeviceCell) {
+    let key = |attribute: &str| format!("{} 0x{:02X} {}", prefix, cell.address, attribute);
+    if !cell.name.is_empty() {
+        sections.push((key("NAME"), cell.name.clone()));
+    }
+    if !cell.description.is_empty() {
+        sections.push((key("HINT"), cell.description.clone()));
+    }
+    if cell.min_value != 0 || cell.max_value != 0 {
+        sections.push((
+            key("MIN_MAX"),
+            format!("{} {}", cell.min_value, cell.max_value),
+        ));
+    }
+    if !cell.allowed_values.is_empty() {
+        let allowed_values: Vec<String> =
+            cell.allowed_values.iter().map(|value| value.to_string()).collect();
+        sections.push((key("ALLOW"), allowed_values.join(" ")));
+    }
+    if cell.default_value != 0 {
+        sections.push((key("DEF"), cell.default_value.to_string()));
+    }
+    if let Some(multi_byte) = &cell.multi_byte {
+        sections.push((key("SIZE"), multi_byte.size.to_string()));
+        if multi_byte.endianness == MkEndianness::Little {
+            sections.push((key("ENDIAN"), "LITTLE".to_string()));
+        }
+    }
+    if !cell.bits.is_empty() {
+        let bits: Vec<String> = cell
+            .bits
+            .iter()
+            .map(|bits| {
+                if bits.first_bit == bits.last_bit {
+                    format!("{} {}", bits.first_bit, bits.name)
+                } else {
+                    format!("{}-{} {}", bits.first_bit, bits.last_bit, bits.name)
+                }
+            })
+            .collect();
+        sections.push((key("BITS"), bits.join("\n")));
+    }
+    if let Some(scaling) = &cell.scaling {
+        // a cell with only a SCALE of 1 still needs one of the sections to have a scaling
+        if scaling.scale != 1.0 || (scaling.offset == 0.0 && scaling.unit.is_none()) {
+            sections.push((key("SCALE"), scaling.scale.to_string()));
+        }
+        if scaling.offset != 0.0 {
+            sections.push((key("OFFSET"), scaling.offset.to_string()));
+        }
+        if let Some(unit) = &scaling.unit {
+            sections.push((key("UNIT"), unit.clone()));
+        }
+    }
+    if !cell.active_if.is_empty() {
+        let conditions: Vec<String> =
+            cell.active_if.iter().map(|condition| condition.to_string()).collect();
+        sections.push((key("ACTIVE_IF"), conditions.join("\n")));
+    }
+    if !cell.rules.is_empty() {
+        let rules: Vec<String> = cell.rules.iter().map(|rule| rule.to_string()).collect();
+        sections.push((key("REQUIRE"), rules.join("\n")));
+    }
+    // the values read from the table of the HINT are read from it again
+    if !cell.enum_values.is_empty() && cell.enum_values != parse_hint_enum_values(&cell.description) {
+        let enum_values: Vec<String> = cell
+            .enum_values
+            .iter()
+            .map(|enum_value| match &enum_value.unit {
+                Some(unit) => format!("{} : {} : {}", enum_value.value, enum_value.label, unit),
+                None => format!("{} : {}", enum_value.value, enum_value.label),
+            })
+            .collect();
+        sections.push((key("ENUM"), enum_values.join("\n")));
+    }
+}
@@ -1,14 +1,16 @@
 //! This module contains functions related to getting and setting the device calibration.
 //! These functions are used by the Tauri frontend's calibration tab.
 
-use crate::data_types::{DeviceEntity, MkDeviceCell, MkDeviceCalib};
+use crate::cell_validation::validate_cell_changes;
+use crate::data_types::{DeviceEntity, MkDeviceCalib, MkDeviceCell, MkWriteReport};
 use crate::device_calibration_parser::parse_device_calib;
 use crate::device_config_parser::parse_device_config;
-use crate::tinymesh_serial_util::{
-    clear_output_buffer_of_device,
-    read_bytes_till_3e_from_device_to_buffer, send_bytes_to_device,
-};
-use serialport::SerialPort;
+use crate::device_protocol::{execute_command, Command};
+use crate::device_transport::DeviceTransport;
+use crate::port_arbiter::lock_device;
+use crate::tinymesh_config_mod::{build_write_report, with_config_mode};
+use crate::tinymesh_serial_util::DeviceError;
+use std::path::Path;
 use tauri::{AppHandle, State};
 
 /// This function gets the device calibration from the connected serial device.
@@ -26,11 +28,13 @@ pub fn get_device_calib(
     device_entity: State<DeviceEntity>,
     app_handle: AppHandle,
 ) -> Result<MkDeviceCalib, String> {
-    let mut device = device_entity.port.lock().map_err(|err| err.to_string())?;
-    let device = device
-        .as_mut()
-        .ok_or("Could not lock the selected device".to_string())?;
-    let device_calib = get_device_calib_from_device(device, &app_handle)?;
+    let mut device =
+        lock_device(&device_entity.port, Some(&app_handle)).map_err(|err| err.to_string())?;
+    let mut device_mode = device_entity.device_mode.lock().map_err(|err| err.to_string())?;
+    let device_calib = with_config_mode(&mut *device, &mut device_mode, Some(&app_handle), |device| {
+        get_device_calib_from_device(device, None, Some(&app_handle))
+    })
+    .map_err(|err| err.to_string())?;
 
     let mut device_calib_from_state = device_entity
         .device_calib
@@ -43,40 +47,33 @@ pub fn get_device_calib(
     return Ok(device_calib);
 }
 
-
+/// This function reads the calibration memory from the given device and decodes it.
+/// The configuration memory is read first to find out the model of the device.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `rmd_file_path` - An optional path to the RMD file. If `None`, the RMD file is looked up by the device model.
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// A `MkDeviceCalib` struct containing the device calibration, or a `DeviceError`.
 pub fn get_device_calib_from_device(
-    device: &mut Box<dyn SerialPort>,
-    app_handle: &AppHandle,
-) -> Result<MkDeviceCalib, String> {
-    // info!("\ntinymesh_calibration_mod::get_device_calib_from_device(device, app_handle)\n");
-
-    let mut config_bytes_buffer = vec![];
-    if clear_output_buffer_of_device(device) && send_bytes_to_device(device, &[0x30], &app_handle) {
-        // info!("\nCALIB_DEVICE ==== {:?}\n", device);
+    device: &mut dyn DeviceTransport,
+    rmd_file_path: Option<&Path>,
+    app_handle: Option<&AppHandle>,
+) -> Result<MkDeviceCalib, DeviceError> {
+    let config_bytes_buffer = execute_command(device, &Command::ReadConfig, app_handle)?.into_bytes()?;
 
-        read_bytes_till_3e_from_device_to_buffer(device, &mut config_bytes_buffer, &app_handle);
-        // info!("\ntinymesh_calibration_mod::get_device_calib_from_device---> config_bytes_buffer = {:?}\n", config_bytes_buffer);
+    let device_config_info = parse_device_config(&config_bytes_buffer, rmd_file_path, app_handle)
+        .map_err(DeviceError::InvalidData)?;
+    let model = device_config_info.model;
+    // use the RMD file the configuration was decoded with
+    let rmd_file_path = rmd_file_path.or(device_config_info.rmd_file_path.as_deref());
 
-        let device_config_info = parse_device_config(&config_bytes_buffer, None, Some(&app_handle))?;
-        let model = device_config_info.model;
+    let calib_bytes_buffer =
+        execute_command(device, &Command::ReadCalibration, app_handle)?.into_bytes()?;
 
-        config_bytes_buffer = vec![];
-        if clear_output_buffer_of_device(device) && send_bytes_to_device(device, &[0x72], &app_handle) {
-            // info!("\nCALIB_DEVICE 2 ==== {:?}\n", device);
-    
-            read_bytes_till_3e_from_device_to_buffer(device, &mut config_bytes_buffer, &app_handle);
-            // info!("\ntinymesh_calibration_mod::get_device_calib_from_device---> config_bytes_buffer = {:?}\n", config_bytes_buffer);
-    
-            let device_calib = parse_device_calib(&config_bytes_buffer, model, None, Some(&app_handle))?;
-    
-            // info!("\ntinymesh_calibration_mod::get_device_calib_from_device---> device_calib = {:?}\n", device_calib);
-            return Ok(device_calib);
-        }
-
-        // info!("\ntinymesh_calibration_mod::get_device_calib_from_device---> device_calib = {:?}\n", device_calib);
-        return Err("Unable to get calibration. Model not found.".to_string());
-    }
-    return Err("Unable to get calibration. Looks like sending bytes failed.".to_string());
+    parse_device_calib(&calib_bytes_buffer, model, rmd_file_path, app_handle)
+        .map_err(DeviceError::InvalidData)
 }
 
 /// This function sets the device calibration in the connected serial device.
@@ -95,43 +92,121 @@ pub fn set_device_calib(
     device_entity: State<DeviceEntity>,
     app_handle: AppHandle,
 ) -> bool {
-    if let Ok(mut device) = device_entity.port.lock() {
-        if let Some(device) = device.as_mut() {
-            if clear_output_buffer_of_device(device) {
-                let mut bytes_to_send = vec![];
-                if let Ok(device_calib_optional) = device_entity.device_calib.lock() {
-                    if let Some(device_calib) = &*device_calib_optional {
-                        bytes_to_send = get_bytes_to_send_for_calib_change(device_calib, &cells);
-                    }
-                }
-                if bytes_to_send.is_empty() {
-                    return false;
-                }
-                let send_result = send_bytes_to_device(device, &[b'H', b'W'], &app_handle);
-                if send_result {
-                    let mut buffer = vec![];
-                    read_bytes_till_3e_from_device_to_buffer(device, &mut buffer, &app_handle);
-                    clear_output_buffer_of_device(device);
-                    if buffer.len() == 0 {
-                        let send_changes_result =
-                            send_bytes_to_device(device, &bytes_to_send, &app_handle);
-                        if send_changes_result {
-                            let mut buffer2 = vec![];
-                            read_bytes_till_3e_from_device_to_buffer(
-                                device,
-                                &mut buffer2,
-                                &app_handle,
-                            );
-                            return buffer2.len() == 0;
-                        }
-                    }
-                }
+    if let (Ok(mut device), Ok(mut device_mode)) = (
+        lock_device(&device_entity.port, Some(&app_handle)),
+        device_entity.device_mode.lock(),
+    ) {
+        if let Ok(device_calib_optional) = device_entity.device_calib.lock() {
+            if let Some(device_calib) = &*device_calib_optional {
+                return with_config_mode(&mut *device, &mut device_mode, Some(&app_handle), |device| {
+                    set_device_calib_on_device(device, device_calib, &cells, Some(&app_handle))
+                })
+                .is_ok();
             }
         }
     }
     return false;
 }
 
+/// This function writes the cells that differ from `device_calib` to the given device using the `HW` command.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `device_calib` - The calibration last read from the device
+/// * `cells` - The new values of the calibration cells
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// An `Ok(())` if the device calibration was set successfully, or a `DeviceError`.
+/// `DeviceError::InvalidCells` is returned without sending anything if a changed cell is locked
+/// or its value is not accepted by the RMD file, and `DeviceError::InvalidData` if none of the cells changed.
+pub fn set_device_calib_on_device(
+    device: &mut dyn DeviceTransport,
+    device_calib: &MkDeviceCalib,
+    cells: &[MkDeviceCell],
+    app_handle: Option<&AppHandle>,
+) -> Result<(), DeviceError> {
+    validate_cell_changes(
+        &device_calib.calibration_cells,
+        cells,
+        &device_calib.c_locked_cells,
+    )
+    .map_err(DeviceError::InvalidCells)?;
+    let changes = get_changes_for_calib_change(device_calib, cells);
+    if changes.is_empty() {
+        return Err(DeviceError::InvalidData("No calibration changes to send".to_string()));
+    }
+    execute_command(device, &Command::WriteCalibration(changes), app_handle)?;
+    Ok(())
+}
+
+/// This function sets the device calibration in the connected serial device,
+/// and verifies the write by reading the calibration back.
+/// The calibration in the state of the program is replaced with the calibration read back.
+/// # Arguments
+/// * `cells` - A vector of `MkDeviceCell` structs containing the new device calibration
+/// * `device_entity` - The state of the program (provided by Tauri)
+/// * `app_handle` - The Tauri application handle (provided by Tauri)
+///
+/// # Returns
+/// A `MkWriteReport` with the outcome of every written cell, or an error if the write or the read-back failed.
+#[tauri::command]
+pub fn set_and_verify_device_calib(
+    cells: Vec<MkDeviceCell>,
+    device_entity: State<DeviceEntity>,
+    app_handle: AppHandle,
+) -> Result<MkWriteReport, String> {
+    let mut device =
+        lock_device(&device_entity.port, Some(&app_handle)).map_err(|err| err.to_string())?;
+    let mut device_mode = device_entity.device_mode.lock().map_err(|err| err.to_string())?;
+    let mut device_calib = device_entity
+        .device_calib
+        .lock()
+        .map_err(|err| err.to_string())?;
+    let previous_calib = device_calib
+        .as_ref()
+        .ok_or("Read the device calibration first".to_string())?;
+    let (report, read_back_calib) =
+        with_config_mode(&mut *device, &mut device_mode, Some(&app_handle), |device| {
+            set_and_verify_device_calib_on_device(
+                device,
+                previous_calib,
+                &cells,
+                None,
+                Some(&app_handle),
+            )
+        })
+        .map_err(|err| err.to_string())?;
+    *device_calib = Some(read_back_calib);
+    return Ok(report);
+}
+
+/// This function writes the changed cells to the given device and reads the calibration back (using `r`) to verify the write.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `device_calib` - The calibration last read from the device
+/// * `cells` - The new values of the calibration cells
+/// * `rmd_file_path` - An optional path to the RMD file. If `None`, the RMD file is looked up by the device model.
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// The `MkWriteReport` and the calibration read back, or a `DeviceError` if the write or the read-back failed.
+pub fn set_and_verify_device_calib_on_device(
+    device: &mut dyn DeviceTransport,
+    device_calib: &MkDeviceCalib,
+    cells: &[MkDeviceCell],
+    rmd_file_path: Option<&Path>,
+    app_handle: Option<&AppHandle>,
+) -> Result<(MkWriteReport, MkDeviceCalib), DeviceError> {
+    set_device_calib_on_device(device, device_calib, cells, app_handle)?;
+    let read_back_calib = get_device_calib_from_device(device, rmd_file_path, app_handle)?;
+    let report = build_write_report(
+        &device_calib.calibration_cells,
+        cells,
+        &read_back_calib.calibration_cells,
+    );
+    Ok((report, read_back_calib))
+}
+
 /// This function sends a factory reset command to the connected serial device.
 /// # Arguments
 /// * `device_entity` - The state of the program (provided by Tauri)
@@ -155,20 +230,24 @@ pub fn set_device_calib(
 //     return false;
 // }
 
-fn get_bytes_to_send_for_calib_change(
+/// Returns the address/value pairs of the cells whose value differs from the cell at the same address in `device_calib`.
+/// Cells at addresses that are not part of `device_calib` are not written.
+fn get_changes_for_calib_change(
     device_calib: &MkDeviceCalib,
     cells: &[MkDeviceCell],
-) -> Vec<u8> {
-    let mut bytes_to_send = vec![];
-    for (index, cell) in cells.iter().enumerate() {
-        if cell.current_value != device_calib.calibration_cells[index].current_value {
-            bytes_to_send.push(cell.address as u8);
-            bytes_to_send.push(cell.current_value);
+) -> Vec<(u8, u8)> {
+    let mut changes = vec![];
+    for cell in cells {
+        let previous = device_calib
+            .calibration_cells
+            .iter()
+            .find(|previous| previous.address == cell.address);
+        if let Some(previous) = previous {
+            if cell.current_value != previous.current_value {
+                changes.push((cell.address as u8, cell.current_value));
+            }
         }
     }
-    if bytes_to_send.len() > 0 {
-        bytes_to_send.push(0xff);
-    }
-    return bytes_to_send;
+    return changes;
 }
 
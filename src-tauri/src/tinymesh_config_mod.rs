@@ -1,15 +1,33 @@
 //! This module contains functions related to getting and setting the device configuration.
 //! These functions are used by the Tauri frontend's configuration tab.
 
-use crate::data_types::{DeviceEntity, MkDeviceCell, MkDeviceConfig};
+use crate::cell_rules::{check_cell_rules, find_inactive_cells};
+use crate::cell_validation::validate_cell_changes;
+use crate::cell_values::{set_engineering_value, set_typed_value};
+use crate::config_diff::{
+    diff_device_configs, resolve_config_source, MkConfigDiff, MkConfigSource,
+};
+use crate::config_profile::MkConfigProfile;
+use crate::data_types::{
+    DeviceEntity, DeviceMode, MkCellRulesReport, MkCellWriteResult, MkCellWriteStatus,
+    MkCloneReport, MkCloneUnitReport, MkDeviceCell, MkDeviceConfig, MkWriteReport,
+};
 use crate::device_config_parser::parse_device_config;
+use crate::device_protocol::{execute_command, extract_send_recv_seq, Command};
+use crate::device_transport::DeviceTransport;
+use crate::port_arbiter::lock_device;
 use crate::tinymesh_serial_util::{
-    clear_output_buffer_of_device, read_bytes_from_device_to_buffer,
-    read_bytes_till_3e_from_device_to_buffer, send_bytes_to_device,
+    open_serial_transport, read_bytes_till_3e_with_limits, DeviceError,
+    DEFAULT_MAX_RESPONSE_LENGTH,
 };
-use serialport::SerialPort;
+use log::info;
+use std::path::Path;
+use std::time::Duration;
 use tauri::{AppHandle, State};
 
+/// How long to wait for the prompt after pulling the CONFIG pin, before falling back to the `0x00` sequence.
+const CONFIG_PIN_TIMEOUT: Duration = Duration::from_millis(500);
+
 /// This function gets the device configuration from the connected serial device.
 /// It will read the configuration from the device, match it with corresponding module description RMD file
 /// and return it as a `MkDeviceConfig` struct.
@@ -25,11 +43,13 @@ pub fn get_device_config(
     device_entity: State<DeviceEntity>,
     app_handle: AppHandle,
 ) -> Result<MkDeviceConfig, String> {
-    let mut device = device_entity.port.lock().map_err(|err| err.to_string())?;
-    let device = device
-        .as_mut()
-        .ok_or("Could not lock the selected device".to_string())?;
-    let device_config = get_device_config_from_device(device, &app_handle)?;
+    let mut device =
+        lock_device(&device_entity.port, Some(&app_handle)).map_err(|err| err.to_string())?;
+    let mut device_mode = device_entity.device_mode.lock().map_err(|err| err.to_string())?;
+    let device_config = with_config_mode(&mut *device, &mut device_mode, Some(&app_handle), |device| {
+        get_device_config_from_device(device, None, Some(&app_handle))
+    })
+    .map_err(|err| err.to_string())?;
 
     let mut device_config_from_state = device_entity
         .device_config
@@ -42,26 +62,22 @@ pub fn get_device_config(
     return Ok(device_config);
 }
 
-
+/// This function reads the configuration memory from the given device and decodes it.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `rmd_file_path` - An optional path to the RMD file. If `None`, the RMD file is looked up by the device model.
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// A `MkDeviceConfig` struct containing the device configuration, or a `DeviceError`.
 pub fn get_device_config_from_device(
-    device: &mut Box<dyn SerialPort>,
-    app_handle: &AppHandle,
-) -> Result<MkDeviceConfig, String> {
-    // info!("\nget_device_config_from_device::config_bytes_buffer(device, app_handle)\n");
-
-    let mut config_bytes_buffer = vec![];
-    if clear_output_buffer_of_device(device) && send_bytes_to_device(device, &[0x30], &app_handle) {
-        // info!("\nCONFIG_DEVICE ==== {:?}\n", device);
-
-        read_bytes_till_3e_from_device_to_buffer(device, &mut config_bytes_buffer, &app_handle);
-        // info!("\nget_device_config_from_device::config_bytes_buffer, {:?}\n", config_bytes_buffer);
-
-        let device_config = parse_device_config(&config_bytes_buffer, None, Some(&app_handle))?;
-        // info!("\nget_device_config_from_device::device_config, {:?}\n", device_config);
-
-        return Ok(device_config);
-    }
-    return Err("Unable to get config. Looks like sending bytes failed.".to_string());
+    device: &mut dyn DeviceTransport,
+    rmd_file_path: Option<&Path>,
+    app_handle: Option<&AppHandle>,
+) -> Result<MkDeviceConfig, DeviceError> {
+    let config_bytes_buffer = execute_command(device, &Command::ReadConfig, app_handle)?.into_bytes()?;
+    parse_device_config(&config_bytes_buffer, rmd_file_path, app_handle)
+        .map_err(DeviceError::InvalidData)
 }
 
 /// This function sets the device configuration in the connected serial device.
@@ -80,83 +96,729 @@ pub fn set_device_config(
     device_entity: State<DeviceEntity>,
     app_handle: AppHandle,
 ) -> bool {
-    if let Ok(mut device) = device_entity.port.lock() {
-        if let Some(device) = device.as_mut() {
-            if clear_output_buffer_of_device(device) {
-                let mut bytes_to_send = vec![];
-                if let Ok(device_config_optional) = device_entity.device_config.lock() {
-                    if let Some(device_config) = &*device_config_optional {
-                        bytes_to_send = get_bytes_to_send_for_config_change(device_config, &cells);
-                    }
-                }
-                if bytes_to_send.is_empty() {
-                    return false;
-                }
-                let send_result = send_bytes_to_device(device, &[b'M'], &app_handle);
-                if send_result {
-                    let mut buffer = vec![];
-                    read_bytes_till_3e_from_device_to_buffer(device, &mut buffer, &app_handle);
-                    clear_output_buffer_of_device(device);
-                    if buffer.len() == 0 {
-                        let send_changes_result =
-                            send_bytes_to_device(device, &bytes_to_send, &app_handle);
-                        if send_changes_result {
-                            let mut buffer2 = vec![];
-                            read_bytes_till_3e_from_device_to_buffer(
-                                device,
-                                &mut buffer2,
-                                &app_handle,
-                            );
-                            return buffer2.len() == 0;
-                        }
-                    }
-                }
+    if let (Ok(mut device), Ok(mut device_mode)) = (
+        lock_device(&device_entity.port, Some(&app_handle)),
+        device_entity.device_mode.lock(),
+    ) {
+        if let Ok(device_config_optional) = device_entity.device_config.lock() {
+            if let Some(device_config) = &*device_config_optional {
+                return with_config_mode(&mut *device, &mut device_mode, Some(&app_handle), |device| {
+                    set_device_config_on_device(device, device_config, &cells, Some(&app_handle))
+                })
+                .is_ok();
             }
         }
     }
     return false;
 }
 
-/// This function sends a factory reset command to the connected serial device.
+/// This function writes the cells that differ from `device_config` to the given device using the `M` command.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `device_config` - The configuration last read from the device
+/// * `cells` - The new values of the configuration cells
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// An `Ok(())` if the device configuration was set successfully, or a `DeviceError`.
+/// `DeviceError::InvalidCells` is returned without sending anything if a changed cell is locked
+/// or its value is not accepted by the RMD file, and `DeviceError::InvalidData` if none of the cells changed.
+pub fn set_device_config_on_device(
+    device: &mut dyn DeviceTransport,
+    device_config: &MkDeviceConfig,
+    cells: &[MkDeviceCell],
+    app_handle: Option<&AppHandle>,
+) -> Result<(), DeviceError> {
+    validate_cell_changes(&device_config.cells, cells, &device_config.locked_cells)
+        .map_err(DeviceError::InvalidCells)?;
+    let changes = get_changes_for_config_change(device_config, cells);
+    if changes.is_empty() {
+        return Err(DeviceError::InvalidData("No configuration changes to send".to_string()));
+    }
+    execute_command(device, &Command::WriteConfig(changes), app_handle)?;
+    Ok(())
+}
+
+/// This function sets the device configuration in the connected serial device,
+/// and verifies the write by reading the configuration back.
+/// The configuration in the state of the program is replaced with the configuration read back.
 /// # Arguments
+/// * `cells` - A vector of `MkDeviceCell` structs containing the new device configuration
 /// * `device_entity` - The state of the program (provided by Tauri)
 /// * `app_handle` - The Tauri application handle (provided by Tauri)
 ///
 /// # Returns
-/// A boolean value indicating whether the factory reset command was successful.
+/// A `MkWriteReport` with the outcome of every written cell, or an error if the write or the read-back failed.
 #[tauri::command]
-pub fn factory_reset(device_entity: State<DeviceEntity>, app_handle: AppHandle) -> bool {
-    if let Ok(mut device) = device_entity.port.lock() {
-        if let Some(device) = device.as_mut() {
-            clear_output_buffer_of_device(device);
-            let send_result = send_bytes_to_device(device, &[b'@', b'T', b'M'], &app_handle);
+pub fn set_and_verify_device_config(
+    cells: Vec<MkDeviceCell>,
+    device_entity: State<DeviceEntity>,
+    app_handle: AppHandle,
+) -> Result<MkWriteReport, String> {
+    let mut device =
+        lock_device(&device_entity.port, Some(&app_handle)).map_err(|err| err.to_string())?;
+    let mut device_mode = device_entity.device_mode.lock().map_err(|err| err.to_string())?;
+    let mut device_config = device_entity
+        .device_config
+        .lock()
+        .map_err(|err| err.to_string())?;
+    let previous_config = device_config
+        .as_ref()
+        .ok_or("Read the device configuration first".to_string())?;
+    let (report, read_back_config) =
+        with_config_mode(&mut *device, &mut device_mode, Some(&app_handle), |device| {
+            set_and_verify_device_config_on_device(
+                device,
+                previous_config,
+                &cells,
+                None,
+                Some(&app_handle),
+            )
+        })
+        .map_err(|err| err.to_string())?;
+    *device_config = Some(read_back_config);
+    return Ok(report);
+}
 
-            if send_result {
-                let mut buffer = vec![];
-                read_bytes_till_3e_from_device_to_buffer(device, &mut buffer, &app_handle);
+/// This function writes the changed cells to the given device and reads the configuration back to verify the write.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `device_config` - The configuration last read from the device
+/// * `cells` - The new values of the configuration cells
+/// * `rmd_file_path` - An optional path to the RMD file. If `None`, the RMD file is looked up by the device model.
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// The `MkWriteReport` and the configuration read back, or a `DeviceError` if the write or the read-back failed.
+pub fn set_and_verify_device_config_on_device(
+    device: &mut dyn DeviceTransport,
+    device_config: &MkDeviceConfig,
+    cells: &[MkDeviceCell],
+    rmd_file_path: Option<&Path>,
+    app_handle: Option<&AppHandle>,
+) -> Result<(MkWriteReport, MkDeviceConfig), DeviceError> {
+    set_device_config_on_device(device, device_config, cells, app_handle)?;
+    let read_back_config = get_device_config_from_device(device, rmd_file_path, app_handle)?;
+    let report = build_write_report(&device_config.cells, cells, &read_back_config.cells);
+    Ok((report, read_back_config))
+}
 
-                return buffer.len() == 0;
-            }
+/// This function compares the cells read back after a write with the requested values.
+/// Cells are matched by address. Only the cells whose requested value differs from the previous value are reported,
+/// requested addresses that are unknown to the model or missing from the read-back are reported as failed.
+/// # Arguments
+/// * `previous_cells` - The cells before the write
+/// * `requested_cells` - The cells with the requested values
+/// * `read_back_cells` - The cells read back after the write
+///
+/// # Returns
+/// A `MkWriteReport` with one entry per written address.
+pub fn build_write_report(
+    previous_cells: &[MkDeviceCell],
+    requested_cells: &[MkDeviceCell],
+    read_back_cells: &[MkDeviceCell],
+) -> MkWriteReport {
+    let mut report = MkWriteReport::default();
+    for requested in requested_cells {
+        let previous_value = find_cell_value(previous_cells, requested.address);
+        if previous_value == Some(requested.current_value) {
+            continue;
         }
+        let read_back_value = find_cell_value(read_back_cells, requested.address);
+        let status = if previous_value.is_none() {
+            // unknown addresses are never written
+            MkCellWriteStatus::Failed
+        } else if read_back_value == Some(requested.current_value) {
+            MkCellWriteStatus::Written
+        } else if read_back_value == previous_value {
+            MkCellWriteStatus::Ignored
+        } else {
+            MkCellWriteStatus::Failed
+        };
+        report.cells.push(MkCellWriteResult {
+            address: requested.address,
+            name: requested.name.clone(),
+            previous_value,
+            requested_value: requested.current_value,
+            read_back_value,
+            status,
+        });
+    }
+    report
+}
+
+/// Returns the value of the cell at `address`, or None if there is no such cell.
+fn find_cell_value(cells: &[MkDeviceCell], address: usize) -> Option<u8> {
+    cells
+        .iter()
+        .find(|cell| cell.address == address)
+        .map(|cell| cell.current_value)
+}
+
+/// This function sets a multi-byte value, or the named bits of an option byte, in the edited cells.
+/// The layout of the value is taken from the configuration last read from the device, not from the frontend.
+/// **NOTE**: Nothing is sent to the device, the returned cells are written with `set_device_config`
+/// or `set_and_verify_device_config`, which send the bytes that changed as `M` address/value pairs.
+/// # Arguments
+/// * `cells` - The cells with the values edited so far
+/// * `address` - The first address of the value
+/// * `bits` - The name of the bits to set, or `None` to set the whole value
+/// * `value` - The new value
+/// * `device_entity` - The state of the program (provided by Tauri)
+///
+/// # Returns
+/// The cells with the new value, or an error if no configuration was read yet, the cell has no such value
+/// or the value doesn't fit in it.
+#[tauri::command]
+pub fn set_device_typed_value(
+    cells: Vec<MkDeviceCell>,
+    address: usize,
+    bits: Option<String>,
+    value: u32,
+    device_entity: State<DeviceEntity>,
+) -> Result<Vec<MkDeviceCell>, String> {
+    edit_device_cells(&cells, &device_entity, |cells| {
+        set_typed_value(cells, address, bits.as_deref(), value)
+    })
+}
+
+/// This function sets a cell to a value in engineering units, e.g. `5 s`, `-10 dBm` or one of the labels of the cell.
+/// The value is converted to the raw value with the `SCALE` and `OFFSET` of the cell, taken from the configuration
+/// last read from the device. For cells spanning several addresses, the whole value is set.
+/// **NOTE**: Nothing is sent to the device, the returned cells are written with `set_device_config`
+/// or `set_and_verify_device_config`.
+/// # Arguments
+/// * `cells` - The cells with the values edited so far
+/// * `address` - The address of the cell
+/// * `value` - The engineering value, with or without its unit
+/// * `device_entity` - The state of the program (provided by Tauri)
+///
+/// # Returns
+/// The cells with the new value, or an error if no configuration was read yet or the value is not accepted.
+#[tauri::command]
+pub fn set_device_engineering_value(
+    cells: Vec<MkDeviceCell>,
+    address: usize,
+    value: String,
+    device_entity: State<DeviceEntity>,
+) -> Result<Vec<MkDeviceCell>, String> {
+    edit_device_cells(&cells, &device_entity, |cells| {
+        set_engineering_value(cells, address, &value)
+    })
+}
+
+/// This function evaluates the `ACTIVE_IF` and `REQUIRE` rules of the RMD file for the edited cells,
+/// so the frontend can hide the cells that are not used and show the broken rules before writing.
+/// The rules are taken from the configuration last read from the device.
+/// # Arguments
+/// * `cells` - The cells with the values edited so far
+/// * `device_entity` - The state of the program (provided by Tauri)
+///
+/// # Returns
+/// The inactive cells and the broken rules, or an error if no configuration was read yet.
+#[tauri::command]
+pub fn check_device_cell_rules(
+    cells: Vec<MkDeviceCell>,
+    device_entity: State<DeviceEntity>,
+) -> Result<MkCellRulesReport, String> {
+    let cells = edit_device_cells(&cells, &device_entity, |_| Ok(vec![]))?;
+    return Ok(MkCellRulesReport {
+        inactive_cells: find_inactive_cells(&cells),
+        violations: check_cell_rules(&cells),
+    });
+}
+
+/// Applies `edit` to the cell definitions of the configuration last read from the device,
+/// with the current values of the edited cells.
+fn edit_device_cells(
+    cells: &[MkDeviceCell],
+    device_entity: &DeviceEntity,
+    edit: impl FnOnce(&mut [MkDeviceCell]) -> Result<Vec<(usize, u8)>, String>,
+) -> Result<Vec<MkDeviceCell>, String> {
+    let device_config = device_entity
+        .device_config
+        .lock()
+        .map_err(|err| err.to_string())?;
+    let device_config = device_config
+        .as_ref()
+        .ok_or("Read the device configuration first".to_string())?;
+    let mut result = device_config.cells.clone();
+    for cell in result.iter_mut() {
+        if let Some(edited) = cells.iter().find(|edited| edited.address == cell.address) {
+            cell.current_value = edited.current_value;
+        }
+    }
+    edit(&mut result)?;
+    return Ok(result);
+}
+
+/// This function compares two configurations of the connected device, e.g. the device and a saved profile,
+/// or the device and the defaults of its RMD file.
+/// # Arguments
+/// * `old` - The configuration before the change
+/// * `new` - The configuration after the change
+/// * `device_entity` - The state of the program (provided by Tauri)
+///
+/// # Returns
+/// The cells that differ, or an error if no configuration was read yet or a profile can't be used.
+#[tauri::command]
+pub fn get_config_diff(
+    old: MkConfigSource,
+    new: MkConfigSource,
+    device_entity: State<DeviceEntity>,
+) -> Result<MkConfigDiff, String> {
+    build_config_diff(&old, &new, &device_entity)
+}
+
+/// This function compares two configurations of the connected device and saves the differences for a change review.
+/// # Arguments
+/// * `old` - The configuration before the change
+/// * `new` - The configuration after the change
+/// * `file_path` - The path of the file to write, as JSON if it ends with `.json` and as text otherwise
+/// * `device_entity` - The state of the program (provided by Tauri)
+///
+/// # Returns
+/// An `Ok(())` if the diff was saved, or an error if the configurations can't be compared or the file could not be written.
+#[tauri::command]
+pub fn export_config_diff(
+    old: MkConfigSource,
+    new: MkConfigSource,
+    file_path: String,
+    device_entity: State<DeviceEntity>,
+) -> Result<(), String> {
+    build_config_diff(&old, &new, &device_entity)?.save(Path::new(&file_path))
+}
+
+fn build_config_diff(
+    old: &MkConfigSource,
+    new: &MkConfigSource,
+    device_entity: &DeviceEntity,
+) -> Result<MkConfigDiff, String> {
+    let device_config = device_entity
+        .device_config
+        .lock()
+        .map_err(|err| err.to_string())?;
+    let device_config = device_config
+        .as_ref()
+        .ok_or("Read the device configuration first".to_string())?;
+    let (old_config, old_name) = resolve_config_source(old, device_config)?;
+    let (new_config, new_name) = resolve_config_source(new, device_config)?;
+    return Ok(diff_device_configs(
+        &old_config,
+        &new_config,
+        (old_name, new_name),
+    ));
+}
+
+/// This function saves the device configuration in the state of the program as a configuration profile.
+/// # Arguments
+/// * `file_path` - The path of the JSON file to write
+/// * `device_entity` - The state of the program (provided by Tauri)
+///
+/// # Returns
+/// An `Ok(())` if the profile was saved, or an error if no configuration was read yet or the file could not be written.
+#[tauri::command]
+pub fn export_config_profile(
+    file_path: String,
+    device_entity: State<DeviceEntity>,
+) -> Result<(), String> {
+    let device_config = device_entity
+        .device_config
+        .lock()
+        .map_err(|err| err.to_string())?;
+    let device_config = device_config
+        .as_ref()
+        .ok_or("Read the device configuration first".to_string())?;
+    MkConfigProfile::from_device_config(device_config).save(Path::new(&file_path))
+}
+
+/// This function loads a configuration profile and writes it to the connected serial device.
+/// The configuration is read from the device first, so only the editable cells that differ are written.
+/// The configuration in the state of the program is replaced with the configuration read back.
+/// # Arguments
+/// * `file_path` - The path of the JSON file to load
+/// * `device_entity` - The state of the program (provided by Tauri)
+/// * `app_handle` - The Tauri application handle (provided by Tauri)
+///
+/// # Returns
+/// A `MkWriteReport` with the outcome of every written cell, or an error if the profile is invalid,
+/// was made for another model, or the write failed.
+#[tauri::command]
+pub fn import_config_profile(
+    file_path: String,
+    device_entity: State<DeviceEntity>,
+    app_handle: AppHandle,
+) -> Result<MkWriteReport, String> {
+    let profile = MkConfigProfile::load(Path::new(&file_path))?;
+    let mut device =
+        lock_device(&device_entity.port, Some(&app_handle)).map_err(|err| err.to_string())?;
+    let mut device_mode = device_entity.device_mode.lock().map_err(|err| err.to_string())?;
+    let (report, read_back_config) =
+        with_config_mode(&mut *device, &mut device_mode, Some(&app_handle), |device| {
+            import_config_profile_to_device(device, &profile, None, Some(&app_handle))
+        })
+        .map_err(|err| err.to_string())?;
+    let mut device_config = device_entity
+        .device_config
+        .lock()
+        .map_err(|err| err.to_string())?;
+    *device_config = Some(read_back_config);
+    return Ok(report);
+}
+
+/// This function writes a configuration profile to the given device.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `profile` - The configuration profile to write
+/// * `rmd_file_path` - An optional path to the RMD file. If `None`, the RMD file is looked up by the device model.
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// The `MkWriteReport` and the configuration of the device after the import, or a `DeviceError`.
+/// The report is empty if the device already matches the profile.
+pub fn import_config_profile_to_device(
+    device: &mut dyn DeviceTransport,
+    profile: &MkConfigProfile,
+    rmd_file_path: Option<&Path>,
+    app_handle: Option<&AppHandle>,
+) -> Result<(MkWriteReport, MkDeviceConfig), DeviceError> {
+    let device_config = get_device_config_from_device(device, rmd_file_path, app_handle)?;
+    let cells = profile
+        .apply_to(&device_config)
+        .map_err(DeviceError::InvalidData)?;
+    let has_changes = device_config
+        .cells
+        .iter()
+        .zip(&cells)
+        .any(|(previous, requested)| previous.current_value != requested.current_value);
+    if !has_changes {
+        return Ok((MkWriteReport::default(), device_config));
+    }
+    info!("Importing configuration profile for {}", profile.model);
+    set_and_verify_device_config_on_device(device, &device_config, &cells, rmd_file_path, app_handle)
+}
+
+/// This function clones a golden configuration to several modules, connecting to each port in turn.
+/// The golden configuration is either a saved profile or the configuration in the state of the program.
+/// The modules are expected to be in transparent mode, as after power-up, and are returned to it afterwards.
+/// # Arguments
+/// * `port_names` - The serial ports the modules are connected to
+/// * `baud_rate` - The baud rate of the serial ports
+/// * `unique_cells` - The addresses of the per-unit cells (e.g. the addresses of the module) that are not cloned
+/// * `profile_file_path` - An optional path to a saved profile. If `None`, the configuration in the state of the program is used.
+/// * `device_entity` - The state of the program (provided by Tauri)
+/// * `app_handle` - The Tauri application handle (provided by Tauri)
+///
+/// # Returns
+/// A `MkCloneReport` with the outcome of every unit, or an error if the golden configuration could not be loaded.
+#[tauri::command]
+pub fn clone_config_to_devices(
+    port_names: Vec<String>,
+    baud_rate: u32,
+    unique_cells: Vec<usize>,
+    profile_file_path: Option<String>,
+    device_entity: State<DeviceEntity>,
+    app_handle: AppHandle,
+) -> Result<MkCloneReport, String> {
+    let golden_profile = match profile_file_path {
+        Some(file_path) => MkConfigProfile::load(Path::new(&file_path))?,
+        None => {
+            let device_config = device_entity
+                .device_config
+                .lock()
+                .map_err(|err| err.to_string())?;
+            let device_config = device_config
+                .as_ref()
+                .ok_or("Read the device configuration first".to_string())?;
+            MkConfigProfile::from_device_config(device_config)
+        }
+    };
+    let mut clone_report = MkCloneReport::default();
+    for port_name in port_names {
+        info!("Cloning configuration to {}", port_name);
+        let unit_report = match open_serial_transport(&port_name, baud_rate) {
+            Ok(mut transport) => clone_config_to_unit(
+                &port_name,
+                &mut transport,
+                &golden_profile,
+                &unique_cells,
+                None,
+                Some(&app_handle),
+            ),
+            Err(err) => MkCloneUnitReport {
+                port_name,
+                model: None,
+                passed: false,
+                error: Some(err),
+                write_report: MkWriteReport::default(),
+            },
+        };
+        clone_report.units.push(unit_report);
+    }
+    return Ok(clone_report);
+}
+
+/// This function clones a golden configuration to a single module in transparent mode.
+/// The module is put into configuration mode, its model is checked against the golden configuration,
+/// the editable cells except `unique_cells` are written and verified, and the module is returned to transparent mode.
+/// # Arguments
+/// * `port_name` - The name of the unit, used in the report
+/// * `device` - The transport connected to the module
+/// * `golden_profile` - The configuration to clone
+/// * `unique_cells` - The addresses of the per-unit cells that are not cloned
+/// * `rmd_file_path` - An optional path to the RMD file. If `None`, the RMD file is looked up by the device model.
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// The `MkCloneUnitReport` of the module. The unit passes if every changed cell was verified.
+pub fn clone_config_to_unit(
+    port_name: &str,
+    device: &mut dyn DeviceTransport,
+    golden_profile: &MkConfigProfile,
+    unique_cells: &[usize],
+    rmd_file_path: Option<&Path>,
+    app_handle: Option<&AppHandle>,
+) -> MkCloneUnitReport {
+    let profile = golden_profile.without_cells(unique_cells);
+    let mut device_mode = DeviceMode::Transparent;
+    let result = with_config_mode(device, &mut device_mode, app_handle, |device| {
+        import_config_profile_to_device(device, &profile, rmd_file_path, app_handle)
+    });
+    match result {
+        Ok((write_report, device_config)) => MkCloneUnitReport {
+            port_name: port_name.to_string(),
+            model: Some(device_config.model),
+            passed: write_report.is_successful(),
+            error: None,
+            write_report,
+        },
+        Err(err) => MkCloneUnitReport {
+            port_name: port_name.to_string(),
+            model: None,
+            passed: false,
+            error: Some(err.to_string()),
+            write_report: MkWriteReport::default(),
+        },
+    }
+}
+
+/// This function puts the connected serial device into configuration mode.
+/// # Arguments
+/// * `device_entity` - The state of the program (provided by Tauri)
+/// * `app_handle` - The Tauri application handle (provided by Tauri)
+///
+/// # Returns
+/// A boolean value indicating whether the device answered with the configuration mode prompt.
+#[tauri::command]
+pub fn enter_config_mode(device_entity: State<DeviceEntity>, app_handle: AppHandle) -> bool {
+    if let (Ok(mut device), Ok(mut device_mode)) = (
+        lock_device(&device_entity.port, Some(&app_handle)),
+        device_entity.device_mode.lock(),
+    ) {
+        if enter_config_mode_on_device(&mut *device, Some(&app_handle)).is_ok() {
+            *device_mode = DeviceMode::Config;
+            return true;
+        }
+    }
+    return false;
+}
+
+/// This function returns the connected serial device to transparent / packet mode.
+/// # Arguments
+/// * `device_entity` - The state of the program (provided by Tauri)
+/// * `app_handle` - The Tauri application handle (provided by Tauri)
+///
+/// # Returns
+/// A boolean value indicating whether the exit command was sent successfully.
+#[tauri::command]
+pub fn exit_config_mode(device_entity: State<DeviceEntity>, app_handle: AppHandle) -> bool {
+    if let (Ok(mut device), Ok(mut device_mode)) = (
+        lock_device(&device_entity.port, Some(&app_handle)),
+        device_entity.device_mode.lock(),
+    ) {
+        if exit_config_mode_on_device(&mut *device, Some(&app_handle)).is_ok() {
+            *device_mode = DeviceMode::Transparent;
+            return true;
+        }
+    }
+    return false;
+}
+
+/// This function returns the mode the connected device was last switched to.
+/// # Arguments
+/// * `device_entity` - The state of the program (provided by Tauri)
+///
+/// # Returns
+/// The `DeviceMode` tracked by the backend.
+#[tauri::command]
+pub fn get_device_mode(device_entity: State<DeviceEntity>) -> DeviceMode {
+    if let Ok(device_mode) = device_entity.device_mode.lock() {
+        return *device_mode;
+    }
+    return DeviceMode::Unknown;
+}
+
+/// This function puts the given device into configuration mode.
+/// It pulls the CONFIG pin first if the connection drives it (see `SerialTransport::with_config_pin`),
+/// and sends the `0x00` sequence if the connection has no CONFIG line or the module does not answer with the `>` prompt in time.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// An `Ok(())` if the device answered with the configuration mode prompt, or a `DeviceError`.
+pub fn enter_config_mode_on_device(
+    device: &mut dyn DeviceTransport,
+    app_handle: Option<&AppHandle>,
+) -> Result<(), DeviceError> {
+    if device.set_config_pin(true).is_ok() {
+        // data received before the prompt is transparent traffic and is discarded
+        let mut buffer = vec![];
+        let result = read_bytes_till_3e_with_limits(
+            device,
+            &mut buffer,
+            CONFIG_PIN_TIMEOUT,
+            DEFAULT_MAX_RESPONSE_LENGTH,
+            app_handle,
+        );
+        device.set_config_pin(false)?;
+        if result.is_ok() {
+            return Ok(());
+        }
+        info!("No prompt after pulling the CONFIG pin, sending the config mode sequence");
+    }
+    execute_command(device, &Command::EnterConfigMode, app_handle)?;
+    Ok(())
+}
+
+/// This function returns the given device to transparent / packet mode using the `X` command.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// An `Ok(())` if the command was sent, or a `DeviceError`.
+pub fn exit_config_mode_on_device(
+    device: &mut dyn DeviceTransport,
+    app_handle: Option<&AppHandle>,
+) -> Result<(), DeviceError> {
+    execute_command(device, &Command::ExitConfigMode, app_handle)?;
+    Ok(())
+}
+
+/// This function makes sure the given device is in configuration mode before sending commands to it.
+/// Only a device known to be in transparent mode is switched, see `DeviceMode::Unknown`.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `device_mode` - The tracked mode of the device, updated if the mode is switched
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// The mode the device was in before, to be passed to `restore_device_mode`, or a `DeviceError`.
+pub fn ensure_config_mode(
+    device: &mut dyn DeviceTransport,
+    device_mode: &mut DeviceMode,
+    app_handle: Option<&AppHandle>,
+) -> Result<DeviceMode, DeviceError> {
+    let previous_mode = *device_mode;
+    if previous_mode == DeviceMode::Transparent {
+        enter_config_mode_on_device(device, app_handle)?;
+        *device_mode = DeviceMode::Config;
+    }
+    Ok(previous_mode)
+}
+
+/// This function switches the given device back to the mode returned by `ensure_config_mode`.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `device_mode` - The tracked mode of the device, updated if the mode is switched
+/// * `previous_mode` - The mode to restore
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// An `Ok(())` if the mode was restored (or nothing had to be done), or a `DeviceError`.
+pub fn restore_device_mode(
+    device: &mut dyn DeviceTransport,
+    device_mode: &mut DeviceMode,
+    previous_mode: DeviceMode,
+    app_handle: Option<&AppHandle>,
+) -> Result<(), DeviceError> {
+    if previous_mode == DeviceMode::Transparent && *device_mode == DeviceMode::Config {
+        exit_config_mode_on_device(device, app_handle)?;
+        *device_mode = DeviceMode::Transparent;
+    }
+    Ok(())
+}
+
+/// This function runs `operation` with the given device in configuration mode,
+/// and restores the previous mode afterwards, even if the operation failed.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `device_mode` - The tracked mode of the device
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+/// * `operation` - The operation to run in configuration mode
+///
+/// # Returns
+/// The result of `operation`, or a `DeviceError` if the mode could not be switched.
+pub fn with_config_mode<T>(
+    device: &mut dyn DeviceTransport,
+    device_mode: &mut DeviceMode,
+    app_handle: Option<&AppHandle>,
+    operation: impl FnOnce(&mut dyn DeviceTransport) -> Result<T, DeviceError>,
+) -> Result<T, DeviceError> {
+    let previous_mode = ensure_config_mode(device, device_mode, app_handle)?;
+    let result = operation(device);
+    let restore_result = restore_device_mode(device, device_mode, previous_mode, app_handle);
+    let value = result?;
+    restore_result?;
+    Ok(value)
+}
+
+/// This function sends a factory reset command to the connected serial device.
+/// # Arguments
+/// * `device_entity` - The state of the program (provided by Tauri)
+/// * `app_handle` - The Tauri application handle (provided by Tauri)
+///
+/// # Returns
+/// A boolean value indicating whether the factory reset command was successful.
+#[tauri::command]
+pub fn factory_reset(device_entity: State<DeviceEntity>, app_handle: AppHandle) -> bool {
+    if let Ok(mut device) = lock_device(&device_entity.port, Some(&app_handle)) {
+        return factory_reset_device(&mut *device, Some(&app_handle)).is_ok();
     }
     return false;
 }
 
-fn get_bytes_to_send_for_config_change(
+/// This function sends the `@TM` factory reset command to the given device.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// An `Ok(())` if the device acknowledged the factory reset, or a `DeviceError`.
+pub fn factory_reset_device(
+    device: &mut dyn DeviceTransport,
+    app_handle: Option<&AppHandle>,
+) -> Result<(), DeviceError> {
+    execute_command(device, &Command::FactoryReset, app_handle)?;
+    Ok(())
+}
+
+/// Returns the address/value pairs of the cells whose value differs from the cell at the same address in `device_config`.
+/// Cells at addresses that are not part of `device_config` are not written.
+fn get_changes_for_config_change(
     device_config: &MkDeviceConfig,
     cells: &[MkDeviceCell],
-) -> Vec<u8> {
-    let mut bytes_to_send = vec![];
-    for (index, cell) in cells.iter().enumerate() {
-        if cell.current_value != device_config.cells[index].current_value {
-            bytes_to_send.push(cell.address as u8);
-            bytes_to_send.push(cell.current_value);
+) -> Vec<(u8, u8)> {
+    let mut changes = vec![];
+    for cell in cells {
+        if let Some(previous_value) = find_cell_value(&device_config.cells, cell.address) {
+            if cell.current_value != previous_value {
+                changes.push((cell.address as u8, cell.current_value));
+            }
         }
     }
-    if bytes_to_send.len() > 0 {
-        bytes_to_send.push(0xff);
-    }
-    return bytes_to_send;
+    return changes;
 }
 
 /// This function executes a mode sequence on the connected serial device.
@@ -177,39 +839,35 @@ pub fn execute_mode_sequence(
     device_entity: State<DeviceEntity>,
     app_handle: AppHandle,
 ) -> bool {
-    let mut recv_buffer = vec![];
-    if let Ok(mut device) = device_entity.port.lock() {
-        if let Some(device) = device.as_mut() {
-            clear_output_buffer_of_device(device);
-            if let Some((send_seq, recv_seq)) = extract_send_recv_seq(&sequence_str) {
-                let send_result = send_bytes_to_device(device, &send_seq, &app_handle);
-                if recv_seq.ends_with(&[b'>']) {
-                    read_bytes_till_3e_from_device_to_buffer(device, &mut recv_buffer, &app_handle);
-                    if send_result && recv_buffer == recv_seq[..recv_seq.len() - 1] {
-                        return true;
-                    }
-                } else {
-                    read_bytes_from_device_to_buffer(device, &mut recv_buffer, &app_handle);
-                    if send_result && recv_buffer == recv_seq {
-                        return true;
-                    }
-                }
-            }
+    if let Ok(mut device) = lock_device(&device_entity.port, Some(&app_handle)) {
+        if let Some((send_seq, recv_seq)) = extract_send_recv_seq(&sequence_str) {
+            return execute_mode_sequence_on_device(&mut *device, &send_seq, &recv_seq, Some(&app_handle))
+                .is_ok();
         }
     }
     return false;
 }
 
-fn extract_send_recv_seq(sequence_str: &str) -> Option<(Vec<u8>, Vec<u8>)> {
-    if let [send_seq, recv_seq] = sequence_str
-        .trim()
-        .split_whitespace()
-        .collect::<Vec<&str>>()
-        .as_slice()
-    {
-        let send = send_seq.trim_start_matches('a').as_bytes().to_vec();
-        let recv = recv_seq.trim_start_matches('#').as_bytes().to_vec();
-        return Some((send, recv));
-    }
-    None
+/// This function sends the input bytes of a mode sequence to the given device
+/// and matches the device's output to the expected bytes.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `send_seq` - The bytes to send
+/// * `recv_seq` - The bytes expected back. If they end with `>`, the response is read until the prompt.
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// An `Ok(())` if the device answered as expected, or a `DeviceError`.
+pub fn execute_mode_sequence_on_device(
+    device: &mut dyn DeviceTransport,
+    send_seq: &[u8],
+    recv_seq: &[u8],
+    app_handle: Option<&AppHandle>,
+) -> Result<(), DeviceError> {
+    let command = Command::ModeSequence {
+        send: send_seq.to_vec(),
+        expect: recv_seq.to_vec(),
+    };
+    execute_command(device, &command, app_handle)?;
+    Ok(())
 }
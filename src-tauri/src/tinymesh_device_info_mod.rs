@@ -1,13 +1,17 @@
 //! This module contains functions for getting information about the connected TinyMesh device.
 //! These functions are used in the Tauri frontend's device info tab.
 
-use crate::data_types::DeviceEntity;
-use crate::tinymesh_config_mod::get_device_config_from_device;
-use crate::tinymesh_serial_util::{
-    clear_output_buffer_of_device, read_bytes_till_3e_from_device_to_buffer, send_bytes_to_device,
+use crate::data_types::{DeviceEntity, DeviceMode, MkBandPlan, MkRssiConversion};
+use crate::device_protocol::{execute_command, Command};
+use crate::device_transport::DeviceTransport;
+use crate::port_arbiter::lock_device;
+use crate::tinymesh_config_mod::{
+    ensure_config_mode, get_device_config_from_device, restore_device_mode, with_config_mode,
 };
-use log::info;
-use serialport::SerialPort;
+use crate::tinymesh_serial_util::{clear_output_buffer_of_device, DeviceError};
+use log::{error, info};
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
 use tauri::{AppHandle, Manager, State};
 
 /// This function gets the RSSI value from the connected serial device for the current channel.
@@ -19,21 +23,77 @@ use tauri::{AppHandle, Manager, State};
 /// A string containing the RSSI value in dBm or an error message if the RSSI value could not be read.
 #[tauri::command]
 pub fn get_device_rssi(device_entity: State<DeviceEntity>, app_handle: AppHandle) -> String {
-    if let Ok(mut device) = device_entity.port.lock() {
-        if let Some(device) = device.as_mut() {
-            clear_output_buffer_of_device(device);
-            if let Ok(result) = get_rssi_from_device(device, &app_handle) {
-                return format!(
-                    "RSSI: -{} dBm, DEC: {}",
-                    ((result as f64) * 0.5) as f64,
-                    result
-                );
-            }
-        }
+    let rssi_conversion = get_rssi_conversion(&device_entity);
+    if let Ok(result) = read_from_device(&device_entity, &app_handle, get_rssi_from_device) {
+        return match rssi_conversion.convert(result).dbm {
+            Some(dbm) => format!("RSSI: {} dBm, DEC: {}", dbm, result),
+            None => format!("RSSI: [SATURATED], DEC: {}", result),
+        };
     }
     return "RSSI: [UNABLE TO READ]".to_string();
 }
 
+/// This function returns the RSSI conversion of the model of the device configuration in the state of the program.
+/// The default conversion is used if the configuration was not read yet.
+fn get_rssi_conversion(device_entity: &DeviceEntity) -> MkRssiConversion {
+    if let Ok(device_config) = device_entity.device_config.lock() {
+        if let Some(device_config) = device_config.as_ref() {
+            return device_config.rssi_conversion;
+        }
+    }
+    return MkRssiConversion::default();
+}
+
+/// This function returns the band plan of the model of the device configuration in the state of the program.
+/// Returns `None` if the configuration was not read yet or the RMD file has no band plan.
+fn get_band_plan(device_entity: &DeviceEntity) -> Option<MkBandPlan> {
+    if let Ok(device_config) = device_entity.device_config.lock() {
+        return device_config.as_ref()?.band_plan;
+    }
+    return None;
+}
+
+/// A channel and its center frequency
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct MkChannelFrequency {
+    pub channel: u8,
+    pub frequency_mhz: f64,
+}
+
+/// This function converts between channel numbers and frequencies using the band plan of the connected model.
+/// Exactly one of `channel` and `frequency_mhz` must be given.
+/// # Arguments
+/// * `channel` - The channel to get the frequency of
+/// * `frequency_mhz` - The frequency in MHz to get the closest channel of
+/// * `device_entity` - The state of the program (provided by Tauri)
+///
+/// # Returns
+/// The `MkChannelFrequency`, or an error if the configuration was not read yet,
+/// the model has no band plan, or the channel or frequency is outside the band plan.
+#[tauri::command]
+pub fn convert_channel_frequency(
+    channel: Option<u8>,
+    frequency_mhz: Option<f64>,
+    device_entity: State<DeviceEntity>,
+) -> Result<MkChannelFrequency, String> {
+    let band_plan = get_band_plan(&device_entity)
+        .ok_or("Read the device configuration of a model with a band plan first".to_string())?;
+    let channel = match (channel, frequency_mhz) {
+        (Some(channel), None) => channel,
+        (None, Some(frequency_mhz)) => band_plan
+            .channel_of(frequency_mhz)
+            .ok_or(format!("{} MHz is not a channel of the band plan", frequency_mhz))?,
+        _ => return Err("Either a channel or a frequency is required".to_string()),
+    };
+    let frequency_mhz = band_plan
+        .frequency_of(channel)
+        .ok_or(format!("Channel {} is not part of the band plan", channel))?;
+    return Ok(MkChannelFrequency {
+        channel,
+        frequency_mhz,
+    });
+}
+
 /// This function gets the analog pins from the connected serial device.
 /// # Arguments
 /// * `device_entity` - The state of the program (provided by Tauri)
@@ -43,18 +103,13 @@ pub fn get_device_rssi(device_entity: State<DeviceEntity>, app_handle: AppHandle
 /// A string containing the analog pin values or an error message if the analog values could not be read.
 #[tauri::command]
 pub fn get_device_analog(device_entity: State<DeviceEntity>, app_handle: AppHandle) -> String {
-    if let Ok(mut device) = device_entity.port.lock() {
-        if let Some(device) = device.as_mut() {
-            clear_output_buffer_of_device(device);
-            if let Ok(analog) = get_analog_from_device(device, &app_handle) {
-                let result_str = analog
-                    .iter()
-                    .map(|byte| format!("{:02X}", byte))
-                    .collect::<Vec<String>>()
-                    .join(" ");
-                return format!("Analog: [{}]", result_str);
-            }
-        }
+    if let Ok(analog) = read_from_device(&device_entity, &app_handle, get_analog_from_device) {
+        let result_str = analog
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<String>>()
+            .join(" ");
+        return format!("Analog: [{}]", result_str);
     }
     return "Analog: [UNABLE TO READ]".to_string();
 }
@@ -68,13 +123,8 @@ pub fn get_device_analog(device_entity: State<DeviceEntity>, app_handle: AppHand
 /// A string containing the digital pin values or an error message if the digital values could not be read.
 #[tauri::command]
 pub fn get_device_digital(device_entity: State<DeviceEntity>, app_handle: AppHandle) -> String {
-    if let Ok(mut device) = device_entity.port.lock() {
-        if let Some(device) = device.as_mut() {
-            clear_output_buffer_of_device(device);
-            if let Ok(digital) = get_digital_from_device(device, &app_handle) {
-                return format!("Digital: {:02X}", digital);
-            }
-        }
+    if let Ok(digital) = read_from_device(&device_entity, &app_handle, get_digital_from_device) {
+        return format!("Digital: {:02X}", digital);
     }
     return "Digital: [UNABLE TO READ]".to_string();
 }
@@ -88,13 +138,10 @@ pub fn get_device_digital(device_entity: State<DeviceEntity>, app_handle: AppHan
 /// A string containing the temperature value or an error message if the temperature value could not be read.
 #[tauri::command]
 pub fn get_device_temperature(device_entity: State<DeviceEntity>, app_handle: AppHandle) -> String {
-    if let Ok(mut device) = device_entity.port.lock() {
-        if let Some(device) = device.as_mut() {
-            clear_output_buffer_of_device(device);
-            if let Ok(temperature_dec) = get_temperature_from_device(device, &app_handle) {
-                return format!("Temperature: {} \u{00B0}C", (temperature_dec as i32) - 128);
-            }
-        }
+    if let Ok(temperature_dec) =
+        read_from_device(&device_entity, &app_handle, get_temperature_from_device)
+    {
+        return format!("Temperature: {} \u{00B0}C", (temperature_dec as i32) - 128);
     }
     return "Temperature: [UNABLE TO READ]".to_string();
 }
@@ -108,103 +155,95 @@ pub fn get_device_temperature(device_entity: State<DeviceEntity>, app_handle: Ap
 /// A string containing the voltage value or an error message if the voltage value could not be read.
 #[tauri::command]
 pub fn get_device_voltage(device_entity: State<DeviceEntity>, app_handle: AppHandle) -> String {
-    if let Ok(mut device) = device_entity.port.lock() {
-        if let Some(device) = device.as_mut() {
-            clear_output_buffer_of_device(device);
-            if let Ok(voltage) = get_voltage_from_device(device, &app_handle) {
-                return format!("Voltage: {:.2} V", (voltage as f64) * 0.030);
-            }
-        }
+    if let Ok(voltage) = read_from_device(&device_entity, &app_handle, get_voltage_from_device) {
+        return format!("Voltage: {:.2} V", (voltage as f64) * 0.030);
     }
     return "Voltage: [UNABLE TO READ]".to_string();
 }
 
-fn get_rssi_from_device(
-    device: &mut Box<dyn SerialPort>,
+/// This function runs `read` on the connected device in configuration mode, after clearing its output buffer.
+/// # Arguments
+/// * `device_entity` - The state of the program (provided by Tauri)
+/// * `app_handle` - The Tauri application handle (provided by Tauri)
+/// * `read` - The read to run on the device
+///
+/// # Returns
+/// The result of `read`, or a `DeviceError` if no device is connected or its mode could not be switched.
+fn read_from_device<T>(
+    device_entity: &DeviceEntity,
     app_handle: &AppHandle,
-) -> Result<u8, String> {
-    let mut buffer = vec![];
-    let send_result = send_bytes_to_device(device, &[b'S'], app_handle);
-    if send_result {
-        read_bytes_till_3e_from_device_to_buffer(device, &mut buffer, app_handle);
-        if buffer.len() == 1 {
-            return Ok(buffer[0]);
-        }
-    }
-    return Err("RSSI: Bad".to_string());
+    read: fn(&mut dyn DeviceTransport, Option<&AppHandle>) -> Result<T, DeviceError>,
+) -> Result<T, DeviceError> {
+    let mut device = lock_device(&device_entity.port, Some(app_handle))?;
+    let mut device_mode = device_entity
+        .device_mode
+        .lock()
+        .map_err(|err| DeviceError::InvalidData(err.to_string()))?;
+    with_config_mode(&mut *device, &mut device_mode, Some(app_handle), |device| {
+        let _ = clear_output_buffer_of_device(device);
+        read(device, Some(app_handle))
+    })
+}
+
+/// This function reads the RSSI of the current channel from the given device using the `S` command.
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// The raw RSSI byte, or a `DeviceError` if the RSSI could not be read.
+pub fn get_rssi_from_device(
+    device: &mut dyn DeviceTransport,
+    app_handle: Option<&AppHandle>,
+) -> Result<u8, DeviceError> {
+    execute_command(device, &Command::GetRssi, app_handle)?.into_byte()
 }
 
 fn get_analog_from_device(
-    device: &mut Box<dyn SerialPort>,
-    app_handle: &AppHandle,
-) -> Result<Vec<u8>, String> {
-    let mut buffer = vec![];
-    let send_result = send_bytes_to_device(device, &[b'A'], app_handle);
-    if send_result {
-        read_bytes_till_3e_from_device_to_buffer(device, &mut buffer, app_handle);
-        if buffer.len() > 1 {
-            return Ok(buffer);
-        }
-    }
-    return Err("Analog: [UNABLE TO READ]".to_string());
+    device: &mut dyn DeviceTransport,
+    app_handle: Option<&AppHandle>,
+) -> Result<Vec<u8>, DeviceError> {
+    execute_command(device, &Command::GetAnalog, app_handle)?.into_bytes()
 }
 
 fn get_digital_from_device(
-    device: &mut Box<dyn SerialPort>,
-    app_handle: &AppHandle,
-) -> Result<u8, String> {
-    let mut buffer = vec![];
-    let send_result = send_bytes_to_device(device, &[b'D'], app_handle);
-    if send_result {
-        read_bytes_till_3e_from_device_to_buffer(device, &mut buffer, app_handle);
-        if buffer.len() == 1 {
-            return Ok(buffer[0]);
-        }
-    }
-    return Err("Digital: [UNABLE TO READ]".to_string());
+    device: &mut dyn DeviceTransport,
+    app_handle: Option<&AppHandle>,
+) -> Result<u8, DeviceError> {
+    execute_command(device, &Command::GetDigital, app_handle)?.into_byte()
 }
 
 fn get_temperature_from_device(
-    device: &mut Box<dyn SerialPort>,
-    app_handle: &AppHandle,
-) -> Result<u8, String> {
-    let mut buffer = vec![];
-    let send_result = send_bytes_to_device(device, &[b'U'], app_handle);
-    if send_result {
-        read_bytes_till_3e_from_device_to_buffer(device, &mut buffer, app_handle);
-        if buffer.len() == 1 {
-            return Ok(buffer[0]);
-        }
-    }
-    return Err("Temperature: [UNABLE TO READ]".to_string());
+    device: &mut dyn DeviceTransport,
+    app_handle: Option<&AppHandle>,
+) -> Result<u8, DeviceError> {
+    execute_command(device, &Command::GetTemperature, app_handle)?.into_byte()
 }
 
 fn get_voltage_from_device(
-    device: &mut Box<dyn SerialPort>,
-    app_handle: &AppHandle,
-) -> Result<u8, String> {
-    let mut buffer = vec![];
-    let send_result = send_bytes_to_device(device, &[b'V'], app_handle);
-    if send_result {
-        read_bytes_till_3e_from_device_to_buffer(device, &mut buffer, app_handle);
-        if buffer.len() == 1 {
-            return Ok(buffer[0]);
-        }
-    }
-    return Err("Voltage: [UNABLE TO READ]".to_string());
+    device: &mut dyn DeviceTransport,
+    app_handle: Option<&AppHandle>,
+) -> Result<u8, DeviceError> {
+    execute_command(device, &Command::GetVoltage, app_handle)?.into_byte()
 }
 
 /// This struct contains the data that is emitted as a tauri event in Spectrum Analyzer mode
 #[derive(Clone, serde::Serialize)]
 pub struct RSSIEvent {
-    pub rssi: f64,
+    /// The RSSI in dBm, or `None` if the reading is saturated
+    pub rssi: Option<f64>,
+    pub raw: u8,
+    pub saturated: bool,
     pub channel: u8,
+    /// The center frequency of the channel in MHz, if the model has a band plan
+    pub frequency_mhz: Option<f64>,
 }
 
 /// This function starts the RSSI stream background process and adds the running task to the `rssi_task` state variable.
 /// It will also set the `is_rssi_task_running` flag.
 /// It starts an infinite loop that will circle through all the channels, and read their RSSI.
 /// It will emit an event for each RSSI value that is read.
+/// The device is switched to configuration mode for the duration of the stream, and switched back once it stops.
 ///
 /// # Arguments
 /// * `device_entity` - The state of the program (provided by Tauri)
@@ -214,6 +253,7 @@ pub fn start_rssi_stream(device_entity: State<DeviceEntity>, app_handle: AppHand
     // info!("Starting RSSI stream");
     let device_port = device_entity.port.clone();
     let device_port_2 = device_entity.port.clone();
+    let device_mode = device_entity.device_mode.clone();
     if let Ok(mut is_rssi_task_running) = device_entity.is_rssi_task_running.lock() {
         if !*is_rssi_task_running {
             *is_rssi_task_running = true;
@@ -234,61 +274,58 @@ pub fn start_rssi_stream(device_entity: State<DeviceEntity>, app_handle: AppHand
                 max_channel = channel.max_value as u8;
             }
         } else {
-            if let Ok(mut device) = device_port_2.lock() {
-                if let Some(device) = device.as_mut() {
-                    if let Ok(device_config_from_call) =
-                        get_device_config_from_device(device, &app_handle)
+            if let (Ok(mut device), Ok(mut device_mode)) = (
+                lock_device(&device_port_2, Some(&app_handle)),
+                device_mode.lock(),
+            ) {
+                if let Ok(device_config_from_call) =
+                    with_config_mode(&mut *device, &mut device_mode, Some(&app_handle), |device| {
+                        get_device_config_from_device(device, None, Some(&app_handle))
+                    })
+                {
+                    *device_config = Some(device_config_from_call.clone());
+                    if let Some(channel) = device_config_from_call
+                        .cells
+                        .iter()
+                        .find(|cell| cell.name == "RF Channel")
                     {
-                        *device_config = Some(device_config_from_call.clone());
-                        if let Some(channel) = device_config_from_call
-                            .cells
-                            .iter()
-                            .find(|cell| cell.name == "RF Channel")
-                        {
-                            min_channel = channel.min_value as u8;
-                            max_channel = channel.max_value as u8;
-                        }
+                        min_channel = channel.min_value as u8;
+                        max_channel = channel.max_value as u8;
                     }
                 }
             }
         }
     }
+    let rssi_conversion = get_rssi_conversion(&device_entity);
+    let band_plan = get_band_plan(&device_entity);
 
     let stream = tauri::async_runtime::spawn(async move {
-        if let Ok(mut device) = device_port.lock() {
-            if let Some(mut device) = device.as_mut() {
-                loop {
-                    if let Ok(is_rssi_task_running) = is_rssi_task_running.lock() {
-                        if !*is_rssi_task_running {
-                            info!("Stopping RSSI stream");
-                            return;
-                        }
-                    }
-
-                    for i in min_channel..=max_channel {
-                        if let Ok(is_rssi_task_running) = is_rssi_task_running.lock() {
-                            if !*is_rssi_task_running {
-                                info!("Stopping RSSI stream");
-                                return;
-                            }
-                        }
-                        clear_output_buffer_of_device(&mut device);
-                        let channel_switch_success = switch_to_channel(i, &mut device, &app_handle);
-                        if channel_switch_success {
-                            if let Ok(rssi) = get_rssi_from_device(&mut device, &app_handle) {
-                                app_handle
-                                    .emit_all(
-                                        "rssi_event",
-                                        RSSIEvent {
-                                            rssi: -(rssi as f64) / 2.0,
-                                            channel: i,
-                                        },
-                                    )
-                                    .unwrap();
-                            }
-                        }
-                    }
-                }
+        if let Ok(mut device) = lock_device(&device_port, Some(&app_handle)) {
+            let result = run_rssi_stream(
+                &mut *device,
+                &device_mode,
+                &is_rssi_task_running,
+                min_channel..=max_channel,
+                Some(&app_handle),
+                |channel, rssi| {
+                    let reading = rssi_conversion.convert(rssi);
+                    app_handle
+                        .emit_all(
+                            "rssi_event",
+                            RSSIEvent {
+                                rssi: reading.dbm,
+                                raw: reading.raw,
+                                saturated: reading.saturated,
+                                channel,
+                                frequency_mhz: band_plan
+                                    .and_then(|band_plan| band_plan.frequency_of(channel)),
+                            },
+                        )
+                        .map_err(|err| err.to_string())
+                },
+            );
+            if let Err(err) = result {
+                error!("RSSI stream failed: {}", err);
             }
         }
     });
@@ -297,6 +334,55 @@ pub fn start_rssi_stream(device_entity: State<DeviceEntity>, app_handle: AppHand
     }
 }
 
+/// This function reads the RSSI of the channels in turn, until `is_running` is cleared or `on_reading` fails.
+/// The device is switched to configuration mode for the duration of the stream, and switched back once it stops.
+/// The mode is only locked while it is switched, so that it can be read (e.g. by `get_device_mode`) while the stream runs.
+///
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `device_mode` - The tracked mode of the device
+/// * `is_running` - The flag that stops the stream once cleared
+/// * `channels` - The channels to read
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+/// * `on_reading` - Called with the channel and the raw RSSI of every reading
+///
+/// # Returns
+/// An `Ok(())` once the stream stopped, or a `DeviceError` if the mode of the device could not be switched.
+pub fn run_rssi_stream(
+    device: &mut dyn DeviceTransport,
+    device_mode: &Mutex<DeviceMode>,
+    is_running: &Mutex<bool>,
+    channels: RangeInclusive<u8>,
+    app_handle: Option<&AppHandle>,
+    mut on_reading: impl FnMut(u8, u8) -> Result<(), String>,
+) -> Result<(), DeviceError> {
+    let lock_mode = || {
+        device_mode
+            .lock()
+            .map_err(|err| DeviceError::InvalidData(err.to_string()))
+    };
+    let previous_mode = ensure_config_mode(device, &mut *lock_mode()?, app_handle)?;
+    let is_running = || is_running.lock().map(|is_running| *is_running).unwrap_or(false);
+    'stream: while is_running() {
+        for channel in channels.clone() {
+            if !is_running() {
+                break 'stream;
+            }
+            let _ = clear_output_buffer_of_device(device);
+            if switch_to_channel(channel, device, app_handle).is_ok() {
+                if let Ok(rssi) = get_rssi_from_device(device, app_handle) {
+                    if let Err(err) = on_reading(channel, rssi) {
+                        error!("Could not report the RSSI reading, stopping the stream: {}", err);
+                        break 'stream;
+                    }
+                }
+            }
+        }
+    }
+    info!("Stopping RSSI stream");
+    restore_device_mode(device, &mut *lock_mode()?, previous_mode, app_handle)
+}
+
 /// This function stops the RSSI stream background process and removes the running task from the `rssi_task` state variable.
 /// It will also set the `is_rssi_task_running` flag to false.
 ///
@@ -317,19 +403,19 @@ pub fn stop_rssi_stream(device_entity: State<DeviceEntity>) {
     }
 }
 
-fn switch_to_channel(
+/// This function switches the given device to the specified RF channel using the `C` command.
+/// # Arguments
+/// * `channel` - The RF channel to switch to
+/// * `device` - The transport connected to the device
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// An `Ok(())` if the device acknowledged the channel switch, or a `DeviceError`.
+pub fn switch_to_channel(
     channel: u8,
-    device: &mut Box<dyn SerialPort>,
-    app_handle: &AppHandle,
-) -> bool {
-    let mut read_bytes_result = vec![];
-    let send_bytes_result = send_bytes_to_device(device, &[b'C'], app_handle);
-    read_bytes_till_3e_from_device_to_buffer(device, &mut read_bytes_result, app_handle);
-    if send_bytes_result && read_bytes_result.len() == 0 {
-        let mut read_bytes_result2 = vec![];
-        let send_bytes_result2 = send_bytes_to_device(device, &[channel], app_handle);
-        read_bytes_till_3e_from_device_to_buffer(device, &mut read_bytes_result2, app_handle);
-        return send_bytes_result2 && read_bytes_result2.len() == 0;
-    }
-    return false;
+    device: &mut dyn DeviceTransport,
+    app_handle: Option<&AppHandle>,
+) -> Result<(), DeviceError> {
+    execute_command(device, &Command::SetChannel(channel), app_handle)?;
+    Ok(())
 }
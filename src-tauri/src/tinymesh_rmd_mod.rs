@@ -0,0 +1,38 @@
+//! This module contains functions related to the RMD module description files.
+//! These functions are used by the Tauri frontend to check RMD files before they are used with a device.
+
+use tauri::State;
+
+use crate::data_types::DeviceEntity;
+use crate::mk_module_description::MkRmdDiagnostic;
+use crate::rmd_lint::lint_rmd;
+use crate::rmd_registry::MkRmdFile;
+
+/// This function checks a RMD file for mistakes (see `rmd_lint::lint_rmd`).
+/// # Arguments
+/// * `file_path` - The path to the RMD file
+///
+/// # Returns
+/// The diagnostics found in the file ordered by line, or an error if the file could not be read.
+#[tauri::command]
+pub fn lint_rmd_file(file_path: String) -> Result<Vec<MkRmdDiagnostic>, String> {
+    let input = std::fs::read_to_string(&file_path).map_err(|err| err.to_string())?;
+    Ok(lint_rmd(&input))
+}
+
+/// This function lists the models described by the RMD files of the module search directories
+/// (see `rmd_registry::rmd_directories`). The directories are scanned again, so new files are listed.
+/// # Arguments
+/// * `device_entity` - The state of the program, holding the RMD registry
+///
+/// # Returns
+/// The RMD file used for every model, sorted by model.
+#[tauri::command]
+pub fn list_rmd_models(device_entity: State<DeviceEntity>) -> Result<Vec<MkRmdFile>, String> {
+    let mut rmd_registry = device_entity
+        .rmd_registry
+        .lock()
+        .map_err(|err| err.to_string())?;
+    rmd_registry.scan();
+    Ok(rmd_registry.models())
+}
@@ -1,13 +1,87 @@
 //! This module contains functions for interacting with the serial port.
 //! These functions are called by the Tauri frontend to communicate with the serial port.
 
-use crate::data_types::{DeviceEntity, EventPayload};
+use crate::cell_validation::CellValidationError;
+use crate::data_types::{DeviceEntity, DeviceMode, EventPayload};
+use crate::device_emulator::TinyMeshEmulator;
+use crate::device_transport::{DeviceTransport, SerialTransport};
 use crate::input_processing::process_input;
 use log::{error, info};
-use serialport::SerialPort;
-use std::time::Duration;
+use std::fmt;
+use std::io;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Manager, State};
 
+/// The overall time a device is given to answer with the `>` prompt.
+pub const DEFAULT_PROMPT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The maximum number of bytes accepted before the `>` prompt.
+/// The largest regular response is a memory dump, which is well below this limit.
+pub const DEFAULT_MAX_RESPONSE_LENGTH: usize = 1024;
+
+/// Errors that can occur while exchanging bytes with a device.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub enum DeviceError {
+    /// No device is connected.
+    NotConnected,
+    /// The device did not answer with the `>` prompt before the deadline.
+    /// Contains the bytes received so far.
+    Timeout(Vec<u8>),
+    /// The connection to the device was lost (e.g. the USB adapter was unplugged).
+    PortGone(String),
+    /// The device sent more than the maximum number of bytes without a `>` prompt.
+    Overflow(usize),
+    /// The device answered with bytes that don't match the response expected for the command.
+    UnexpectedResponse(Vec<u8>),
+    /// The response could not be decoded, e.g. because no RMD file matches the device.
+    InvalidData(String),
+    /// The requested cell values violate the constraints of the RMD file. Nothing was sent.
+    InvalidCells(Vec<CellValidationError>),
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceError::NotConnected => write!(f, "No device is connected"),
+            DeviceError::Timeout(received) => write!(
+                f,
+                "Timed out waiting for the device prompt (received {} bytes)",
+                received.len()
+            ),
+            DeviceError::PortGone(reason) => write!(f, "Lost connection to the device: {}", reason),
+            DeviceError::Overflow(length) => write!(
+                f,
+                "Device response exceeded {} bytes without a prompt",
+                length
+            ),
+            DeviceError::UnexpectedResponse(received) => {
+                write!(f, "Unexpected response from the device: {:02X?}", received)
+            }
+            DeviceError::InvalidData(reason) => write!(f, "{}", reason),
+            DeviceError::InvalidCells(errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(|err| err.to_string())
+                    .collect::<Vec<String>>()
+                    .join("; ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DeviceError {}
+
+impl From<io::Error> for DeviceError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => DeviceError::Timeout(vec![]),
+            _ => DeviceError::PortGone(err.to_string()),
+        }
+    }
+}
+
 /// This function resets the state of the program.
 /// It is called when a new connection is being made or when the device is disconnected.
 /// # Arguments
@@ -19,6 +93,10 @@ use tauri::{AppHandle, Manager, State};
 pub fn reset_program_state(device_entity: State<DeviceEntity>) -> Result<(), String> {
     info!("Resetting program state");
     *device_entity.port.lock().map_err(|err| err.to_string())? = None;
+    *device_entity
+        .device_mode
+        .lock()
+        .map_err(|err| err.to_string())? = DeviceMode::Unknown;
     *device_entity
         .is_rssi_task_running
         .lock()
@@ -79,6 +157,7 @@ pub fn get_connected_device(device_entity: State<DeviceEntity>) -> Option<String
 /// # Arguments
 /// * `device_name` - The name of the serial port to connect to.
 /// * `baud_rate` - The baud rate to use when connecting to the serial port.
+/// * `use_config_pin` - Whether the adapter wires RTS to the CONFIG pin of the module, see `SerialTransport::with_config_pin` (defaults to false).
 /// * `device_entity` - The state of the program (provided by Tauri)
 ///
 /// # Returns
@@ -87,19 +166,70 @@ pub fn get_connected_device(device_entity: State<DeviceEntity>) -> Option<String
 pub fn connect_to_device(
     device_name: &str,
     baud_rate: u32,
+    use_config_pin: Option<bool>,
     device_entity: State<DeviceEntity>,
 ) -> Result<(), String> {
     info!("Connecting to {} with baud rate {}", device_name, baud_rate);
+    let port = open_serial_transport(device_name, baud_rate);
+    let mut device = device_entity.port.lock().map_err(|err| err.to_string())?;
+    *device = Some(Box::new(port?.with_config_pin(use_config_pin.unwrap_or(false))));
+    set_device_mode(&device_entity, DeviceMode::Unknown);
+    return Ok(());
+}
+
+/// This function opens a serial port with the settings used for TinyMesh modules.
+/// # Arguments
+/// * `device_name` - The name of the serial port
+/// * `baud_rate` - The baud rate of the serial port
+///
+/// # Returns
+/// The opened `SerialTransport`, or an error if the port could not be opened.
+pub fn open_serial_transport(device_name: &str, baud_rate: u32) -> Result<SerialTransport, String> {
     let port = serialport::new(device_name, baud_rate)
         .data_bits(serialport::DataBits::Eight)
         .timeout(Duration::from_millis(10))
-        .open();
+        .open()
+        .map_err(|err| err.to_string())?;
+    Ok(SerialTransport::new(port))
+}
+
+/// This function connects to a simulated TinyMesh module instead of a serial port.
+/// The emulator is loaded from a RMD file and a hex dump of the configuration memory
+/// (in the format of `resources/tests/config_response.txt`).
+/// # Arguments
+/// * `rmd_file_path` - The path to the RMD file of the emulated model.
+/// * `config_file_path` - The path to the hex dump of the configuration memory.
+/// * `device_entity` - The state of the program (provided by Tauri)
+///
+/// # Returns
+/// An `Ok(())` if the emulator was loaded successfully, or an error if the files could not be read.
+#[tauri::command]
+pub fn connect_to_emulated_device(
+    rmd_file_path: &str,
+    config_file_path: &str,
+    device_entity: State<DeviceEntity>,
+) -> Result<(), String> {
+    info!("Connecting to emulated device {} with config {}", rmd_file_path, config_file_path);
+    let rmd = std::fs::read_to_string(rmd_file_path).map_err(|err| err.to_string())?;
+    let config_hex_dump = std::fs::read_to_string(config_file_path).map_err(|err| err.to_string())?;
+    let emulator = TinyMeshEmulator::from_rmd_and_hex_dump(&rmd, &config_hex_dump)?;
     let mut device = device_entity.port.lock().map_err(|err| err.to_string())?;
-    let open_port = port.map_err(|err| err.to_string())?;
-    *device = Some(open_port);
+    *device = Some(Box::new(emulator.transport()));
+    let device_mode = if emulator.is_in_config_mode() {
+        DeviceMode::Config
+    } else {
+        DeviceMode::Transparent
+    };
+    set_device_mode(&device_entity, device_mode);
     return Ok(());
 }
 
+fn set_device_mode(device_entity: &DeviceEntity, mode: DeviceMode) {
+    if let Ok(mut device_mode) = device_entity.device_mode.lock() {
+        *device_mode = mode;
+    }
+}
+
 /// This function disconnects from the connected serial port.
 /// # Arguments
 /// * `device_entity` - The state of the program (provided by Tauri)
@@ -111,6 +241,7 @@ pub fn disconnect_from_device(device_entity: State<DeviceEntity>) -> bool {
     if let Ok(mut device) = device_entity.port.lock() {
         info!("Disconnecting from device");
         *device = None;
+        set_device_mode(&device_entity, DeviceMode::Unknown);
         return true;
     }
     return false;
@@ -137,8 +268,7 @@ pub fn send_bytes(
     info!("Sending bytes: {:?}", bytes_to_send);
     if let Ok(mut device) = device_entity.port.lock() {
         if let Some(device) = device.as_mut() {
-            let send_result = send_bytes_to_device(device, &bytes_to_send, &app_handle);
-            return send_result;
+            return send_bytes_to_device(device, &bytes_to_send, Some(&app_handle)).is_ok();
         }
     }
     return false;
@@ -154,7 +284,7 @@ pub fn send_bytes(
 pub fn clear_buffer(device_entity: State<DeviceEntity>) -> bool {
     if let Ok(mut device) = device_entity.port.lock() {
         if let Some(device) = device.as_mut() {
-            return clear_output_buffer_of_device(device);
+            return clear_output_buffer_of_device(device).is_ok();
         }
     }
     return false;
@@ -172,94 +302,171 @@ pub fn read_bytes(device_entity: State<DeviceEntity>, app_handle: AppHandle) ->
     let mut result = vec![];
     if let Ok(mut device) = device_entity.port.lock() {
         if let Some(device) = device.as_mut() {
-            read_bytes_from_device_to_buffer(device, &mut result, &app_handle);
+            read_bytes_from_device_to_buffer(device, &mut result, Some(&app_handle));
             return result;
         }
     }
     return result;
 }
 
-pub fn clear_output_buffer_of_device(device: &mut Box<dyn SerialPort>) -> bool {
-    return match device.clear(serialport::ClearBuffer::Output) {
-        Ok(()) => true,
-        Err(_) => false,
-    };
+pub fn clear_output_buffer_of_device(device: &mut dyn DeviceTransport) -> Result<(), DeviceError> {
+    device.clear(serialport::ClearBuffer::Output)?;
+    Ok(())
+}
+
+/// Emits the bytes exchanged with the device to the frontend logging panel.
+/// Nothing is emitted when there is no app handle, e.g. when running without the Tauri frontend.
+pub fn emit_exchange_bytes_event(app_handle: Option<&AppHandle>, data_type: &str, data: Vec<u8>) {
+    if let Some(app_handle) = app_handle {
+        app_handle
+            .emit_all(
+                "exchange_bytes_event",
+                EventPayload {
+                    data_type: data_type.to_string(),
+                    data,
+                },
+            )
+            .unwrap_or_else(|e| error!("Error emitting: {}", e));
+    }
 }
 
 pub fn send_bytes_to_device(
-    device: &mut Box<dyn SerialPort>,
+    device: &mut dyn DeviceTransport,
     bytes_to_send: &[u8],
-    app_handle: &AppHandle,
-) -> bool {
-    return match device.write_all(bytes_to_send) {
-        Ok(()) => {
-            device
-                .flush()
-                .unwrap_or_else(|e| error!("Error flushing: {}", e));
-            app_handle
-                .emit_all(
-                    "exchange_bytes_event",
-                    EventPayload {
-                        data_type: "TX".to_string(),
-                        data: bytes_to_send.to_vec(),
-                    },
-                )
-                .unwrap_or_else(|e| error!("Error emitting: {}", e));
-            true
-        }
-        Err(_) => false,
-    };
+    app_handle: Option<&AppHandle>,
+) -> Result<(), DeviceError> {
+    device.write_all(bytes_to_send).map_err(|err| DeviceError::PortGone(err.to_string()))?;
+    device
+        .flush()
+        .unwrap_or_else(|e| error!("Error flushing: {}", e));
+    emit_exchange_bytes_event(app_handle, "TX", bytes_to_send.to_vec());
+    Ok(())
 }
 
+/// Reads bytes into `buffer` until the `>` prompt is received, using the default deadline and length limit.
+/// The prompt itself is not added to the buffer.
+///
+/// # Returns
+/// The number of bytes read before the prompt, or a `DeviceError` if the prompt never arrived.
 pub fn read_bytes_till_3e_from_device_to_buffer(
-    device: &mut Box<dyn SerialPort>,
+    device: &mut dyn DeviceTransport,
     buffer: &mut Vec<u8>,
-    app_handle: &AppHandle,
-) -> usize {
-    // read into buf until we see 0x3e
+    app_handle: Option<&AppHandle>,
+) -> Result<usize, DeviceError> {
+    read_bytes_till_3e_with_limits(
+        device,
+        buffer,
+        DEFAULT_PROMPT_TIMEOUT,
+        DEFAULT_MAX_RESPONSE_LENGTH,
+        app_handle,
+    )
+}
+
+/// Reads bytes into `buffer` until the `>` prompt is received.
+/// The prompt itself is not added to the buffer.
+///
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `buffer` - The buffer the received bytes are appended to
+/// * `timeout` - The overall deadline for receiving the prompt
+/// * `max_length` - The maximum number of bytes accepted before the prompt
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// The number of bytes read before the prompt.
+/// A `DeviceError::Timeout` if the deadline passed, `DeviceError::Overflow` if more than `max_length`
+/// bytes were received, or `DeviceError::PortGone` if the connection was lost.
+pub fn read_bytes_till_3e_with_limits(
+    device: &mut dyn DeviceTransport,
+    buffer: &mut Vec<u8>,
+    timeout: Duration,
+    max_length: usize,
+    app_handle: Option<&AppHandle>,
+) -> Result<usize, DeviceError> {
+    let deadline = Instant::now() + timeout;
     let mut count = 0;
     let mut temp_buf = [0u8; 1];
-    while buffer.len() == 0 || buffer[buffer.len() - 1] != 0x3e {
-        match device.read_exact(&mut temp_buf) {
+    loop {
+        match device.read(&mut temp_buf) {
+            Ok(0) => {}
             Ok(_) => {
                 if temp_buf[0] == b'>' {
                     break;
-                } else {
-                    buffer.push(temp_buf[0]);
                 }
+                buffer.push(temp_buf[0]);
                 count += 1;
+                if count > max_length {
+                    emit_exchange_bytes_event(app_handle, "RX", buffer.to_vec());
+                    return Err(DeviceError::Overflow(max_length));
+                }
+            }
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+                ) => {}
+            Err(err) => {
+                emit_exchange_bytes_event(app_handle, "RX", buffer.to_vec());
+                return Err(DeviceError::PortGone(err.to_string()));
             }
-            Err(_) => continue,
+        }
+        if Instant::now() >= deadline {
+            emit_exchange_bytes_event(app_handle, "RX", buffer.to_vec());
+            return Err(DeviceError::Timeout(buffer.to_vec()));
         }
     }
-    app_handle
-        .emit_all(
-            "exchange_bytes_event",
-            EventPayload {
-                data_type: "RX".to_string(),
-                data: [buffer.to_vec(), vec![0x3e]].concat(),
-            },
-        )
-        .unwrap_or_else(|e| error!("Error emitting: {}", e));
+    emit_exchange_bytes_event(app_handle, "RX", [buffer.to_vec(), vec![0x3e]].concat());
+    Ok(count)
+}
+
+/// Reads the bytes that are pending on the device into `buffer`, until no more bytes arrive within the read timeout.
+/// The deadline and the length limit stop the read on a module that keeps streaming data.
+///
+/// # Arguments
+/// * `device` - The transport connected to the device
+/// * `buffer` - The buffer the received bytes are appended to
+/// * `timeout` - The overall deadline for reading the pending bytes
+/// * `max_length` - The maximum number of bytes read
+/// * `app_handle` - An optional Tauri application handle, used for emitting the exchanged bytes
+///
+/// # Returns
+/// The number of bytes read.
+pub fn read_pending_bytes_with_limits(
+    device: &mut dyn DeviceTransport,
+    buffer: &mut Vec<u8>,
+    timeout: Duration,
+    max_length: usize,
+    app_handle: Option<&AppHandle>,
+) -> usize {
+    let deadline = Instant::now() + timeout;
+    let mut count = 0;
+    let mut temp_buf = [0u8; 64];
+    while count < max_length && Instant::now() < deadline {
+        let chunk_length = temp_buf.len().min(max_length - count);
+        match device.read(&mut temp_buf[..chunk_length]) {
+            Ok(0) => break,
+            Ok(read) => {
+                buffer.extend_from_slice(&temp_buf[..read]);
+                count += read;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(_) => break,
+        }
+    }
+    if count > 0 {
+        emit_exchange_bytes_event(app_handle, "RX", buffer[buffer.len() - count..].to_vec());
+    }
     count
 }
 
 pub fn read_bytes_from_device_to_buffer(
-    device: &mut Box<dyn SerialPort>,
+    device: &mut dyn DeviceTransport,
     buffer: &mut Vec<u8>,
-    app_handle: &AppHandle,
+    app_handle: Option<&AppHandle>,
 ) -> usize {
     let result = device.read_to_end(buffer).unwrap_or(0);
     if buffer.len() > 0 {
-        app_handle
-            .emit_all(
-                "exchange_bytes_event",
-                EventPayload {
-                    data_type: "RX".to_string(),
-                    data: buffer.to_vec(),
-                },
-            )
-            .unwrap_or_else(|e| error!("Error emitting: {}", e));
+        emit_exchange_bytes_event(app_handle, "RX", buffer.to_vec());
     }
     result
 }
@@ -0,0 +1,92 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::cells_from_rmd;
+    use tinymesh_cc_tool::cell_rules::{check_cell_rules, find_inactive_cells};
+    use tinymesh_cc_tool::cell_validation::{validate_cell_changes, CellValidationError};
+    use tinymesh_cc_tool::data_types::{MkCellCondition, MkConditionOperator, MkDeviceCell};
+
+    const RMD: &str = "[DEVICE_MODEL]\nRF TM4070\n\
+        [M 0x03 NAME]\nProtocol mode\n[M 0x03 MIN_MAX]\n0 3\n\
+        [M 0x07 NAME]\nRouter timeout\n[M 0x07 MIN_MAX]\n0 255\n[M 0x07 ACTIVE_IF]\n0x0E == 2\n\
+        [M 0x0E NAME]\nDevice Type\n[M 0x0E MIN_MAX]\n1 3\n\
+        [M 0x5D NAME]\nSleep or RTS\n[M 0x5D MIN_MAX]\n0 1\n\
+        [M 0x5D REQUIRE]\n0x0E == 3 IF 0x5D == 1\n0x03 <= 1\n";
+
+    fn read_cells(values: &[(usize, u8)]) -> Vec<MkDeviceCell> {
+        let mut data = vec![0; 0x80];
+        for (address, value) in values {
+            data[*address] = *value;
+        }
+        cells_from_rmd(RMD, &data)
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let cells = read_cells(&[]);
+        assert_eq!(
+            cells[0x07].active_if,
+            vec![MkCellCondition {
+                address: 0x0E,
+                operator: MkConditionOperator::Equal,
+                value: 2,
+            }]
+        );
+        let rules: Vec<String> = cells[0x5D]
+            .rules
+            .iter()
+            .map(|rule| rule.to_string())
+            .collect();
+        assert_eq!(rules, vec!["0x0E == 3 IF 0x5D == 1", "0x03 <= 1"]);
+    }
+
+    #[test]
+    fn test_find_inactive_cells() {
+        // the router timeout is only used by routers
+        assert_eq!(find_inactive_cells(&read_cells(&[(0x0E, 1)])), vec![0x07]);
+        assert!(find_inactive_cells(&read_cells(&[(0x0E, 2)])).is_empty());
+    }
+
+    #[test]
+    fn test_check_cell_rules() {
+        // sleep is only allowed for end devices
+        assert!(check_cell_rules(&read_cells(&[(0x0E, 3), (0x5D, 1)])).is_empty());
+        assert!(check_cell_rules(&read_cells(&[(0x0E, 1), (0x5D, 0)])).is_empty());
+        let violations = check_cell_rules(&read_cells(&[(0x0E, 1), (0x5D, 1), (0x03, 2)]));
+        let messages: Vec<String> = violations
+            .iter()
+            .map(|violation| violation.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Cell 0x5D (Sleep or RTS) requires 0x0E == 3 IF 0x5D == 1",
+                "Cell 0x5D (Sleep or RTS) requires 0x03 <= 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_cell_changes_checks_rules() {
+        let previous = read_cells(&[(0x0E, 3), (0x5D, 1)]);
+        let mut requested = previous.clone();
+        requested[0x0E].current_value = 1;
+        assert_eq!(
+            validate_cell_changes(&previous, &requested, &[]),
+            Err(vec![CellValidationError::RuleViolated {
+                address: 0x5D,
+                name: "Sleep or RTS".to_string(),
+                rule: "0x0E == 3 IF 0x5D == 1".to_string(),
+            }])
+        );
+        requested[0x5D].current_value = 0;
+        assert!(validate_cell_changes(&previous, &requested, &[]).is_ok());
+
+        // a rule the device already breaks doesn't block other changes
+        let previous = read_cells(&[(0x0E, 1), (0x5D, 1)]);
+        let mut requested = previous.clone();
+        requested[0x07].current_value = 10;
+        assert!(validate_cell_changes(&previous, &requested, &[]).is_ok());
+    }
+}
@@ -0,0 +1,140 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{create_emulator, rmd_file_path};
+    use tinymesh_cc_tool::cell_validation::{
+        validate_cell_changes, validate_cell_value, CellValidationError,
+    };
+    use tinymesh_cc_tool::data_types::MkDeviceCell;
+    use tinymesh_cc_tool::tinymesh_config_mod::{
+        get_device_config_from_device, set_device_config_on_device,
+    };
+    use tinymesh_cc_tool::tinymesh_serial_util::DeviceError;
+
+    fn range_cell(address: usize, min_value: u8, max_value: u8, current_value: u8) -> MkDeviceCell {
+        MkDeviceCell {
+            address,
+            name: format!("Cell {}", address),
+            min_value,
+            max_value,
+            current_value,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_cell_value() {
+        let cell = range_cell(1, 1, 5, 5);
+        assert!(validate_cell_value(&cell, 1).is_ok());
+        assert!(validate_cell_value(&cell, 5).is_ok());
+        assert_eq!(
+            validate_cell_value(&cell, 6),
+            Err(CellValidationError::OutOfRange {
+                address: 1,
+                name: "Cell 1".to_string(),
+                value: 6,
+                min_value: 1,
+                max_value: 5,
+            })
+        );
+
+        let cell = MkDeviceCell {
+            allowed_values: vec![0, 1, 4],
+            ..range_cell(2, 0, 0, 0)
+        };
+        assert!(validate_cell_value(&cell, 4).is_ok());
+        assert!(matches!(
+            validate_cell_value(&cell, 2),
+            Err(CellValidationError::NotAllowed { value: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_cell_without_min_max_is_not_writable() {
+        // neither MIN_MAX nor ALLOW, so no value is known to be accepted
+        let cell = range_cell(3, 0, 0, 0);
+        for value in [0, 1, 200] {
+            assert_eq!(
+                validate_cell_value(&cell, value),
+                Err(CellValidationError::NotWritable {
+                    address: 3,
+                    name: "Cell 3".to_string(),
+                })
+            );
+        }
+
+        let previous = vec![range_cell(0, 1, 5, 1), cell];
+        let mut requested = previous.clone();
+        requested[1].current_value = 1;
+        assert_eq!(
+            validate_cell_changes(&previous, &requested, &[]),
+            Err(vec![CellValidationError::NotWritable {
+                address: 3,
+                name: "Cell 3".to_string(),
+            }])
+        );
+        // unchanged cells are not validated
+        assert!(validate_cell_changes(&previous, &previous, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_validate_cell_changes() {
+        let previous = vec![range_cell(0, 1, 5, 1), range_cell(1, 1, 5, 1), range_cell(2, 1, 5, 1)];
+        let mut requested = previous.clone();
+        requested[0].current_value = 9;
+        requested[1].current_value = 2;
+        let errors = validate_cell_changes(&previous, &requested, &[1]).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0], CellValidationError::OutOfRange { address: 0, .. }));
+        assert!(matches!(errors[1], CellValidationError::Locked { address: 1, .. }));
+
+        // unchanged locked cells are fine
+        assert!(validate_cell_changes(&previous, &previous, &[1]).is_ok());
+
+        // the constraints sent by the frontend are ignored
+        let mut requested = previous.clone();
+        requested[2].max_value = 255;
+        requested[2].current_value = 200;
+        assert!(validate_cell_changes(&previous, &requested, &[]).is_err());
+
+        // cells are matched by address, not by position
+        let requested = vec![range_cell(2, 1, 5, 3), range_cell(0, 1, 5, 9)];
+        assert_eq!(
+            validate_cell_changes(&previous, &requested, &[]),
+            Err(vec![CellValidationError::OutOfRange {
+                address: 0,
+                name: "Cell 0".to_string(),
+                value: 9,
+                min_value: 1,
+                max_value: 5,
+            }])
+        );
+
+        // cells that don't match the module description are rejected
+        let requested = vec![range_cell(7, 1, 5, 2)];
+        assert_eq!(
+            validate_cell_changes(&previous, &requested, &[]),
+            Err(vec![CellValidationError::UnknownCell { address: 7 }])
+        );
+    }
+
+    #[test]
+    fn test_invalid_write_is_not_sent() {
+        let emulator = create_emulator();
+        let mut transport = emulator.transport();
+        let device_config =
+            get_device_config_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        transport.take_tx();
+
+        // RF Power only accepts 1..=5
+        let mut cells = device_config.cells.clone();
+        cells[1].current_value = 9;
+        assert!(matches!(
+            set_device_config_on_device(&mut transport, &device_config, &cells, None),
+            Err(DeviceError::InvalidCells(_))
+        ));
+        assert!(transport.take_tx().is_empty());
+        assert_eq!(emulator.config_memory()[1], 5);
+    }
+}
@@ -0,0 +1,168 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::cells_from_rmd;
+    use tinymesh_cc_tool::cell_values::{
+        decode_typed_values, set_engineering_value, set_typed_value,
+    };
+    use tinymesh_cc_tool::data_types::{MkCellScaling, MkDeviceCell, MkTypedValue};
+
+    const RMD: &str = "[DEVICE_MODEL]\nRF TM4070\n\
+        [M 0x21 NAME]\nGPIO 0 Analog High Trig\n[M 0x21 SIZE]\n2\n\
+        [M 0x2D NAME]\nUnique ID\n[M 0x2D SIZE]\n4\n[M 0x2D ENDIAN]\nLITTLE\n\
+        [M 0x40 NAME]\nOptions\n[M 0x40 BITS]\n0 Sleep enable\n4-7 Wake-up interval\n\
+        [M 0x01 NAME]\nRF Power\n[M 0x01 HINT]\n1 : -20 dBm\n2 : -10 dBm\n\
+        [M 0x07 NAME]\nRouter timeout\n[M 0x07 SCALE]\n0.1\n[M 0x07 UNIT]\ns\n\
+        [M 0x08 NAME]\nTemperature limit\n[M 0x08 OFFSET]\n-128\n[M 0x08 UNIT]\n\u{00B0}C\n";
+
+    fn read_cells(data: &[u8]) -> Vec<MkDeviceCell> {
+        cells_from_rmd(RMD, data)
+    }
+
+    fn test_data() -> Vec<u8> {
+        let mut data = vec![0; 0x80];
+        data[0x21] = 0x03;
+        data[0x22] = 0xE8;
+        data[0x2D..=0x30].copy_from_slice(&[0x78, 0x56, 0x34, 0x12]);
+        data[0x40] = 0x51;
+        data
+    }
+
+    #[test]
+    fn test_decode_typed_values() {
+        let typed_values = decode_typed_values(&read_cells(&test_data()));
+        let values: Vec<(usize, usize, u32)> = typed_values
+            .iter()
+            .map(|value| (value.address, value.size, value.value))
+            .collect();
+        assert_eq!(
+            values,
+            vec![(0x21, 2, 1000), (0x2D, 4, 0x12345678), (0x40, 1, 0x51)]
+        );
+        let options: &MkTypedValue = &typed_values[2];
+        let bits: Vec<(&str, u8)> = options
+            .bits
+            .iter()
+            .map(|bits| (bits.name.as_str(), bits.value))
+            .collect();
+        assert_eq!(bits, vec![("Sleep enable", 1), ("Wake-up interval", 5)]);
+
+        // a value whose bytes are beyond the cells is left out
+        assert_eq!(
+            decode_typed_values(&read_cells(&test_data()[..0x2F])).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_set_typed_value() {
+        let mut cells = read_cells(&test_data());
+        // only the bytes that change are returned
+        assert_eq!(
+            set_typed_value(&mut cells, 0x21, None, 1001),
+            Ok(vec![(0x22, 0xE9)])
+        );
+        assert_eq!(
+            set_typed_value(&mut cells, 0x2D, None, 0xAA345678),
+            Ok(vec![(0x30, 0xAA)])
+        );
+        assert_eq!(
+            set_typed_value(&mut cells, 0x40, Some("Wake-up interval"), 2),
+            Ok(vec![(0x40, 0x21)])
+        );
+        assert_eq!(
+            set_typed_value(&mut cells, 0x40, Some("Sleep enable"), 1),
+            Ok(vec![])
+        );
+        let values: Vec<u32> = decode_typed_values(&cells)
+            .iter()
+            .map(|value| value.value)
+            .collect();
+        assert_eq!(values, vec![1001, 0xAA345678, 0x21]);
+
+        assert_eq!(
+            set_typed_value(&mut cells, 0x21, None, 0x10000),
+            Err("65536 does not fit in the 2 bytes of cell 0x21".to_string())
+        );
+        assert_eq!(
+            set_typed_value(&mut cells, 0x40, Some("Wake-up interval"), 16),
+            Err("16 does not fit in bits 4-7 of cell 0x40".to_string())
+        );
+        assert!(set_typed_value(&mut cells, 0x40, Some("Unknown"), 1).is_err());
+        assert_eq!(
+            set_typed_value(&mut cells, 0x01, None, 7),
+            Ok(vec![(0x01, 7)])
+        );
+        // nothing is changed by a rejected value
+        assert_eq!(cells[0x22].current_value, 0xE9);
+    }
+
+    #[test]
+    fn test_engineering_values() {
+        let cells = read_cells(&test_data());
+        let timeout = &cells[0x07];
+        assert_eq!(
+            timeout.scaling,
+            Some(MkCellScaling {
+                scale: 0.1,
+                offset: 0.0,
+                unit: Some("s".to_string()),
+            })
+        );
+        assert_eq!(timeout.unit(), Some("s"));
+        assert_eq!(timeout.format_value(50), "5 s");
+        assert_eq!(timeout.from_engineering(0.3), Ok(3));
+        assert_eq!(timeout.parse_engineering("5 s"), Ok(50));
+        assert_eq!(timeout.parse_engineering(" 5.5  s"), Ok(55));
+        assert_eq!(
+            timeout.parse_engineering("5.05 s"),
+            Err(
+                "5.05 s can't be set in cell 0x07 (Router timeout), its steps are 0.1 s"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            timeout.parse_engineering("30 s"),
+            Err("30 s is outside 0 s..=25.5 s for cell 0x07 (Router timeout)".to_string())
+        );
+        assert_eq!(
+            timeout.parse_engineering("5 ms"),
+            Err("'5 ms' has the unit ms, cell 0x07 (Router timeout) is in s".to_string())
+        );
+
+        let temperature = &cells[0x08];
+        assert_eq!(temperature.to_engineering(100), -28.0);
+        assert_eq!(temperature.parse_engineering("-10"), Ok(118));
+        assert_eq!(temperature.format_value(128), "0 \u{00B0}C");
+
+        // the labels of the cell are accepted, with or without their unit
+        let rf_power = &cells[0x01];
+        assert_eq!(rf_power.parse_engineering("-10 dBm"), Ok(2));
+        assert_eq!(rf_power.parse_engineering("-20"), Ok(1));
+        assert_eq!(rf_power.format_value(2), "-10 dBm");
+        assert!(rf_power.parse_engineering("-5 dBm").is_err());
+
+        // cells without scaling take raw values
+        assert_eq!(cells[0x02].parse_engineering("12"), Ok(12));
+        assert!(cells[0x02].parse_engineering("256").is_err());
+        assert!(cells[0x02].parse_engineering("fast").is_err());
+
+        // multi-byte values are set as a whole
+        let mut cells = cells;
+        cells[0x21].scaling = Some(MkCellScaling {
+            scale: 0.5,
+            offset: 0.0,
+            unit: Some("mV".to_string()),
+        });
+        assert_eq!(
+            set_engineering_value(&mut cells, 0x21, "1000.5 mV"),
+            Ok(vec![(0x21, 0x07), (0x22, 0xD1)])
+        );
+        assert_eq!(cells[0x21].format_value(2001), "1000.5 mV");
+        assert_eq!(
+            set_engineering_value(&mut cells, 0x07, "2 s"),
+            Ok(vec![(0x07, 20)])
+        );
+    }
+}
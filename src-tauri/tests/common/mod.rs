@@ -0,0 +1,92 @@
+//! Fixtures shared by the integration tests.
+//! Every test file compiles this module on its own, so helpers one of them doesn't use are not dead code.
+#![allow(dead_code)]
+
+use std::fs::{create_dir_all, read_to_string, remove_dir_all};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tinymesh_cc_tool::data_types::MkDeviceCell;
+use tinymesh_cc_tool::device_emulator::TinyMeshEmulator;
+use tinymesh_cc_tool::mk_module_description::MkModuleDescription;
+
+/// Returns the path of a file in `resources/tests`.
+pub fn resource_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("resources/tests")
+        .join(name)
+}
+
+/// Returns the path of the bundled RF TM4070 RMD file.
+pub fn rmd_file_path() -> PathBuf {
+    resource_path("RF TM4070.rmd")
+}
+
+/// Reads the bundled RF TM4070 RMD file.
+pub fn read_rmd() -> String {
+    read_to_string(rmd_file_path()).unwrap()
+}
+
+/// Reads the configuration memory dump of `resources/tests/config_response.txt` as bytes.
+pub fn read_config_response() -> Vec<u8> {
+    read_to_string(resource_path("config_response.txt"))
+        .unwrap()
+        .split_whitespace()
+        .map(|s| u8::from_str_radix(s, 16).unwrap())
+        .collect()
+}
+
+/// Creates an emulated RF TM4070 with the bundled RMD file and configuration dump.
+pub fn create_emulator() -> TinyMeshEmulator {
+    let config_response = read_to_string(resource_path("config_response.txt")).unwrap();
+    TinyMeshEmulator::from_rmd_and_hex_dump(&read_rmd(), &config_response).unwrap()
+}
+
+/// Decodes a configuration memory dump with the cells of an RMD file, one cell per byte of `data`.
+pub fn cells_from_rmd(rmd: &str, data: &[u8]) -> Vec<MkDeviceCell> {
+    let module_description = MkModuleDescription::new(rmd).unwrap();
+    data.iter()
+        .enumerate()
+        .map(|(address, value)| MkDeviceCell {
+            current_value: *value,
+            ..module_description.cells[address].clone()
+        })
+        .collect()
+}
+
+/// A temporary directory of a single test, removed with its content when dropped.
+/// The name is unique per process and call, so tests running in parallel never share files.
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// Creates an empty directory in the system temp dir, named after `name`.
+    pub fn new(name: &str) -> TestDir {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "tinymesh_{}_{}_{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = remove_dir_all(&path);
+        create_dir_all(&path).unwrap();
+        TestDir { path }
+    }
+
+    /// Returns the path of the directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the path of `name` inside the directory.
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.path);
+    }
+}
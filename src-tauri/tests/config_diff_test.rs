@@ -0,0 +1,160 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{cells_from_rmd, TestDir};
+    use tinymesh_cc_tool::config_diff::{
+        diff_device_configs, resolve_config_source, MkCellDiff, MkConfigDiff, MkConfigSource,
+    };
+    use tinymesh_cc_tool::config_profile::MkConfigProfile;
+    use tinymesh_cc_tool::data_types::MkDeviceConfig;
+
+    const RMD: &str = "[DEVICE_MODEL]\nRF TM4070\n\
+        [M 0x00 NAME]\nRF Channel\n[M 0x00 DEF]\n1\n\
+        [M 0x01 NAME]\nRF Power\n[M 0x01 DEF]\n5\n[M 0x01 ENUM]\n2 : -10 : dBm\n5 : 11 : dBm\n\
+        [M 0x02 NAME]\nRouter timeout\n[M 0x02 SCALE]\n0.1\n[M 0x02 UNIT]\ns\n\
+        [M 0x03 NAME]\nGPIO 0 Analog High Trig\n[M 0x03 SIZE]\n2\n";
+
+    fn device_config(data: &[u8]) -> MkDeviceConfig {
+        MkDeviceConfig {
+            model: "RF TM4070".to_string(),
+            hw_version: "1.00".to_string(),
+            firmware_version: "1.53".to_string(),
+            cells: cells_from_rmd(RMD, data),
+            locked_cells: vec![1],
+            ..Default::default()
+        }
+    }
+
+    fn names() -> (String, String) {
+        ("old".to_string(), "new".to_string())
+    }
+
+    #[test]
+    fn test_diff_device_configs() {
+        let old = device_config(&[1, 5, 50, 0x03, 0xE8]);
+        let new = device_config(&[1, 2, 75, 0x03, 0xE9]);
+        let diff = diff_device_configs(&old, &new, names());
+        assert_eq!(
+            diff.cells,
+            vec![
+                MkCellDiff {
+                    address: 1,
+                    name: "RF Power".to_string(),
+                    size: 1,
+                    old_value: Some(5),
+                    new_value: Some(2),
+                    old_engineering_value: Some("11 dBm".to_string()),
+                    new_engineering_value: Some("-10 dBm".to_string()),
+                    locked: true,
+                },
+                MkCellDiff {
+                    address: 2,
+                    name: "Router timeout".to_string(),
+                    size: 1,
+                    old_value: Some(50),
+                    new_value: Some(75),
+                    old_engineering_value: Some("5 s".to_string()),
+                    new_engineering_value: Some("7.5 s".to_string()),
+                    locked: false,
+                },
+                MkCellDiff {
+                    address: 3,
+                    name: "GPIO 0 Analog High Trig".to_string(),
+                    size: 2,
+                    old_value: Some(1000),
+                    new_value: Some(1001),
+                    old_engineering_value: Some("1000".to_string()),
+                    new_engineering_value: Some("1001".to_string()),
+                    locked: false,
+                },
+            ]
+        );
+
+        // a cell only one configuration has is reported without a value on the other side
+        let diff = diff_device_configs(&old, &device_config(&[1, 5, 50]), names());
+        assert_eq!(diff.cells.len(), 1);
+        assert_eq!(
+            (
+                diff.cells[0].address,
+                diff.cells[0].old_value,
+                diff.cells[0].new_value
+            ),
+            (3, Some(1000), None)
+        );
+
+        assert!(diff_device_configs(&old, &old, names()).cells.is_empty());
+    }
+
+    #[test]
+    fn test_config_diff_export() {
+        let old = device_config(&[1, 5, 50, 0x03, 0xE8]);
+        let new = device_config(&[2, 2, 50, 0x03, 0xE9]);
+        let diff = diff_device_configs(&old, &new, names());
+        assert_eq!(
+            diff.to_string(),
+            "--- old\n+++ new\n\
+            0x00 RF Channel: 1 -> 2\n\
+            0x01 RF Power: 5 (11 dBm) -> 2 (-10 dBm) [locked]\n\
+            0x03-0x04 GPIO 0 Analog High Trig: 1000 -> 1001\n"
+        );
+        assert_eq!(
+            MkConfigDiff::default().to_string(),
+            "--- \n+++ \nNo differences\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&diff.to_json().unwrap()).unwrap();
+        assert_eq!(json["cells"][1]["new_engineering_value"], "-10 dBm");
+        assert_eq!(json["cells"][1]["locked"], true);
+
+        let test_dir = TestDir::new("config_diff_export");
+        let file_path = test_dir.join("diff.json");
+        diff.save(&file_path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            diff.to_json().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_resolve_config_source() {
+        let device_config = device_config(&[3, 2, 50, 0x03, 0xE8]);
+
+        let (config, name) =
+            resolve_config_source(&MkConfigSource::Device, &device_config).unwrap();
+        assert_eq!(name, "RF TM4070 (HW 1.00, FW 1.53)");
+        assert_eq!(config.cells[0].current_value, 3);
+
+        let (defaults, name) =
+            resolve_config_source(&MkConfigSource::Defaults, &device_config).unwrap();
+        assert_eq!(name, "RMD defaults of RF TM4070");
+        let diff = diff_device_configs(&device_config, &defaults, names());
+        let changes: Vec<(usize, Option<u32>)> = diff
+            .cells
+            .iter()
+            .map(|cell| (cell.address, cell.new_value))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![(0, Some(1)), (1, Some(5)), (2, Some(0)), (3, Some(0))]
+        );
+
+        // the locked cells of a profile are part of the diff, unlike when the profile is imported
+        let mut profile = MkConfigProfile::from_device_config(&device_config);
+        profile.cells[1].value = 5;
+        let test_dir = TestDir::new("config_diff_source");
+        let file_path = test_dir.join("profile.json");
+        profile.save(&file_path).unwrap();
+        let source = MkConfigSource::Profile {
+            file_path: file_path.to_string_lossy().to_string(),
+        };
+        let (config, _) = resolve_config_source(&source, &device_config).unwrap();
+        let diff = diff_device_configs(&device_config, &config, names());
+        assert_eq!(diff.cells.len(), 1);
+        assert_eq!((diff.cells[0].address, diff.cells[0].locked), (1, true));
+
+        profile.model = "RF TM1000".to_string();
+        profile.save(&file_path).unwrap();
+        assert!(resolve_config_source(&source, &device_config).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{create_emulator, rmd_file_path, TestDir};
+    use tinymesh_cc_tool::config_profile::{MkConfigProfile, CONFIG_PROFILE_VERSION};
+    use tinymesh_cc_tool::data_types::MkCellWriteStatus;
+    use tinymesh_cc_tool::device_emulator::TinyMeshEmulator;
+    use tinymesh_cc_tool::tinymesh_config_mod::{
+        clone_config_to_unit, get_device_config_from_device, import_config_profile_to_device,
+    };
+
+    fn read_profile(emulator: &TinyMeshEmulator) -> MkConfigProfile {
+        let mut transport = emulator.transport();
+        let device_config =
+            get_device_config_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        MkConfigProfile::from_device_config(&device_config)
+    }
+
+    #[test]
+    fn test_profile_round_trip() {
+        let profile = read_profile(&create_emulator());
+        assert_eq!(profile.version, CONFIG_PROFILE_VERSION);
+        assert_eq!(profile.model, "RF TM4070");
+        assert_eq!(profile.cells[1].value, 5);
+
+        let test_dir = TestDir::new("config_profile");
+        let file_path = test_dir.join("profile.json");
+        profile.save(&file_path).unwrap();
+        assert_eq!(MkConfigProfile::load(&file_path), Ok(profile));
+    }
+
+    #[test]
+    fn test_profile_version_is_checked() {
+        let mut profile = read_profile(&create_emulator());
+        profile.version = CONFIG_PROFILE_VERSION + 1;
+        assert!(MkConfigProfile::from_json(&profile.to_json().unwrap()).is_err());
+        assert!(MkConfigProfile::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_apply_profile_skips_locked_cells() {
+        let emulator = create_emulator();
+        let mut transport = emulator.transport();
+        let mut device_config =
+            get_device_config_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        device_config.locked_cells = vec![2];
+        let mut profile = MkConfigProfile::from_device_config(&device_config);
+        profile.cells[1].value = 3;
+        profile.cells[2].value = 9;
+        let cells = profile.apply_to(&device_config).unwrap();
+        assert_eq!(cells[1].current_value, 3);
+        assert_eq!(cells[2].current_value, device_config.cells[2].current_value);
+
+        profile.model = "RF TM1000".to_string();
+        assert!(profile.apply_to(&device_config).is_err());
+    }
+
+    #[test]
+    fn test_import_profile_writes_changed_cells() {
+        let mut profile = read_profile(&create_emulator());
+        profile.cells[1].value = 3;
+
+        let emulator = create_emulator();
+        let mut transport = emulator.transport();
+        let (report, device_config) =
+            import_config_profile_to_device(&mut transport, &profile, Some(&rmd_file_path()), None)
+                .unwrap();
+        assert_eq!(report.cells.len(), 1);
+        assert_eq!(report.cells[0].status, MkCellWriteStatus::Written);
+        assert_eq!(device_config.cells[1].current_value, 3);
+        assert_eq!(emulator.config_memory()[1], 3);
+
+        // importing the same profile again doesn't write anything
+        transport.take_tx();
+        let (report, _) =
+            import_config_profile_to_device(&mut transport, &profile, Some(&rmd_file_path()), None)
+                .unwrap();
+        assert!(report.cells.is_empty());
+        assert_eq!(transport.take_tx(), b"0");
+    }
+
+    #[test]
+    fn test_clone_config_to_units() {
+        let mut golden_profile = read_profile(&create_emulator());
+        golden_profile.cells[1].value = 3;
+        golden_profile.cells[2].value = 8;
+
+        let emulator = create_emulator();
+        emulator.set_config_mode(false);
+        let mut transport = emulator.transport();
+        let unit_report = clone_config_to_unit(
+            "unit 1",
+            &mut transport,
+            &golden_profile,
+            &[2],
+            Some(&rmd_file_path()),
+            None,
+        );
+        assert!(unit_report.passed, "{:?}", unit_report.error);
+        assert_eq!(unit_report.model.as_deref(), Some("RF TM4070"));
+        assert_eq!(unit_report.write_report.cells.len(), 1);
+        assert_eq!(emulator.config_memory()[1], 3);
+        // unique cells keep the value of the unit
+        assert_ne!(emulator.config_memory()[2], 8);
+        // the unit is returned to transparent mode
+        assert!(!emulator.is_in_config_mode());
+
+        golden_profile.model = "RF TM1000".to_string();
+        let emulator = create_emulator();
+        emulator.set_config_mode(false);
+        let unit_report = clone_config_to_unit(
+            "unit 2",
+            &mut emulator.transport(),
+            &golden_profile,
+            &[],
+            Some(&rmd_file_path()),
+            None,
+        );
+        assert!(!unit_report.passed);
+        assert!(unit_report.error.is_some());
+        assert_eq!(emulator.config_memory()[1], 5);
+    }
+}
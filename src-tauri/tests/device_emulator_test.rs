@@ -0,0 +1,346 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{create_emulator, rmd_file_path};
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    use tinymesh_cc_tool::data_types::{DeviceMode, MkCellWriteStatus, MkDeviceCell};
+    use tinymesh_cc_tool::device_emulator::parse_hex_dump;
+    use tinymesh_cc_tool::tinymesh_calibration_mod::{
+        get_device_calib_from_device, set_and_verify_device_calib_on_device,
+    };
+    use tinymesh_cc_tool::tinymesh_config_mod::{
+        build_write_report, enter_config_mode_on_device, exit_config_mode_on_device,
+        get_device_config_from_device, set_and_verify_device_config_on_device,
+        set_device_config_on_device, with_config_mode,
+    };
+    use tinymesh_cc_tool::tinymesh_device_info_mod::{
+        get_rssi_from_device, run_rssi_stream, switch_to_channel,
+    };
+
+    #[test]
+    fn test_parse_hex_dump() {
+        assert_eq!(parse_hex_dump("04 05 0xFF\n3E"), Ok(vec![0x04, 0x05, 0xFF, 0x3E]));
+        assert!(parse_hex_dump("04 XY").is_err());
+    }
+
+    #[test]
+    fn test_config_dump() {
+        let emulator = create_emulator();
+        let response = emulator.handle_bytes(b"0");
+        assert_eq!(response.len(), 129);
+        assert_eq!(response[0], 0x04);
+        assert_eq!(response.last(), Some(&b'>'));
+    }
+
+    #[test]
+    fn test_get_and_set_device_config() {
+        let emulator = create_emulator();
+        let mut transport = emulator.transport();
+        let device_config =
+            get_device_config_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        assert_eq!(device_config.model, "RF TM4070");
+        assert_eq!(device_config.cells[1].current_value, 5);
+        // the RF Channel cell is set to channel 4
+        assert_eq!(device_config.cells[0].frequency_mhz, Some(865.7));
+
+        let mut cells = device_config.cells.clone();
+        cells[1].current_value = 3;
+        cells[2].current_value = 8;
+        assert!(set_device_config_on_device(&mut transport, &device_config, &cells, None).is_ok());
+        assert_eq!(emulator.config_memory()[1], 3);
+        assert_eq!(emulator.config_memory()[2], 8);
+
+        let device_config =
+            get_device_config_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        assert_eq!(device_config.cells[1].current_value, 3);
+    }
+
+    #[test]
+    fn test_get_device_calib() {
+        let emulator = create_emulator();
+        let mut transport = emulator.transport();
+        let device_calib =
+            get_device_calib_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        assert_eq!(device_calib.model, "RF TM4070");
+        assert_eq!(device_calib.calibration_cells[0].name, "Temp Offset");
+        assert_eq!(device_calib.calibration_cells[0].current_value, 128);
+    }
+
+    #[test]
+    fn test_calibration_write() {
+        let emulator = create_emulator();
+        assert_eq!(emulator.handle_bytes(b"HW"), b">");
+        assert_eq!(emulator.handle_bytes(&[0x00, 130, 0x03, 5, 0xFF]), b">");
+        assert_eq!(emulator.calibration_memory()[0], 130);
+        assert_eq!(emulator.calibration_memory()[3], 5);
+    }
+
+    #[test]
+    fn test_rssi_per_channel() {
+        let emulator = create_emulator();
+        emulator.set_rssi(2, 140);
+        let mut transport = emulator.transport();
+        assert!(switch_to_channel(2, &mut transport, None).is_ok());
+        assert_eq!(emulator.channel(), 2);
+        assert_eq!(get_rssi_from_device(&mut transport, None), Ok(140));
+        assert!(switch_to_channel(3, &mut transport, None).is_ok());
+        assert_eq!(get_rssi_from_device(&mut transport, None), Ok(200));
+    }
+
+    #[test]
+    fn test_factory_reset() {
+        let emulator = create_emulator();
+        emulator.handle_bytes(&[b'M', 0x01, 2, 0xFF]);
+        assert_eq!(emulator.config_memory()[1], 2);
+        assert_eq!(emulator.handle_bytes(b"@TM"), b">");
+        assert_eq!(emulator.config_memory()[1], 5);
+    }
+
+    #[test]
+    fn test_sensor_commands() {
+        let emulator = create_emulator();
+        emulator.set_sensor_values(&[0x01, 0x02, 0x03, 0x04], 0x0F, 150, 100);
+        assert_eq!(emulator.handle_bytes(b"A"), vec![0x01, 0x02, 0x03, 0x04, b'>']);
+        assert_eq!(emulator.handle_bytes(b"D"), vec![0x0F, b'>']);
+        assert_eq!(emulator.handle_bytes(b"U"), vec![150, b'>']);
+        assert_eq!(emulator.handle_bytes(b"V"), vec![100, b'>']);
+    }
+
+    #[test]
+    fn test_mode_sequences_and_split_commands() {
+        let emulator = create_emulator();
+        assert_eq!(emulator.handle_bytes(b"G"), b">");
+        assert_eq!(emulator.handle_bytes(b"1"), b">");
+        // multi-byte commands may arrive in separate writes
+        assert!(emulator.handle_bytes(b"@T").is_empty());
+        assert_eq!(emulator.handle_bytes(b"M"), b">");
+        // unknown commands are ignored
+        assert!(emulator.handle_bytes(b"Q").is_empty());
+        assert_eq!(
+            emulator.received_commands(),
+            vec![b"G".to_vec(), b"1".to_vec(), b"@TM".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_enter_and_exit_config_mode() {
+        let emulator = create_emulator();
+        let mut transport = emulator.transport();
+        assert!(exit_config_mode_on_device(&mut transport, None).is_ok());
+        assert!(!emulator.is_in_config_mode());
+        // commands are sent over the air in transparent mode
+        assert!(emulator.handle_bytes(b"S").is_empty());
+        assert_eq!(emulator.transparent_data(), b"S");
+
+        assert!(enter_config_mode_on_device(&mut transport, None).is_ok());
+        assert!(emulator.is_in_config_mode());
+        assert_eq!(get_rssi_from_device(&mut transport, None), Ok(200));
+    }
+
+    #[test]
+    fn test_device_mode_is_readable_during_rssi_stream() {
+        let emulator = create_emulator();
+        emulator.set_config_mode(false);
+        emulator.set_rssi(2, 140);
+        let device_mode = Arc::new(Mutex::new(DeviceMode::Transparent));
+        let is_running = Arc::new(Mutex::new(true));
+        let (sender, receiver) = mpsc::channel();
+
+        let mut transport = emulator.transport();
+        let stream = {
+            let (device_mode, is_running) = (device_mode.clone(), is_running.clone());
+            std::thread::spawn(move || {
+                run_rssi_stream(
+                    &mut transport,
+                    &device_mode,
+                    &is_running,
+                    1..=2,
+                    None,
+                    |channel, rssi| {
+                        let _ = sender.send((channel, rssi));
+                        Ok(())
+                    },
+                )
+            })
+        };
+        assert_eq!(receiver.recv().unwrap(), (1, 200));
+        assert_eq!(receiver.recv().unwrap(), (2, 140));
+        // the stream keeps the port, but not the mode
+        assert_eq!(*device_mode.try_lock().unwrap(), DeviceMode::Config);
+
+        *is_running.lock().unwrap() = false;
+        assert!(stream.join().unwrap().is_ok());
+        assert_eq!(*device_mode.lock().unwrap(), DeviceMode::Transparent);
+        assert!(!emulator.is_in_config_mode());
+    }
+
+    #[test]
+    fn test_rssi_stream_stops_and_restores_mode_if_a_reading_fails() {
+        let emulator = create_emulator();
+        emulator.set_config_mode(false);
+        let device_mode = Mutex::new(DeviceMode::Transparent);
+        let is_running = Mutex::new(true);
+        let mut readings = 0;
+
+        let result = run_rssi_stream(
+            &mut emulator.transport(),
+            &device_mode,
+            &is_running,
+            1..=2,
+            None,
+            |_, _| {
+                readings += 1;
+                Err("the window is gone".to_string())
+            },
+        );
+        assert!(result.is_ok());
+        assert_eq!(readings, 1);
+        assert_eq!(*device_mode.lock().unwrap(), DeviceMode::Transparent);
+        assert!(!emulator.is_in_config_mode());
+    }
+
+    #[test]
+    fn test_with_config_mode_restores_mode() {
+        let emulator = create_emulator();
+        emulator.set_config_mode(false);
+        let mut transport = emulator.transport();
+        let mut device_mode = DeviceMode::Transparent;
+        let device_config = with_config_mode(&mut transport, &mut device_mode, None, |device| {
+            get_device_config_from_device(device, Some(&rmd_file_path()), None)
+        })
+        .unwrap();
+        assert_eq!(device_config.model, "RF TM4070");
+        assert_eq!(device_mode, DeviceMode::Transparent);
+        assert!(!emulator.is_in_config_mode());
+
+        // a module switched by hand is assumed to be at the prompt and is left alone
+        emulator.set_config_mode(true);
+        let mut device_mode = DeviceMode::Unknown;
+        assert!(with_config_mode(&mut transport, &mut device_mode, None, |device| {
+            get_rssi_from_device(device, None)
+        })
+        .is_ok());
+        assert_eq!(device_mode, DeviceMode::Unknown);
+        assert!(emulator.is_in_config_mode());
+    }
+
+    #[test]
+    fn test_set_and_verify_device_config() {
+        let emulator = create_emulator();
+        let mut transport = emulator.transport();
+        let device_config =
+            get_device_config_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        let mut cells = device_config.cells.clone();
+        cells[1].current_value = 3;
+        let (report, read_back_config) = set_and_verify_device_config_on_device(
+            &mut transport,
+            &device_config,
+            &cells,
+            Some(&rmd_file_path()),
+            None,
+        )
+        .unwrap();
+        assert!(report.is_successful());
+        assert_eq!(report.cells.len(), 1);
+        assert_eq!(report.cells[0].address, 1);
+        assert_eq!(report.cells[0].read_back_value, Some(3));
+        assert_eq!(read_back_config.cells[1].current_value, 3);
+    }
+
+    #[test]
+    fn test_set_and_verify_device_calib() {
+        let emulator = create_emulator();
+        let mut transport = emulator.transport();
+        let device_calib =
+            get_device_calib_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        let mut cells = device_calib.calibration_cells.clone();
+        cells[0].current_value = 130;
+        let (report, _) = set_and_verify_device_calib_on_device(
+            &mut transport,
+            &device_calib,
+            &cells,
+            Some(&rmd_file_path()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(report.cells[0].status, MkCellWriteStatus::Written);
+        assert_eq!(emulator.calibration_memory()[0], 130);
+    }
+
+    #[test]
+    fn test_write_report_statuses() {
+        let cell = |address: usize, current_value: u8| MkDeviceCell {
+            address,
+            current_value,
+            ..Default::default()
+        };
+        let previous = vec![cell(0, 1), cell(1, 1), cell(2, 1), cell(3, 1)];
+        let requested = vec![cell(0, 2), cell(1, 2), cell(2, 2), cell(3, 1)];
+        let read_back = vec![cell(0, 2), cell(1, 1), cell(2, 7), cell(3, 1)];
+        let report = build_write_report(&previous, &requested, &read_back);
+        let statuses: Vec<MkCellWriteStatus> =
+            report.cells.iter().map(|cell| cell.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                MkCellWriteStatus::Written,
+                MkCellWriteStatus::Ignored,
+                MkCellWriteStatus::Failed
+            ]
+        );
+        assert!(!report.is_successful());
+    }
+
+    #[test]
+    fn test_write_report_matches_cells_by_address() {
+        let cell = |address: usize, current_value: u8| MkDeviceCell {
+            address,
+            current_value,
+            ..Default::default()
+        };
+        let previous = vec![cell(0, 1), cell(1, 1), cell(2, 1)];
+        // a subset in a different order, with an address the model does not have
+        let requested = vec![cell(2, 2), cell(9, 2), cell(0, 2)];
+        // the read-back misses address 2
+        let read_back = vec![cell(0, 2), cell(1, 1)];
+        let report = build_write_report(&previous, &requested, &read_back);
+        let results: Vec<(usize, Option<u8>, MkCellWriteStatus)> = report
+            .cells
+            .iter()
+            .map(|cell| (cell.address, cell.previous_value, cell.status))
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                (2, Some(1), MkCellWriteStatus::Failed),
+                (9, None, MkCellWriteStatus::Failed),
+                (0, Some(1), MkCellWriteStatus::Written)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_write_matches_cells_by_address() {
+        let emulator = create_emulator();
+        let mut transport = emulator.transport();
+        let device_config =
+            get_device_config_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        let mut cell = device_config.cells[1].clone();
+        cell.current_value = 3;
+        let (report, _) = set_and_verify_device_config_on_device(
+            &mut transport,
+            &device_config,
+            &[cell.clone()],
+            Some(&rmd_file_path()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(report.cells.len(), 1);
+        assert_eq!(report.cells[0].address, cell.address);
+        assert_eq!(report.cells[0].previous_value, Some(5));
+        assert_eq!(report.cells[0].status, MkCellWriteStatus::Written);
+        assert_eq!(emulator.config_memory()[cell.address], 3);
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests {
+    use tinymesh_cc_tool::device_protocol::{
+        execute_command, Command, ExpectedResponse, Exchange, Response,
+    };
+    use tinymesh_cc_tool::device_transport::MemoryTransport;
+    use tinymesh_cc_tool::tinymesh_serial_util::DeviceError;
+
+    #[test]
+    fn test_encode_memory_writes() {
+        assert_eq!(
+            Command::WriteConfig(vec![(0x01, 0x05), (0x02, 0x08)]).encode(),
+            vec![
+                Exchange {
+                    request: b"M".to_vec(),
+                    expected_response: ExpectedResponse::Prompt,
+                },
+                Exchange {
+                    request: vec![0x01, 0x05, 0x02, 0x08, 0xFF],
+                    expected_response: ExpectedResponse::Prompt,
+                },
+            ]
+        );
+        assert_eq!(
+            Command::WriteCalibration(vec![(0x00, 130)]).encode()[1].request,
+            vec![0x00, 130, 0xFF]
+        );
+        assert_eq!(Command::WriteCalibration(vec![]).encode()[0].request, b"HW");
+    }
+
+    #[test]
+    fn test_encode_commands() {
+        assert_eq!(Command::ReadConfig.encode()[0].request, b"0");
+        assert_eq!(Command::ReadCalibration.encode()[0].request, b"r");
+        assert_eq!(Command::FactoryReset.encode()[0].request, b"@TM");
+        assert_eq!(Command::GetTemperature.encode()[0].request, b"U");
+        assert_eq!(Command::EnterConfigMode.encode()[0].request, vec![0x00]);
+        assert_eq!(
+            Command::ExitConfigMode.encode(),
+            vec![Exchange {
+                request: b"X".to_vec(),
+                expected_response: ExpectedResponse::Nothing,
+            }]
+        );
+        let channel_switch = Command::SetChannel(7).encode();
+        assert_eq!(channel_switch[0].request, b"C");
+        assert_eq!(channel_switch[1].request, vec![7]);
+    }
+
+    #[test]
+    fn test_mode_sequence() {
+        let command = Command::from_mode_sequence("aG #>").unwrap();
+        assert_eq!(
+            command,
+            Command::ModeSequence {
+                send: b"G".to_vec(),
+                expect: b">".to_vec(),
+            }
+        );
+        assert_eq!(command.encode()[0].expected_response, ExpectedResponse::Data);
+        assert_eq!(command.decode(vec![]), Ok(Response::Ack));
+        assert!(Command::from_mode_sequence("aG").is_none());
+    }
+
+    #[test]
+    fn test_decode_responses() {
+        assert_eq!(Command::GetRssi.decode(vec![0x50]), Ok(Response::Byte(0x50)));
+        assert_eq!(
+            Command::GetRssi.decode(vec![]),
+            Err(DeviceError::UnexpectedResponse(vec![]))
+        );
+        assert_eq!(
+            Command::GetAnalog.decode(vec![0x01, 0x02]),
+            Ok(Response::Bytes(vec![0x01, 0x02]))
+        );
+        assert_eq!(
+            Command::FactoryReset.decode(vec![0x01]),
+            Err(DeviceError::UnexpectedResponse(vec![0x01]))
+        );
+    }
+
+    #[test]
+    fn test_execute_command() {
+        let mut transport = MemoryTransport::with_responder(Box::new(|bytes: &[u8]| match bytes {
+            b"0" => vec![0x04, 0x05, b'>'],
+            _ => vec![b'>'],
+        }));
+        assert_eq!(
+            execute_command(&mut transport, &Command::ReadConfig, None),
+            Ok(Response::Memory(vec![0x04, 0x05]))
+        );
+        assert_eq!(
+            execute_command(&mut transport, &Command::WriteConfig(vec![(0x01, 0x03)]), None),
+            Ok(Response::Ack)
+        );
+        assert_eq!(transport.take_tx(), vec![b'0', b'M', 0x01, 0x03, 0xFF]);
+    }
+}
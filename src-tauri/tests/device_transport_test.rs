@@ -0,0 +1,81 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{read_config_response, rmd_file_path};
+    use std::io::Read;
+    use tinymesh_cc_tool::device_transport::{DeviceTransport, MemoryTransport};
+    use tinymesh_cc_tool::tinymesh_config_mod::{
+        get_device_config_from_device, set_device_config_on_device,
+    };
+    use tinymesh_cc_tool::tinymesh_device_info_mod::{get_rssi_from_device, switch_to_channel};
+
+    #[test]
+    fn test_memory_transport_read_write() {
+        let mut transport = MemoryTransport::new();
+        transport.push_rx(&[1, 2, 3]);
+        let mut buffer = [0u8; 2];
+        assert_eq!(transport.read(&mut buffer).unwrap(), 2);
+        assert_eq!(buffer, [1, 2]);
+        assert_eq!(transport.read(&mut buffer).unwrap(), 1);
+        assert_eq!(
+            transport.read(&mut buffer).unwrap_err().kind(),
+            std::io::ErrorKind::TimedOut
+        );
+
+        std::io::Write::write_all(&mut transport, b"HW").unwrap();
+        let mut cloned = transport.try_clone().unwrap();
+        std::io::Write::write_all(&mut cloned, b"0").unwrap();
+        assert_eq!(transport.take_tx(), b"HW0");
+        assert!(transport.take_tx().is_empty());
+    }
+
+    #[test]
+    fn test_get_device_config_from_memory_transport() {
+        let config_response = read_config_response();
+        let mut transport = MemoryTransport::with_responder(Box::new(move |bytes| {
+            if bytes == b"0" {
+                config_response.clone()
+            } else {
+                vec![]
+            }
+        }));
+        let device_config =
+            get_device_config_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        assert_eq!(transport.take_tx(), b"0");
+        assert_eq!(device_config.model, "RF TM4070");
+        assert_eq!(device_config.hw_version, "1.00");
+        assert_eq!(device_config.firmware_version, "1.53");
+        assert_eq!(device_config.cells[0].name, "RF Channel");
+        assert_eq!(device_config.cells[0].current_value, 4);
+    }
+
+    #[test]
+    fn test_set_device_config_on_memory_transport() {
+        let config_response = read_config_response();
+        let mut transport = MemoryTransport::new();
+        transport.push_rx(&config_response);
+        let device_config =
+            get_device_config_from_device(&mut transport, Some(&rmd_file_path()), None).unwrap();
+        transport.take_tx();
+
+        let mut cells = device_config.cells.clone();
+        cells[0].current_value = 7;
+        // the module answers `>` after the `M` command and again after the memory write
+        transport.push_rx(b">>");
+        let mut device: Box<dyn DeviceTransport> = Box::new(transport.clone());
+        assert!(set_device_config_on_device(&mut device, &device_config, &cells, None).is_ok());
+        assert_eq!(transport.take_tx(), vec![b'M', 0x00, 7, 0xff]);
+    }
+
+    #[test]
+    fn test_rssi_from_memory_transport() {
+        let mut transport = MemoryTransport::with_responder(Box::new(|bytes| match bytes {
+            b"S" => vec![0x50, b'>'],
+            _ => vec![b'>'],
+        }));
+        assert!(switch_to_channel(3, &mut transport, None).is_ok());
+        assert_eq!(get_rssi_from_device(&mut transport, None), Ok(0x50));
+        assert_eq!(transport.take_tx(), vec![b'C', 3, b'S']);
+    }
+}
@@ -0,0 +1,423 @@
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+    use tinymesh_cc_tool::data_types::{
+        MkBandPlan, MkCellBits, MkCellEnumValue, MkCellScaling, MkEndianness, MkMultiByte, MkRmdCompatibility, MkRssiConversion,
+    };
+    use tinymesh_cc_tool::mk_module_description::{
+        parse_hint_enum_values, MkCellListSections, MkModuleDescription, MkRmdDiagnostic,
+        MkRmdSeverity,
+    };
+
+    fn read_rmd() -> String {
+        read_to_string(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/tests/RF TM4070.rmd"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rssi_conversion_is_parsed() {
+        let module_description = MkModuleDescription::new(&read_rmd()).unwrap();
+        assert_eq!(
+            module_description.rssi_conversion,
+            MkRssiConversion {
+                offset: 0.0,
+                scale_factor: -0.5,
+                saturation: Some(16),
+            }
+        );
+        assert!(!module_description.unknown_data.contains_key("RSSI_OFFSET"));
+
+        // RMD files without RSSI keys use the TinyMesh default
+        let module_description = MkModuleDescription::new("[DEVICE_MODEL]\nRF TM1000\n").unwrap();
+        assert_eq!(module_description.rssi_conversion, MkRssiConversion::default());
+    }
+
+    #[test]
+    fn test_rssi_conversion() {
+        let conversion = MkRssiConversion {
+            offset: -10.0,
+            scale_factor: -0.5,
+            saturation: Some(16),
+        };
+        let reading = conversion.convert(200);
+        assert_eq!(reading.dbm, Some(-110.0));
+        assert!(!reading.saturated);
+
+        // saturated readings are flagged instead of converted
+        let reading = conversion.convert(16);
+        assert_eq!(reading.dbm, None);
+        assert!(reading.saturated);
+
+        let conversion = MkRssiConversion {
+            offset: -130.0,
+            scale_factor: 0.5,
+            saturation: Some(240),
+        };
+        assert_eq!(conversion.convert(100).dbm, Some(-80.0));
+        assert!(conversion.is_saturated(250));
+        assert!(!MkRssiConversion::default().is_saturated(0));
+    }
+
+    #[test]
+    fn test_band_plan() {
+        let module_description = MkModuleDescription::new(&read_rmd()).unwrap();
+        let band_plan = module_description.band_plan.unwrap();
+        assert_eq!(
+            band_plan,
+            MkBandPlan {
+                first_center_mhz: 865.1,
+                channel_width_khz: 200.0,
+                first_channel: 1,
+                last_channel: 10,
+            }
+        );
+        assert_eq!(band_plan.frequency_of(1), Some(865.1));
+        assert_eq!(band_plan.frequency_of(4), Some(865.7));
+        assert_eq!(band_plan.frequency_of(10), Some(866.9));
+        assert_eq!(band_plan.frequency_of(0), None);
+        assert_eq!(band_plan.frequency_of(11), None);
+
+        assert_eq!(band_plan.channel_of(865.7), Some(4));
+        assert_eq!(band_plan.channel_of(865.74), Some(4));
+        assert_eq!(band_plan.channel_of(864.0), None);
+        assert_eq!(band_plan.channel_of(867.1), None);
+
+        let module_description = MkModuleDescription::new("[DEVICE_MODEL]\nRF TM1000\n").unwrap();
+        assert_eq!(module_description.band_plan, None);
+
+        // an inverted RF Channel range gives no band plan
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n[M 0x00 NAME]\nRF Channel\n[M 0x00 MIN_MAX]\n10 0\n\
+            [FREQUENCY_CHANNEL_FIRST_CENTER]\n865.1\n[FREQUENCY_CHANNEL_WIDTH]\n200\n";
+        let module_description = MkModuleDescription::new(rmd).unwrap();
+        assert_eq!(module_description.band_plan, None);
+        assert!(module_description
+            .warnings
+            .iter()
+            .any(|warning| warning.message.starts_with("The band plan needs a RF Channel MIN_MAX")));
+        let inverted = MkBandPlan {
+            first_channel: 10,
+            last_channel: 0,
+            ..band_plan
+        };
+        assert_eq!(inverted.channel_of(865.1), None);
+        assert_eq!(inverted.frequency_of(5), None);
+    }
+
+    #[test]
+    fn test_legacy_cell_list_sections() {
+        let module_description = MkModuleDescription::new(&read_rmd()).unwrap();
+        assert_eq!(
+            module_description.cell_list_sections,
+            MkCellListSections {
+                editable_cells: Some("EDITABLE_CELLS".to_string()),
+                locked_cells: Some("LOCKED_CELLS".to_string()),
+                c_editable_cells: None,
+                c_locked_cells: None,
+            }
+        );
+        let mut expected_locked_cells = vec![0x04, 0x05, 0x0F];
+        expected_locked_cells.extend(0x3C..=0x50);
+        expected_locked_cells.extend([0x52, 0x53, 0x62, 0x6F, 0x70]);
+        expected_locked_cells.extend(0x79..=0x7F);
+        assert_eq!(module_description.locked_cells, expected_locked_cells);
+        assert_eq!(module_description.editable_cells[..3], [0x00, 0x01, 0x02]);
+        assert!(module_description.editable_cells.contains(&0x51));
+        assert!(!module_description.editable_cells.contains(&0x39));
+        assert!(!module_description.unknown_data.contains_key("LOCKED_CELLS"));
+    }
+
+    #[test]
+    fn test_prefixed_cell_list_sections() {
+        let rmd = "[M EDITABLE_CELLS]\n0x00 0x01\n[LOCKED_CELLS]\n0x02\n[M LOCKED_CELLS]\n0x03 4\n[C LOCKED_CELLS]\n0x00\n";
+        let module_description = MkModuleDescription::new(rmd).unwrap();
+        assert_eq!(module_description.editable_cells, vec![0x00, 0x01]);
+        // the prefixed form wins over the legacy spelling
+        assert_eq!(module_description.locked_cells, vec![0x03, 0x04]);
+        assert_eq!(module_description.c_locked_cells, vec![0x00]);
+        assert_eq!(
+            module_description.cell_list_sections.locked_cells.as_deref(),
+            Some("M LOCKED_CELLS")
+        );
+        assert!(module_description.unknown_data.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_rmd_returns_diagnostics() {
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n\n[M 0x01 NAME]\nRF Power\n\n[M 0x01 MIN_MAX]\n1\n\n[M 0x02 DEF]\n300\n\n[TESTMODE NUMBER]\nfive\n";
+        let diagnostics = MkModuleDescription::new(rmd).unwrap_err();
+        assert_eq!(
+            diagnostics[0],
+            MkRmdDiagnostic {
+                severity: MkRmdSeverity::Error,
+                section: "TESTMODE NUMBER".to_string(),
+                line: Some(14),
+                message: "TESTMODE NUMBER 'five' is not a number".to_string(),
+            }
+        );
+        assert_eq!(
+            diagnostics[1].to_string(),
+            "RMD error at line 8: MIN_MAX '1' needs two values"
+        );
+        assert_eq!(
+            diagnostics[2].to_string(),
+            "RMD error at line 11: DEF '300' contains '300', which is not a value between 0 and 255"
+        );
+        assert_eq!(diagnostics.len(), 3);
+    }
+
+    #[test]
+    fn test_rmd_warnings() {
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n[M 0x01 MIN_MAX]\n5 1\n[M 0x01 COLOR]\nred\n[LOCKED_CELLS]\n0x04 0xZZ\n[RSSI_OFFSET]\nzero\n";
+        let module_description = MkModuleDescription::new(rmd).unwrap();
+        let warnings: Vec<String> = module_description
+            .warnings
+            .iter()
+            .map(|warning| warning.to_string())
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "RMD warning at line 8: '0xZZ' is not a cell address and is ignored",
+                "RMD warning at line 10: RSSI_OFFSET 'zero' is not a valid number and is ignored",
+                "RMD warning at line 4: MIN_MAX '5 1' has a minimum above its maximum",
+                "RMD warning at line 6: COLOR is not a cell attribute and is ignored",
+            ]
+        );
+        assert_eq!(module_description.locked_cells, vec![0x04]);
+        assert_eq!(module_description.rssi_conversion, MkRssiConversion::default());
+
+        // the bundled RMD file has a section name with a trailing space
+        let module_description = MkModuleDescription::new(&read_rmd()).unwrap();
+        assert_eq!(module_description.warnings.len(), 1);
+        assert_eq!(module_description.warnings[0].severity, MkRmdSeverity::Warning);
+    }
+
+    #[test]
+    fn test_compatibility() {
+        let module_description = MkModuleDescription::new(&read_rmd()).unwrap();
+        assert_eq!(module_description.compatibility, MkRmdCompatibility::default());
+        assert_eq!(module_description.compatibility.check("1.00", "1.53"), None);
+
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n[FIRMWARE_VERSION_MIN_MAX]\n1.50 1.54\n[HW_VERSIONS]\n1.00 1.01\n";
+        let module_description = MkModuleDescription::new(rmd).unwrap();
+        let compatibility = &module_description.compatibility;
+        assert!(module_description.unknown_data.is_empty());
+        assert_eq!(compatibility.supports_firmware("1.53"), Some(true));
+        assert_eq!(compatibility.supports_firmware("1.6"), Some(false));
+        assert_eq!(compatibility.supports_hw("1.01"), Some(true));
+        assert_eq!(compatibility.firmware_distance("1.52"), 0);
+        assert!(compatibility.firmware_distance("1.56") < compatibility.firmware_distance("1.60"));
+        assert!(compatibility.firmware_distance("1.48") < compatibility.firmware_distance("1.40"));
+        assert_eq!(
+            compatibility.check("2.00", "1.49").as_deref(),
+            Some("No RMD file was made for firmware 1.49 (the RMD file is for 1.50 to 1.54) and hardware 2.00 (the RMD file is for 1.00, 1.01), the closest match is used")
+        );
+
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n[FIRMWARE_VERSION_MIN_MAX]\n1.54\n";
+        let module_description = MkModuleDescription::new(rmd).unwrap();
+        assert_eq!(module_description.compatibility.firmware_versions, None);
+        assert_eq!(
+            module_description.warnings[0].to_string(),
+            "RMD warning at line 4: FIRMWARE_VERSION_MIN_MAX '1.54' needs two versions and is ignored"
+        );
+    }
+
+    #[test]
+    fn test_multi_byte_and_bits_cells() {
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n\
+            [M 0x21 NAME]\nGPIO 0 Analog High Trig\n[M 0x21 SIZE]\n2\n\
+            [M 0x2D ENDIAN]\nLITTLE\n[M 0x2D SIZE]\n4\n\
+            [M 0x40 NAME]\nOptions\n[M 0x40 BITS]\n0 Sleep enable\n4-7 Wake-up interval\n";
+        let module_description = MkModuleDescription::new(rmd).unwrap();
+        assert!(module_description.warnings.is_empty());
+        assert_eq!(
+            module_description.cells[0x21].multi_byte,
+            Some(MkMultiByte {
+                size: 2,
+                endianness: MkEndianness::Big,
+            })
+        );
+        // ENDIAN may come before SIZE
+        assert_eq!(
+            module_description.cells[0x2D].multi_byte,
+            Some(MkMultiByte {
+                size: 4,
+                endianness: MkEndianness::Little,
+            })
+        );
+        assert_eq!(module_description.cells[0x22].multi_byte, None);
+        assert_eq!(
+            module_description.cells[0x40].bits,
+            vec![
+                MkCellBits {
+                    name: "Sleep enable".to_string(),
+                    first_bit: 0,
+                    last_bit: 0,
+                },
+                MkCellBits {
+                    name: "Wake-up interval".to_string(),
+                    first_bit: 4,
+                    last_bit: 7,
+                },
+            ]
+        );
+
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n[M 0x01 SIZE]\n5\n[M 0x02 ENDIAN]\nMIDDLE\n[M 0x03 BITS]\n0 First\n8 Ninth\n[M 0x04 BITS]\n3-1 Reversed\n";
+        let diagnostics: Vec<String> = MkModuleDescription::new(rmd)
+            .unwrap_err()
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "RMD error at line 4: SIZE '5' is not a number between 2 and 4",
+                "RMD error at line 6: ENDIAN 'MIDDLE' is neither BIG nor LITTLE",
+                "RMD error at line 8: BITS '8 Ninth' contains '8', which is not a bit between 0 and 7",
+                "RMD error at line 11: BITS '3-1 Reversed' has a first bit above its last bit",
+            ]
+        );
+
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n[M 0x01 ENDIAN]\nLITTLE\n[M 0x02 SIZE]\n2\n[M 0x02 BITS]\n0 First\n";
+        let module_description = MkModuleDescription::new(rmd).unwrap();
+        let warnings: Vec<String> = module_description
+            .warnings
+            .iter()
+            .map(|warning| warning.to_string())
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "RMD warning at line 4: ENDIAN is ignored, the cell has no SIZE",
+                "RMD warning at line 8: BITS is ignored, the cell spans several addresses",
+            ]
+        );
+        assert_eq!(module_description.cells[0x01].multi_byte, None);
+        assert!(module_description.cells[0x02].bits.is_empty());
+    }
+
+    #[test]
+    fn test_enum_values() {
+        let enum_value = |value: u8, label: &str, unit: Option<&str>| MkCellEnumValue {
+            value,
+            label: label.to_string(),
+            unit: unit.map(|unit| unit.to_string()),
+        };
+
+        // the table of the HINT is used for RMD files without ENUM sections
+        let module_description = MkModuleDescription::new(&read_rmd()).unwrap();
+        let rf_power = &module_description.cells[0x01];
+        assert_eq!(rf_power.enum_values[0], enum_value(1, "-20", Some("dBm")));
+        assert_eq!(rf_power.enum_values[4], enum_value(5, "11", Some("dBm")));
+        assert_eq!(rf_power.label_of(2).as_deref(), Some("-10 dBm"));
+        assert_eq!(rf_power.label_of(6), None);
+        let data_rate = &module_description.cells[0x02];
+        assert_eq!(data_rate.enum_values[0], enum_value(1, "1 200", Some("bit/s")));
+        assert_eq!(data_rate.enum_values[6], enum_value(7, "Reserved", None));
+        assert_eq!(
+            module_description.cells[0x03].label_of(1).as_deref(),
+            Some("Transparent mode")
+        );
+        // tables with several columns
+        let values: Vec<u8> = module_description.cells[0x6C]
+            .enum_values
+            .iter()
+            .map(|enum_value| enum_value.value)
+            .collect();
+        assert_eq!(values, vec![1, 2, 4, 8, 16, 32, 64, 128]);
+        assert!(module_description.cells[0x04].enum_values.is_empty());
+
+        // an ENUM section takes precedence over the HINT
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n\
+            [M 0x01 HINT]\nRF Power\n1 : -20 dBm\n2 : -10 dBm\n\
+            [M 0x01 ENUM]\n1 : -20 : dBm\n2 : Low power\n";
+        let module_description = MkModuleDescription::new(rmd).unwrap();
+        assert_eq!(
+            module_description.cells[0x01].enum_values,
+            vec![enum_value(1, "-20", Some("dBm")), enum_value(2, "Low power", None)]
+        );
+
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n[M 0x01 ENUM]\n1 -20 dBm\n[M 0x02 ENUM]\n256 : Too big\n";
+        let diagnostics: Vec<String> = MkModuleDescription::new(rmd)
+            .unwrap_err()
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "RMD error at line 4: ENUM '1 -20 dBm' is not of the form 'value : label : unit'",
+                "RMD error at line 6: ENUM '256 : Too big' contains '256', which is not a value between 0 and 255",
+            ]
+        );
+
+        // a single line or repeated values are not a table
+        assert!(parse_hint_enum_values("Default: 255").is_empty());
+        assert!(parse_hint_enum_values("1 : One\n1 : Also one").is_empty());
+    }
+
+    #[test]
+    fn test_scaling() {
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n\
+            [M 0x07 SCALE]\n0.1\n[M 0x07 UNIT]\ns\n\
+            [M 0x08 UNIT]\ndBm\n[M 0x08 OFFSET]\n-130\n";
+        let module_description = MkModuleDescription::new(rmd).unwrap();
+        assert_eq!(
+            module_description.cells[0x07].scaling,
+            Some(MkCellScaling {
+                scale: 0.1,
+                offset: 0.0,
+                unit: Some("s".to_string()),
+            })
+        );
+        assert_eq!(
+            module_description.cells[0x08].scaling,
+            Some(MkCellScaling {
+                scale: 1.0,
+                offset: -130.0,
+                unit: Some("dBm".to_string()),
+            })
+        );
+        assert_eq!(module_description.cells[0x09].scaling, None);
+
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n[M 0x07 SCALE]\n0\n[M 0x08 OFFSET]\nten\n";
+        let diagnostics: Vec<String> = MkModuleDescription::new(rmd)
+            .unwrap_err()
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "RMD error at line 4: SCALE must not be 0",
+                "RMD error at line 6: OFFSET 'ten' is not a number",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let rmd = "[DEVICE_MODEL]\nRF TM4070\n\
+            [M 0x07 ACTIVE_IF]\n0x0E = 2\n\
+            [M 0x08 ACTIVE_IF]\n14 == 2\n\
+            [M 0x09 REQUIRE]\n0x0E == 3 IF\n\
+            [M 0x0A REQUIRE]\n0x0E == 3 AND 0x03\n";
+        let diagnostics: Vec<String> = MkModuleDescription::new(rmd)
+            .unwrap_err()
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "RMD error at line 4: ACTIVE_IF '0x0E = 2' contains '=', which is not one of == != < <= > >=",
+                "RMD error at line 6: ACTIVE_IF '14 == 2' contains '14', which is not a cell address",
+                "RMD error at line 8: REQUIRE '0x0E == 3 IF' has no condition after IF",
+                "RMD error at line 10: REQUIRE '0x0E == 3 AND 0x03' contains '0x03', which is not a condition like '0x0E == 2'",
+            ]
+        );
+    }
+}
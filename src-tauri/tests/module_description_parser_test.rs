@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use tinymesh_cc_tool::module_description_parser::parse_module_description;
+    use tinymesh_cc_tool::module_description_parser::{
+        parse_module_description, parse_rmd_document, MkRmdSpan,
+    };
     use std::fs::read_to_string;
     use std::path::PathBuf;
 
@@ -17,4 +19,35 @@ mod tests {
         println!("{:#?}", result);
         assert_eq!(result.get("DEVICE_MODEL").unwrap(), "RF TM4070");
     }
+
+    #[test]
+    fn test_parse_rmd_document() {
+        let input = "// header comment\n\n[DEVICE_MODEL]\nRF TM4070\n\n// the cell\n// of RF power\n\
+            [M 0x01 NAME] // inline\nRF Power\n[M 0x01 HINT]\nFirst line\nSecond line // inline\n[]\n\
+            stray\n[M 0x01 NAME]\nPower\n[EMPTY]\n// the end\n";
+        let document = parse_rmd_document(input);
+        let names: Vec<&str> = document.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["DEVICE_MODEL", "M 0x01 NAME", "M 0x01 HINT", "", "M 0x01 NAME", "EMPTY"]);
+        assert_eq!(document.sections[0].comments, ["// header comment"]);
+        assert_eq!(document.sections[1].comments, ["// the cell", "// of RF power"]);
+        assert_eq!(document.sections[2].content, "First line\nSecond line");
+        assert_eq!(document.sections[2].span, MkRmdSpan { start_line: 10, end_line: 12 });
+        assert_eq!(document.sections[2].content_line, Some(11));
+        assert_eq!(document.sections[5].content_line, None);
+        assert_eq!(document.trailing_comments, ["// the end"]);
+        assert_eq!(document.stray_lines, [14]);
+
+        let duplicates = document.duplicates();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].0.span.start_line, 8);
+        assert_eq!(duplicates[0].1.span.start_line, 15);
+
+        // the map keeps the last of the duplicate sections and leaves out the stray content
+        let map = document.to_map();
+        assert_eq!(map.len(), 4);
+        assert_eq!(map["M 0x01 NAME"], "Power");
+        assert_eq!(map["EMPTY"], "");
+        assert_eq!(document.section_lines()["M 0x01 NAME"], 16);
+        assert_eq!(document.section_lines()["EMPTY"], 17);
+    }
 }
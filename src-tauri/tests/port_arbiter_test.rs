@@ -0,0 +1,127 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{create_emulator, rmd_file_path};
+    use serialport::ClearBuffer;
+    use std::io::{self, Read, Write};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tinymesh_cc_tool::device_transport::{DeviceTransport, MemoryTransport};
+    use tinymesh_cc_tool::port_arbiter::{lock_device, poll_background_bytes, SharedPort};
+    use tinymesh_cc_tool::tinymesh_config_mod::get_device_config_from_device;
+    use tinymesh_cc_tool::tinymesh_device_info_mod::get_rssi_from_device;
+    use tinymesh_cc_tool::tinymesh_serial_util::{DeviceError, DEFAULT_MAX_RESPONSE_LENGTH};
+
+    /// A module in transparent mode that never stops sending data.
+    struct StreamingTransport;
+
+    impl Read for StreamingTransport {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            buf.fill(0x55);
+            Ok(buf.len())
+        }
+    }
+
+    impl Write for StreamingTransport {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl DeviceTransport for StreamingTransport {
+        fn name(&self) -> Option<String> {
+            Some("streaming".to_string())
+        }
+
+        fn clear(&mut self, _buffer_to_clear: ClearBuffer) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn timeout(&self) -> Duration {
+            Duration::from_millis(10)
+        }
+
+        fn set_timeout(&mut self, _timeout: Duration) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn try_clone(&self) -> io::Result<Box<dyn DeviceTransport>> {
+            Ok(Box::new(StreamingTransport))
+        }
+    }
+
+    fn shared_port(transport: impl DeviceTransport + 'static) -> SharedPort {
+        let transport: Box<dyn DeviceTransport> = Box::new(transport);
+        Arc::new(Mutex::new(Some(transport)))
+    }
+
+    #[test]
+    fn test_lock_device_not_connected() {
+        let port: SharedPort = Default::default();
+        assert!(matches!(lock_device(&port, None), Err(DeviceError::NotConnected)));
+    }
+
+    #[test]
+    fn test_pending_bytes_are_read_before_exchange() {
+        let emulator = create_emulator();
+        let transport = emulator.transport();
+        // bytes received in the background must not be mistaken for the response
+        transport.push_rx(&[0x01, 0x02, b'>']);
+        let port = shared_port(transport);
+        let mut device = lock_device(&port, None).unwrap();
+        assert_eq!(get_rssi_from_device(&mut *device, None), Ok(200));
+    }
+
+    #[test]
+    fn test_background_reader_is_suspended_during_exchange() {
+        let transport = MemoryTransport::new();
+        let port = shared_port(transport.clone());
+        transport.push_rx(&[0x10, 0x20]);
+        {
+            let _device = lock_device(&port, None).unwrap();
+            transport.push_rx(&[0x30]);
+            assert!(poll_background_bytes(&port, None).is_empty());
+        }
+        assert_eq!(poll_background_bytes(&port, None), vec![0x30]);
+    }
+
+    #[test]
+    fn test_exchange_with_background_reader_running() {
+        let emulator = create_emulator();
+        let port = shared_port(emulator.transport());
+        let running = Arc::new(AtomicBool::new(true));
+        let reader = {
+            let port = port.clone();
+            let running = running.clone();
+            std::thread::spawn(move || {
+                while running.load(Ordering::SeqCst) {
+                    poll_background_bytes(&port, None);
+                }
+            })
+        };
+        for _ in 0..5 {
+            let mut device = lock_device(&port, None).unwrap();
+            let device_config =
+                get_device_config_from_device(&mut *device, Some(&rmd_file_path()), None).unwrap();
+            assert_eq!(device_config.model, "RF TM4070");
+        }
+        running.store(false, Ordering::SeqCst);
+        reader.join().unwrap();
+    }
+
+    #[test]
+    fn test_pending_bytes_of_a_streaming_module_are_bounded() {
+        let port = shared_port(StreamingTransport);
+        assert!(lock_device(&port, None).is_ok());
+        assert_eq!(
+            poll_background_bytes(&port, None).len(),
+            DEFAULT_MAX_RESPONSE_LENGTH
+        );
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+    use tinymesh_cc_tool::mk_module_description::MkRmdSeverity;
+    use tinymesh_cc_tool::rmd_lint::lint_rmd;
+
+    fn lint_messages(input: &str) -> Vec<String> {
+        lint_rmd(input)
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_bundled_rmd() {
+        let input = read_to_string(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/tests/RF TM4070.rmd"),
+        )
+        .unwrap();
+        let diagnostics = lint_rmd(&input);
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity == MkRmdSeverity::Warning));
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        assert!(messages.contains(&"RMD warning at line 56: Cell 0x04 is both editable and locked".to_string()));
+        assert!(messages.contains(
+            &"RMD warning at line 2788: [M 0xF2 DEF] was already defined at line 2785, only the last definition is used"
+                .to_string()
+        ));
+    }
+
+    #[test]
+    fn test_lint_cells() {
+        let input = "[DEVICE_MODEL]\nRF TM4070\n\
+            [M 0x01 NAME]\nRF Power\n[M 0x01 MIN_MAX]\n1 5\n[M 0x01 DEF]\n9\n\
+            [M 0x02 NAME]\nMode\n[M 0x02 ALLOW]\n0 1\n[M 0x02 DEF]\n2\n\
+            [M 0x03 NAME]\nNo range\n\
+            [M 0x04 NAME]\nMode 2\n[M 0x04 ALLOW]\n0 1\n[M 0x04 ENUM]\n0 : Off\n2 : Reserved\n";
+        assert_eq!(
+            lint_messages(input),
+            vec![
+                "RMD warning at line 8: DEF: Value 9 of cell 0x01 (RF Power) is outside 1..=5",
+                "RMD warning at line 14: DEF: Value 2 of cell 0x02 (Mode) is not one of [0, 1]",
+                "RMD warning at line 16: Cell 0x03 (No range) has no MIN_MAX or ALLOW",
+                "RMD warning at line 22: ENUM: Value 2 of cell 0x04 (Mode 2) is not one of [0, 1]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_modes_and_cell_lists() {
+        let input = "[DEVICE_MODEL]\nRF TM4070\n\
+            [TESTMODE NUMBER]\n2\n[TESTMODE 1 NAME]\nFirst\n[TESTMODE 3 NAME]\nThird\n\
+            [EDITABLE_CELLS]\n0x01 0x02\n[LOCKED_CELLS]\n0x02\n\
+            [DEVICE_MODEL]\nRF TM4070\n[SOMETHING_ELSE]\n1\n";
+        assert_eq!(
+            lint_messages(input),
+            vec![
+                "RMD warning at line 4: TESTMODE 2 is missing, TESTMODE NUMBER is 2",
+                "RMD warning at line 8: TESTMODE 3 is ignored, TESTMODE NUMBER is 2",
+                "RMD warning at line 12: Cell 0x02 is both editable and locked",
+                "RMD warning at line 13: [DEVICE_MODEL] was already defined at line 1, only the last definition is used",
+                "RMD warning at line 16: [SOMETHING_ELSE] is not part of the RMD format and is ignored",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_reports_parse_errors() {
+        let input = "[DEVICE_MODEL]\nRF TM4070\n[M 0x01 MIN_MAX]\n1\n";
+        let diagnostics = lint_rmd(input);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, MkRmdSeverity::Error);
+        assert_eq!(diagnostics[0].line, Some(4));
+    }
+
+    #[test]
+    fn test_lint_stray_lines() {
+        let input = "RF TM4070\n[DEVICE_MODEL]\nRF TM4070\n[]\n// comment\n0x01\n";
+        assert_eq!(
+            lint_messages(input),
+            vec![
+                "RMD warning at line 1: The line is not part of a section and is ignored",
+                "RMD warning at line 6: The line is not part of a section and is ignored",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_typed_cells() {
+        let input = "[DEVICE_MODEL]\nRF TM4070\n\
+            [M 0x2D SIZE]\n4\n[M 0x2F SIZE]\n2\n\
+            [M 0x40 BITS]\n0-3 Interval\n3 Sleep enable\n\
+            [M 0x41 ACTIVE_IF]\n0x0E == 2\n";
+        assert_eq!(
+            lint_messages(input),
+            vec![
+                "RMD warning at line 6: Cell 0x2F () overlaps the 4 bytes of cell 0x2D",
+                "RMD warning at line 8: Bits 'Sleep enable' of cell 0x40 overlap bits 'Interval'",
+                "RMD warning at line 11: ACTIVE_IF of cell 0x41 refers to cell 0x0E, which is not described",
+            ]
+        );
+    }
+}
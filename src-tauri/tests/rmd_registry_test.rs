@@ -0,0 +1,145 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TestDir;
+    use std::fs::{create_dir_all, write, File};
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+    use tinymesh_cc_tool::rmd_registry::RmdRegistry;
+
+    fn rmd(device_model: &str, power_name: &str) -> String {
+        format!(
+            "[DEVICE_MODEL]\n{}\n[M 0x01 NAME]\n{}\n[M 0x01 MIN_MAX]\n1 5\n",
+            device_model, power_name
+        )
+    }
+
+    #[test]
+    fn test_index_by_device_model() {
+        let test_dir = TestDir::new("rmd_registry_index");
+        let user_dir = test_dir.join("user");
+        let bundled_dir = test_dir.join("bundled");
+        create_dir_all(&user_dir).unwrap();
+        create_dir_all(&bundled_dir).unwrap();
+        write(bundled_dir.join("tm4070.RMD"), rmd("RF TM4070", "Bundled")).unwrap();
+        write(bundled_dir.join("tm4071.rmd"), rmd("RF TM4071", "Bundled")).unwrap();
+        write(user_dir.join("my own.rmd"), rmd("RF TM4070", "User")).unwrap();
+        write(user_dir.join("notes.txt"), rmd("RF TM9999", "Not RMD")).unwrap();
+        write(user_dir.join("broken.rmd"), "[M 0x01 NAME]\nNo model\n").unwrap();
+
+        let mut registry = RmdRegistry::new(vec![
+            test_dir.join("missing"),
+            user_dir.clone(),
+            bundled_dir.clone(),
+        ]);
+        let models: Vec<(String, PathBuf)> = registry
+            .models()
+            .into_iter()
+            .map(|file| (file.device_model, file.file_path))
+            .collect();
+        assert_eq!(
+            models,
+            vec![
+                ("RF TM4070".to_string(), user_dir.join("my own.rmd")),
+                ("RF TM4071".to_string(), bundled_dir.join("tm4071.rmd")),
+            ]
+        );
+        // the first directory wins
+        let module_description = registry.get_module_description("RF TM4070").unwrap();
+        assert_eq!(module_description.cells[1].name, "User");
+        assert!(registry
+            .get_module_description("RF TM9999")
+            .unwrap_err()
+            .starts_with("No RMD file for RF TM9999 found"));
+
+        // files added later are found without an explicit scan
+        write(bundled_dir.join("tm4072.rmd"), rmd("RF TM4072", "Added")).unwrap();
+        assert_eq!(registry.get_module_description("RF TM4072").unwrap().cells[1].name, "Added");
+    }
+
+    #[test]
+    fn test_cache_until_file_changes() {
+        let test_dir = TestDir::new("rmd_registry_cache");
+        let file_path = test_dir.join("tm4070.rmd");
+        write(&file_path, rmd("RF TM4070", "First")).unwrap();
+        let mut registry = RmdRegistry::new(vec![test_dir.path().to_path_buf()]);
+        assert_eq!(registry.get_module_description("RF TM4070").unwrap().cells[1].name, "First");
+
+        // make sure the modification time changes, whatever its resolution
+        write(&file_path, rmd("RF TM4070", "Second")).unwrap();
+        File::options()
+            .write(true)
+            .open(&file_path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(registry.get_module_description("RF TM4070").unwrap().cells[1].name, "Second");
+
+        // a file that doesn't parse anymore is reported
+        write(&file_path, "[DEVICE_MODEL]\nRF TM4070\n[TESTMODE NUMBER]\ntwo\n").unwrap();
+        File::options()
+            .write(true)
+            .open(&file_path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(20))
+            .unwrap();
+        assert!(registry.get_module_description("RF TM4070").is_err());
+    }
+
+    #[test]
+    fn test_select_by_versions() {
+        let test_dir = TestDir::new("rmd_registry_select");
+        let versioned = |firmware: &str, hw: &str, power_name: &str| {
+            format!(
+                "{}[FIRMWARE_VERSION_MIN_MAX]\n{}\n[HW_VERSIONS]\n{}\n",
+                rmd("RF TM4070", power_name),
+                firmware,
+                hw
+            )
+        };
+        write(test_dir.join("a generic.rmd"), rmd("RF TM4070", "Generic")).unwrap();
+        write(test_dir.join("b old.rmd"), versioned("1.40 1.49", "1.00", "Old")).unwrap();
+        write(test_dir.join("c new.rmd"), versioned("1.50 1.54", "1.00 1.01", "New")).unwrap();
+        write(test_dir.join("d new hw.rmd"), versioned("1.50 1.54", "2.00", "New HW")).unwrap();
+        let mut registry = RmdRegistry::new(vec![test_dir.path().to_path_buf()]);
+        assert_eq!(registry.models().len(), 4);
+
+        let selection = registry.select_file("RF TM4070", "1.01", "1.53").unwrap();
+        assert_eq!(selection.file.file_path, test_dir.join("c new.rmd"));
+        assert_eq!(selection.warning, None);
+        let selection = registry.select_file("RF TM4070", "2.00", "1.50").unwrap();
+        assert_eq!(selection.file.file_path, test_dir.join("d new hw.rmd"));
+        // the firmware matters more than the hardware revision
+        let selection = registry.select_file("RF TM4070", "3.00", "1.54").unwrap();
+        assert_eq!(selection.file.file_path, test_dir.join("c new.rmd"));
+        assert_eq!(
+            selection.warning.as_deref(),
+            Some("No RMD file was made for hardware 3.00 (the RMD file is for 1.00, 1.01), the closest match is used")
+        );
+        // a file without a firmware range is better than a file for other versions
+        let selection = registry.select_file("RF TM4070", "1.00", "1.60").unwrap();
+        assert_eq!(selection.file.file_path, test_dir.join("a generic.rmd"));
+        assert_eq!(selection.warning, None);
+        assert!(registry.select_file("RF TM4071", "1.00", "1.53").is_none());
+
+        let (module_description, selection) = registry
+            .get_module_description_for_device("RF TM4070", "1.00", "1.45")
+            .unwrap();
+        assert_eq!(module_description.cells[1].name, "Old");
+        assert_eq!(
+            module_description.compatibility.firmware_versions,
+            Some(("1.40".to_string(), "1.49".to_string()))
+        );
+        assert_eq!(selection.warning, None);
+
+        std::fs::remove_file(test_dir.join("a generic.rmd")).unwrap();
+        registry.scan();
+        let selection = registry.select_file("RF TM4070", "1.00", "1.60").unwrap();
+        assert_eq!(selection.file.file_path, test_dir.join("c new.rmd"));
+        assert_eq!(
+            selection.warning.as_deref(),
+            Some("No RMD file was made for firmware 1.60 (the RMD file is for 1.50 to 1.54), the closest match is used")
+        );
+    }
+}
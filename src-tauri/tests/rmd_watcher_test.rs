@@ -0,0 +1,69 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{read_config_response, read_rmd, TestDir};
+    use std::fs::{remove_file, write, File};
+    use std::sync::Mutex;
+    use std::time::{Duration, SystemTime};
+    use tinymesh_cc_tool::device_config_parser::parse_device_config;
+    use tinymesh_cc_tool::rmd_registry::RmdRegistry;
+    use tinymesh_cc_tool::rmd_watcher::reload_changed_rmd_files;
+
+    #[test]
+    fn test_reload_changed_rmd_file() {
+        let test_dir = TestDir::new("rmd_watcher");
+        let rmd = read_rmd();
+        let rmd_file_path = test_dir.join("tm4070.rmd");
+        write(&rmd_file_path, &rmd).unwrap();
+
+        let device_config =
+            parse_device_config(&read_config_response(), Some(&rmd_file_path), None).unwrap();
+        let current_values: Vec<u8> = device_config.cells.iter().map(|cell| cell.current_value).collect();
+        let device_config = Mutex::new(Some(device_config));
+        let rmd_registry = Mutex::new(RmdRegistry::new(vec![test_dir.path().to_path_buf()]));
+        assert!(reload_changed_rmd_files(&rmd_registry, &device_config).is_empty());
+
+        // the later definition of a section wins
+        write(
+            &rmd_file_path,
+            format!("{}\n[M 0x01 NAME]\nTransmit Power\n[FIRMWARE_VERSION_MIN_MAX]\n1.54 1.54\n", rmd),
+        )
+        .unwrap();
+        File::options()
+            .write(true)
+            .open(&rmd_file_path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        let events = reload_changed_rmd_files(&rmd_registry, &device_config);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].file_path, rmd_file_path);
+        assert_eq!(
+            events[0].module_description.as_ref().unwrap().cells[1].name,
+            "Transmit Power"
+        );
+        assert!(events[0]
+            .diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.starts_with("[M 0x01 NAME] was already defined")));
+        let remapped_config = events[0].device_config.as_ref().unwrap();
+        assert_eq!(remapped_config.cells[1].name, "Transmit Power");
+        assert_eq!(
+            remapped_config.cells.iter().map(|cell| cell.current_value).collect::<Vec<u8>>(),
+            current_values
+        );
+        assert!(remapped_config.rmd_warning.is_some());
+        let device_config_from_state = device_config.lock().unwrap();
+        assert_eq!(device_config_from_state.as_ref().unwrap().cells[1].name, "Transmit Power");
+        drop(device_config_from_state);
+        assert!(reload_changed_rmd_files(&rmd_registry, &device_config).is_empty());
+
+        remove_file(&rmd_file_path).unwrap();
+        let events = reload_changed_rmd_files(&rmd_registry, &device_config);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].module_description.is_none());
+        assert!(events[0].device_config.is_none());
+        assert!(rmd_registry.lock().unwrap().models().is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+    use tinymesh_cc_tool::data_types::{
+        MkBandPlan, MkCellBits, MkCellCondition, MkCellEnumValue, MkCellRule, MkCellScaling,
+        MkConditionOperator, MkDeviceCell, MkDeviceTestMode, MkEndianness, MkMultiByte,
+    };
+    use tinymesh_cc_tool::mk_module_description::MkModuleDescription;
+    use tinymesh_cc_tool::rmd_writer::write_module_description;
+
+    #[test]
+    fn test_write_bundled_rmd() {
+        let input = read_to_string(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/tests/RF TM4070.rmd"),
+        )
+        .unwrap();
+        let module_description = MkModuleDescription::new(&input).unwrap();
+        let output = write_module_description(&module_description);
+        let written = MkModuleDescription::new(&output).unwrap();
+        assert_eq!(written, module_description);
+        assert!(written.warnings.is_empty());
+        // the sections are written in the order of the file
+        assert!(output.starts_with("[DEVICE_MODEL]\nRF TM4070\n\n[FILE_VERSION]\n1.00\n"));
+        assert!(output.contains("[EDITABLE_CELLS]\n0x00 0x01"));
+        // the values read from the tables of the HINTs are not written as ENUM sections
+        assert!(!output.contains(" ENUM]"));
+        // writing the written description gives the same text
+        assert_eq!(write_module_description(&written), output);
+    }
+
+    #[test]
+    fn test_write_new_description() {
+        let mut module_description = MkModuleDescription {
+            device_model: "RF TM4070".to_string(),
+            number_of_testmodes: 1,
+            testmodes: vec![MkDeviceTestMode {
+                testmode_id: 1,
+                name: "Carrier".to_string(),
+                description: "Sends a carrier\non the current channel".to_string(),
+                sequence_on: "T".to_string(),
+                sequence_off: String::new(),
+            }],
+            cells: vec![Default::default(); 256],
+            calibration_cells: vec![Default::default(); 256],
+            editable_cells: vec![0x00, 0x01],
+            locked_cells: vec![0x3C],
+            ..Default::default()
+        };
+        for (address, cell) in module_description.cells.iter_mut().enumerate() {
+            cell.address = address;
+        }
+        for (address, cell) in module_description.calibration_cells.iter_mut().enumerate() {
+            cell.address = address;
+        }
+        module_description.cells[0] = MkDeviceCell {
+            address: 0,
+            name: "RF Channel".to_string(),
+            description: "The channel".to_string(),
+            min_value: 1,
+            max_value: 83,
+            default_value: 1,
+            current_value: 1,
+            ..Default::default()
+        };
+        module_description.cells[1] = MkDeviceCell {
+            address: 1,
+            name: "RF Power".to_string(),
+            allowed_values: vec![1, 3, 5],
+            default_value: 5,
+            current_value: 5,
+            enum_values: vec![
+                MkCellEnumValue {
+                    value: 1,
+                    label: "-20".to_string(),
+                    unit: Some("dBm".to_string()),
+                },
+                MkCellEnumValue {
+                    value: 5,
+                    label: "Full power".to_string(),
+                    unit: None,
+                },
+            ],
+            ..Default::default()
+        };
+        module_description.cells[0x2D].multi_byte = Some(MkMultiByte {
+            size: 4,
+            endianness: MkEndianness::Little,
+        });
+        module_description.cells[0x40].bits = vec![
+            MkCellBits {
+                name: "Sleep enable".to_string(),
+                first_bit: 0,
+                last_bit: 0,
+            },
+            MkCellBits {
+                name: "Wake-up interval".to_string(),
+                first_bit: 4,
+                last_bit: 7,
+            },
+        ];
+        module_description.cells[0x07].scaling = Some(MkCellScaling {
+            scale: 0.1,
+            offset: 0.0,
+            unit: Some("s".to_string()),
+        });
+        module_description.cells[0x08].scaling = Some(MkCellScaling {
+            scale: 1.0,
+            offset: -128.0,
+            unit: None,
+        });
+        module_description.cells[0x09].scaling = Some(MkCellScaling::default());
+        module_description.cells[0x0A].active_if = vec![MkCellCondition {
+            address: 0x0E,
+            operator: MkConditionOperator::Equal,
+            value: 2,
+        }];
+        module_description.cells[0x0A].rules = vec![MkCellRule {
+            conditions: vec![MkCellCondition {
+                address: 0x03,
+                operator: MkConditionOperator::GreaterOrEqual,
+                value: 1,
+            }],
+            when: vec![MkCellCondition {
+                address: 0x0A,
+                operator: MkConditionOperator::NotEqual,
+                value: 0,
+            }],
+        }];
+        module_description.rssi_conversion.saturation = Some(16);
+        module_description.compatibility.firmware_versions = Some(("1.50".to_string(), "1.54".to_string()));
+        module_description.compatibility.hw_versions = vec!["1.00".to_string()];
+        module_description.band_plan = Some(MkBandPlan {
+            first_center_mhz: 865.1,
+            channel_width_khz: 200.0,
+            first_channel: 1,
+            last_channel: 83,
+        });
+        module_description
+            .unknown_data
+            .insert("FILE_VERSION".to_string(), "1.01".to_string());
+
+        let output = write_module_description(&module_description);
+        assert!(output.contains("[M 0x01 ALLOW]\n1 3 5\n"));
+        assert!(output.contains("[M 0x01 ENUM]\n1 : -20 : dBm\n5 : Full power\n"));
+        assert!(output.contains("[M EDITABLE_CELLS]\n0x00 0x01\n"));
+        assert!(output.contains("[FIRMWARE_VERSION_MIN_MAX]\n1.50 1.54\n"));
+        assert!(output.contains("[M 0x07 SCALE]\n0.1\n\n[M 0x07 UNIT]\ns\n"));
+        assert!(output.contains("[M 0x08 OFFSET]\n-128\n"));
+        assert!(output.contains("[M 0x0A ACTIVE_IF]\n0x0E == 2\n\n[M 0x0A REQUIRE]\n0x03 >= 1 IF 0x0A != 0\n"));
+        assert!(output.contains("[M 0x2D SIZE]\n4\n\n[M 0x2D ENDIAN]\nLITTLE\n"));
+        assert!(output.contains("[M 0x40 BITS]\n0 Sleep enable\n4-7 Wake-up interval\n"));
+        assert!(output.ends_with("[]\n"));
+        assert_eq!(MkModuleDescription::new(&output).unwrap(), module_description);
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use tinymesh_cc_tool::device_transport::MemoryTransport;
+    use tinymesh_cc_tool::tinymesh_config_mod::get_device_config_from_device;
+    use tinymesh_cc_tool::tinymesh_device_info_mod::get_rssi_from_device;
+    use tinymesh_cc_tool::tinymesh_serial_util::{
+        read_bytes_till_3e_from_device_to_buffer, read_bytes_till_3e_with_limits, DeviceError,
+    };
+
+    #[test]
+    fn test_read_till_prompt() {
+        let mut transport = MemoryTransport::new();
+        transport.push_rx(&[0x01, 0x02, b'>', 0x03]);
+        let mut buffer = vec![];
+        assert_eq!(
+            read_bytes_till_3e_from_device_to_buffer(&mut transport, &mut buffer, None),
+            Ok(2)
+        );
+        assert_eq!(buffer, vec![0x01, 0x02]);
+    }
+
+    #[test]
+    fn test_read_till_prompt_times_out() {
+        let mut transport = MemoryTransport::new();
+        transport.push_rx(&[0x01, 0x02]);
+        let mut buffer = vec![];
+        let started = Instant::now();
+        let result = read_bytes_till_3e_with_limits(
+            &mut transport,
+            &mut buffer,
+            Duration::from_millis(50),
+            16,
+            None,
+        );
+        assert_eq!(result, Err(DeviceError::Timeout(vec![0x01, 0x02])));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_read_till_prompt_overflows() {
+        let mut transport = MemoryTransport::new();
+        transport.push_rx(&[0x00; 32]);
+        let mut buffer = vec![];
+        let result = read_bytes_till_3e_with_limits(
+            &mut transport,
+            &mut buffer,
+            Duration::from_secs(1),
+            16,
+            None,
+        );
+        assert_eq!(result, Err(DeviceError::Overflow(16)));
+    }
+
+    #[test]
+    fn test_port_gone() {
+        let mut transport = MemoryTransport::new();
+        transport.disconnect();
+        let mut buffer = vec![];
+        assert!(matches!(
+            read_bytes_till_3e_from_device_to_buffer(&mut transport, &mut buffer, None),
+            Err(DeviceError::PortGone(_))
+        ));
+        assert!(matches!(
+            get_rssi_from_device(&mut transport, None),
+            Err(DeviceError::PortGone(_))
+        ));
+    }
+
+    #[test]
+    fn test_errors_propagate_from_commands() {
+        // a module that never answers must not hang the config read
+        let mut transport = MemoryTransport::new();
+        assert!(matches!(
+            get_device_config_from_device(&mut transport, None, None),
+            Err(DeviceError::Timeout(_))
+        ));
+
+        // a RSSI response must be exactly one byte
+        transport.push_rx(&[0x10, 0x20, b'>']);
+        assert_eq!(
+            get_rssi_from_device(&mut transport, None),
+            Err(DeviceError::UnexpectedResponse(vec![0x10, 0x20]))
+        );
+    }
+}
@@ -7,6 +7,33 @@ type MkDeviceConfig = {
   cells: MkDeviceCell[];
   test_modes: MkDeviceTestMode[];
   quick_modes: MkDeviceQuickMode[];
+  band_plan: MkBandPlan | null;
+  rmd_file_path: string | null;
+  rmd_warning: string | null;
+  typed_values: MkTypedValue[];
+  inactive_cells: number[];
+};
+
+type MkTypedValue = {
+  address: number;
+  name: string;
+  size: number;
+  value: number;
+  bits: MkBitsValue[];
+};
+
+type MkBitsValue = {
+  name: string;
+  first_bit: number;
+  last_bit: number;
+  value: number;
+};
+
+type MkBandPlan = {
+  first_center_mhz: number;
+  channel_width_khz: number;
+  first_channel: number;
+  last_channel: number;
 };
 
 type MkDeviceCalib = {
@@ -23,9 +50,83 @@ type MkDeviceCell = {
   allowed_values: number[];
   default_value: number;
   current_value: number;
+  frequency_mhz?: number | null;
+  multi_byte?: MkMultiByte | null;
+  bits?: MkCellBits[];
+  enum_values?: MkCellEnumValue[];
+  scaling?: MkCellScaling | null;
+  active_if?: MkCellCondition[];
+  rules?: MkCellRule[];
   editable: boolean;
 };
 
+type MkMultiByte = {
+  size: number;
+  endianness: "Big" | "Little";
+};
+
+type MkCellBits = {
+  name: string;
+  first_bit: number;
+  last_bit: number;
+};
+
+type MkCellEnumValue = {
+  value: number;
+  label: string;
+  unit: string | null;
+};
+
+type MkCellScaling = {
+  scale: number;
+  offset: number;
+  unit: string | null;
+};
+
+type MkCellCondition = {
+  address: number;
+  operator:
+    | "Equal"
+    | "NotEqual"
+    | "Less"
+    | "LessOrEqual"
+    | "Greater"
+    | "GreaterOrEqual";
+  value: number;
+};
+
+type MkCellRule = {
+  conditions: MkCellCondition[];
+  when: MkCellCondition[];
+};
+
+type MkCellRulesReport = {
+  inactive_cells: number[];
+  violations: { kind: string; address: number; name?: string; rule?: string }[];
+};
+
+type MkConfigSource =
+  | { kind: "Device" }
+  | { kind: "Profile"; file_path: string }
+  | { kind: "Defaults" };
+
+type MkCellDiff = {
+  address: number;
+  name: string;
+  size: number;
+  old_value: number | null;
+  new_value: number | null;
+  old_engineering_value: string | null;
+  new_engineering_value: string | null;
+  locked: boolean;
+};
+
+type MkConfigDiff = {
+  old_name: string;
+  new_name: string;
+  cells: MkCellDiff[];
+};
+
 type MkDeviceTestMode = {
   testmode_id: number;
   name: string;
@@ -47,5 +148,18 @@ export type {
   MkDeviceCell,
   MkDeviceTestMode,
   MkDeviceQuickMode,
-  MkDeviceCalib
+  MkDeviceCalib,
+  MkBandPlan,
+  MkTypedValue,
+  MkBitsValue,
+  MkMultiByte,
+  MkCellBits,
+  MkCellEnumValue,
+  MkCellScaling,
+  MkCellCondition,
+  MkCellRule,
+  MkCellRulesReport,
+  MkConfigSource,
+  MkCellDiff,
+  MkConfigDiff
 };
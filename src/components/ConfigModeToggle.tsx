@@ -35,11 +35,7 @@ const ConfigModeToggle: React.FC<ConfigModeToggleParams> = ({
           await invoke("stop_communication_task", {});
           setCurrentMode("waitingForConfigMode");
           setToggleStatusText(`Waiting for Device... ${retries - tryCount}`);
-          let readResult: number[] = await invoke("read_bytes", {});
-          let success = false;
-          if (readResult && readResult.length === 1 && readResult[0] === 0x3e) {
-            success = true;
-          }
+          let success: boolean = await invoke("enter_config_mode", {});
           if (success) {
             clearInterval(countdownInterval);
             countdownInterval = null;
@@ -51,7 +47,7 @@ const ConfigModeToggle: React.FC<ConfigModeToggleParams> = ({
           }
         } else {
           clearInterval(countdownInterval);
-          await invoke("send_bytes", { input: "X" });
+          await invoke("exit_config_mode", {});
           await invoke("start_communication_task", {});
           setCurrentMode("communication");
           setToggleStatusText("Communication Mode");
@@ -63,7 +59,7 @@ const ConfigModeToggle: React.FC<ConfigModeToggleParams> = ({
         clearInterval(countdownInterval);
         countdownInterval = null;
       }
-      await invoke("send_bytes", { input: "X" });
+      await invoke("exit_config_mode", {});
       await invoke("start_communication_task", {});
       setCurrentMode("communication");
       setToggleStatusText("Communication Mode");
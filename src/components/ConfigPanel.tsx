@@ -14,19 +14,29 @@ import React, {
   createContext,
 } from "react";
 import { invoke } from "@tauri-apps/api";
+import { listen } from "@tauri-apps/api/event";
 import { ask, message } from "@tauri-apps/api/dialog";
 import TestModeSelect from "./TestModeSelect";
 import { Tooltip } from "flowbite-react";
 import { getDeviceConfig, setDeviceConfig } from "../utils/device_info_util";
-import { error } from "tauri-plugin-log-api";
+import { error, warn } from "tauri-plugin-log-api";
 
 import {
+  MkDeviceConfig,
   MkDeviceCell,
   MkDeviceTestMode,
   MkDeviceQuickMode,
+  MkCellEnumValue,
+  MkCellScaling,
 } from "../DataTypes";
 import { ConnectionContext } from "../App";
 
+type RmdChangedEvent = {
+  file_path: string;
+  diagnostics: { severity: string; line: number | null; message: string }[];
+  device_config: MkDeviceConfig | null;
+};
+
 const ConfigTableContext = createContext({
   errorList: [] as number[],
   setErrorList: (_: number[]) => {},
@@ -65,23 +75,46 @@ const ConfigPanel: React.FC = () => {
     await invoke("start_communication_task", {});
   };
 
+  const showConfig = (result: MkDeviceConfig) => {
+    setData(result.cells.filter((item, _) => result.editable_cells.includes(item.address))
+    .map((item, _) => ({
+        ...item,
+        editable: !result.locked_cells.includes(item.address)
+    })));
+    setTestModeOptions(result.test_modes);
+    setQuickModeOptions(result.quick_modes);
+    setModel(result.model);
+    setFirmware(result.firmware_version);
+    setHardware(result.hw_version);
+    setErrorList([]);
+    setEditable(result.editable_cells);
+    setLocked(result.locked_cells);
+  };
+
+  // the backend re-maps the config when the RMD file it was read with changes
+  useEffect(() => {
+    const unlisten = listen<RmdChangedEvent>("rmd_changed_event", (event) => {
+      event.payload.diagnostics.forEach((diagnostic) =>
+        warn(`${event.payload.file_path} line ${diagnostic.line}: ${diagnostic.message}`)
+      );
+      if (event.payload.device_config) {
+        showConfig(event.payload.device_config);
+      }
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
   const readConfig = () => {
     setData([]);
     return getDeviceConfig()
       .then((result) => {
-        setData(result.cells.filter((item, _) => result.editable_cells.includes(item.address))
-        .map((item, _) => ({
-            ...item,
-            editable: !result.locked_cells.includes(item.address)
-        })));
-        setTestModeOptions(result.test_modes);
-        setQuickModeOptions(result.quick_modes);
-        setModel(result.model);
-        setFirmware(result.firmware_version);
-        setHardware(result.hw_version);
-        setErrorList([]);
-        setEditable(result.editable_cells);
-        setLocked(result.locked_cells);
+        showConfig(result);
+        if (result.rmd_warning) {
+          warn(result.rmd_warning);
+          message(result.rmd_warning, { title: "Tauri", type: "warning" });
+        }
       })
       .catch((err) => {
         alert("No matching RMD file available");
@@ -183,12 +216,14 @@ const ConfigPanel: React.FC = () => {
           row.max_value,
           row.allowed_values,
           row.address,
-          row.editable
+          row.editable,
+          row.enum_values ?? [],
+          row.scaling ?? null
         ],
         id: "current_value",
         cell: ({ getValue, column: { id }, table }) => {
-          const [initialValue, minValue, maxValue, allowedValues, address, editable] =
-            getValue() as [number, number, number, number[], number, boolean];
+          const [initialValue, minValue, maxValue, allowedValues, address, editable, enumValues, scaling] =
+            getValue() as [number, number, number, number[], number, boolean, MkCellEnumValue[], MkCellScaling | null];
 
           const [value, setValue] = useState(initialValue.toString());
           const { errorList, setErrorList } = useContext(ConfigTableContext);
@@ -216,10 +251,22 @@ const ConfigPanel: React.FC = () => {
             setValue(initialValue.toString());
           }, [initialValue]);
 
+          const enumValue = enumValues.find((e) => e.value === parseInt(value));
+          const withUnit = (label: string, unit: string | null) =>
+            unit ? `${label} ${unit}` : label;
+          let valueTitle: string | undefined = undefined;
+          if (enumValue) {
+            valueTitle = withUnit(enumValue.label, enumValue.unit);
+          } else if (scaling && !isNaN(parseInt(value))) {
+            const engineeringValue = parseInt(value) * scaling.scale + scaling.offset;
+            valueTitle = withUnit(`${+engineeringValue.toFixed(6)}`, scaling.unit);
+          }
+
           return (
             <>
               <input
                 value={value}
+                title={valueTitle}
                 onChange={handleOnChange}
                 onBlur={onBlur}
                 disabled={!editable}
@@ -6,8 +6,11 @@ import { listen } from "@tauri-apps/api/event";
 import { RssiStreamContext } from "./DeviceInfo";
 
 type RSSIEvent = {
-  rssi: number;
+  rssi: number | null;
+  raw: number;
+  saturated: boolean;
   channel: number;
+  frequency_mhz: number | null;
 };
 
 const RSSIChart: React.FC = () => {
@@ -40,7 +43,13 @@ const RSSIChart: React.FC = () => {
         let index = prevData.findIndex(
           (row) => row[0] === event.payload.channel
         );
-        let rssi = event.payload.rssi > -100 ? event.payload.rssi : -100;
+        // saturated readings have no dBm value, show them as a full bar
+        let rssi =
+          event.payload.rssi === null
+            ? 0
+            : event.payload.rssi > -100
+            ? event.payload.rssi
+            : -100;
 
         if (index === -1) {
           return prevData.concat([[event.payload.channel, rssi, -100 - rssi]]);